@@ -0,0 +1,487 @@
+//! # Budget Module
+//!
+//! Keeps generated output inside a model's context window by choosing which files to include
+//! when the selection would exceed a token and/or byte limit.
+//!
+//! # Selection Rules
+//! - Candidates are ranked by priority globs first, then by [`BudgetRank`] (most recently
+//!   modified or smallest first), then by relative path.
+//! - Files are taken in rank order while they fit; a file that does not fit is dropped (or, with
+//!   `truncate`, shortened to whole lines followed by a `[truncated N lines]` marker) and the
+//!   next candidate is tried, so smaller files can still fill the remaining space.
+//! - Kept files are written in the normal output order; ranking only decides what is kept.
+//!
+//! # Notes
+//! - Costs are measured on each file as rendered by the selected format, plus the document's
+//!   fixed header, footer, and additional commands, so the limits apply to the whole output.
+//! - Token costs use the same tokenizer as the summary; per-file counts are summed, so the final
+//!   total can differ from the budget by a few tokens at file boundaries.
+
+use crate::file_ops::LoadedFile;
+use crate::formats::{RenderContext, RenderedFile};
+use crate::metadata::FileMetadata;
+use crate::patterns::PriorityPatterns;
+use crate::tokens::Tokenizer;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Secondary ranking used to decide which files are kept when a budget is set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum BudgetRank {
+    /// Most recently modified files are kept first.
+    #[default]
+    Recent,
+    /// Smallest files are kept first, fitting as many files as possible.
+    Smallest,
+}
+
+/// Token and byte limits for a generated document.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputBudget {
+    /// Maximum tokens in the whole output, as counted by the selected tokenizer.
+    #[serde(default)]
+    pub max_tokens: Option<usize>,
+    /// Maximum bytes in the whole output.
+    #[serde(default)]
+    pub max_bytes: Option<usize>,
+    /// Whether files that do not fit are truncated instead of dropped.
+    #[serde(default)]
+    pub truncate: bool,
+    /// Ranking applied after priority globs.
+    #[serde(default)]
+    pub rank: BudgetRank,
+}
+
+impl OutputBudget {
+    /// Returns `true` when neither limit is set, so every file is kept.
+    pub fn is_unlimited(&self) -> bool {
+        self.max_tokens.is_none() && self.max_bytes.is_none()
+    }
+
+    /// Returns `true` when no field has been customized.
+    pub fn is_default(&self) -> bool {
+        self == &OutputBudget::default()
+    }
+}
+
+/// A file left out of the output because it did not fit the budget.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DroppedFile {
+    /// Path as it would have been written to the output.
+    pub path: String,
+    /// File on disk the entry was read from.
+    pub source: PathBuf,
+    pub tokens: usize,
+    /// Length of the decoded contents counted against the budget.
+    pub bytes: usize,
+}
+
+/// A file shortened to fit the budget.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TruncatedFile {
+    /// Path as written to the output.
+    pub path: String,
+    /// Number of trailing lines replaced by the truncation marker.
+    pub lines_removed: usize,
+}
+
+/// Files kept, truncated, and dropped by [`apply_budget`].
+#[derive(Debug, Default)]
+pub struct BudgetOutcome {
+    /// Files to write, in their original output order (truncated contents already applied).
+    pub kept: Vec<LoadedFile>,
+    pub truncated: Vec<TruncatedFile>,
+    pub dropped: Vec<DroppedFile>,
+}
+
+/// What happens to one candidate file.
+#[derive(Debug, Clone)]
+enum Decision {
+    Drop,
+    Keep,
+    Truncate(String),
+}
+
+/// Size of rendered output in tokens and bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Cost {
+    pub tokens: usize,
+    pub bytes: usize,
+}
+
+impl Cost {
+    /// Turns optional limits into a cost, treating an unset limit as unbounded.
+    pub fn limit(max_tokens: Option<usize>, max_bytes: Option<usize>) -> Cost {
+        Cost {
+            tokens: max_tokens.unwrap_or(usize::MAX),
+            bytes: max_bytes.unwrap_or(usize::MAX),
+        }
+    }
+
+    /// Returns `true` when neither the tokens nor the bytes exceed `limit`.
+    pub fn fits_within(self, limit: Cost) -> bool {
+        self.tokens <= limit.tokens && self.bytes <= limit.bytes
+    }
+
+    /// Subtracts `other` from both measures, stopping at zero.
+    pub fn saturating_sub(self, other: Cost) -> Cost {
+        Cost {
+            tokens: self.tokens.saturating_sub(other.tokens),
+            bytes: self.bytes.saturating_sub(other.bytes),
+        }
+    }
+}
+
+/// Chooses which of `files` (in output order) fit `budget` and truncates or drops the rest.
+///
+/// # Parameters
+/// - `root_dirs`: Scanned roots, used to match priority globs against each file's path relative
+///   to the first root that contains it.
+/// - `context`: Render context whose format determines how much each file costs.
+/// - `priority`: Priority globs; earlier patterns are kept first.
+/// - `tokenizer`: Counts the tokens of rendered files.
+///
+/// # Errors
+/// Returns an error only if the renderer fails while measuring output.
+pub fn apply_budget(
+    root_dirs: &[&Path],
+    files: Vec<LoadedFile>,
+    budget: &OutputBudget,
+    context: &RenderContext<'_>,
+    priority: &PriorityPatterns,
+    tokenizer: &dyn Tokenizer,
+) -> std::io::Result<BudgetOutcome> {
+    if budget.is_unlimited() {
+        return Ok(BudgetOutcome {
+            kept: files,
+            ..BudgetOutcome::default()
+        });
+    }
+
+    let fixed = measure_document(context, tokenizer)?;
+    let mut remaining = Cost::limit(budget.max_tokens, budget.max_bytes).saturating_sub(fixed);
+
+    let mut ranked: Vec<usize> = (0..files.len()).collect();
+    ranked.sort_by_cached_key(|&index| {
+        let file = &files[index];
+        let relative_path = root_dirs
+            .iter()
+            .find_map(|root_dir| file.path.strip_prefix(root_dir).ok())
+            .unwrap_or(&file.path);
+        let size = match budget.rank {
+            BudgetRank::Smallest => file.contents.len(),
+            BudgetRank::Recent => 0,
+        };
+        let modified = match budget.rank {
+            BudgetRank::Recent => modified_nanos(&file.path),
+            BudgetRank::Smallest => 0,
+        };
+        (
+            priority.rank(relative_path),
+            size,
+            Reverse(modified),
+            relative_path.to_path_buf(),
+        )
+    });
+
+    let mut decisions = vec![Decision::Drop; files.len()];
+    let mut outcome = BudgetOutcome::default();
+    for index in ranked {
+        let file = &files[index];
+        let cost = measure_file(
+            context,
+            file.display_path.as_str(),
+            &file.metadata,
+            &file.contents,
+            tokenizer,
+        )?;
+        if cost.fits_within(remaining) {
+            remaining = remaining.saturating_sub(cost);
+            decisions[index] = Decision::Keep;
+            continue;
+        }
+
+        if budget.truncate {
+            if let Some((contents, lines_removed, cost)) =
+                truncate_to_fit(context, file, remaining, tokenizer)?
+            {
+                remaining = remaining.saturating_sub(cost);
+                decisions[index] = Decision::Truncate(contents);
+                outcome.truncated.push(TruncatedFile {
+                    path: file.display_path.clone(),
+                    lines_removed,
+                });
+                continue;
+            }
+        }
+
+        outcome.dropped.push(DroppedFile {
+            path: file.display_path.clone(),
+            source: file.path.clone(),
+            tokens: file.tokens,
+            bytes: file.contents.len(),
+        });
+    }
+
+    for (mut file, decision) in files.into_iter().zip(decisions) {
+        match decision {
+            Decision::Keep => outcome.kept.push(file),
+            Decision::Truncate(contents) => {
+                file.tokens = tokenizer.count_tokens(&contents);
+                file.contents = contents;
+                outcome.kept.push(file);
+            }
+            Decision::Drop => {}
+        }
+    }
+
+    Ok(outcome)
+}
+
+/// Returns the marker line that replaces `lines_removed` trailing lines.
+fn truncation_marker(lines_removed: usize) -> String {
+    format!("[truncated {lines_removed} lines]\n")
+}
+
+/// Keeps the longest prefix of whole lines (at least one) that fits `remaining` with the marker.
+fn truncate_to_fit(
+    context: &RenderContext<'_>,
+    file: &LoadedFile,
+    remaining: Cost,
+    tokenizer: &dyn Tokenizer,
+) -> std::io::Result<Option<(String, usize, Cost)>> {
+    let lines: Vec<&str> = file.contents.split_inclusive('\n').collect();
+    let truncated = |kept: usize| {
+        let mut contents: String = lines[..kept].concat();
+        if !contents.ends_with('\n') {
+            contents.push('\n');
+        }
+        contents.push_str(&truncation_marker(lines.len() - kept));
+        contents
+    };
+
+    let mut best = None;
+    let (mut low, mut high) = (1, lines.len().saturating_sub(1));
+    while low <= high {
+        let middle = low + (high - low) / 2;
+        let contents = truncated(middle);
+        let cost = measure_file(
+            context,
+            &file.display_path,
+            &file.metadata,
+            &contents,
+            tokenizer,
+        )?;
+        if cost.fits_within(remaining) {
+            best = Some((contents, lines.len() - middle, cost));
+            low = middle + 1;
+        } else {
+            high = middle - 1;
+        }
+    }
+
+    Ok(best)
+}
+
+fn measure(text: &[u8], tokenizer: &dyn Tokenizer) -> Cost {
+    Cost {
+        tokens: tokenizer.count_tokens(&String::from_utf8_lossy(text)),
+        bytes: text.len(),
+    }
+}
+
+/// Measures the document a renderer produces without any files.
+pub fn measure_document(
+    context: &RenderContext<'_>,
+    tokenizer: &dyn Tokenizer,
+) -> std::io::Result<Cost> {
+    let mut renderer = context.options.renderer();
+    let mut output = Vec::new();
+    renderer.begin(&mut output, context)?;
+    renderer.finish(&mut output, context)?;
+    Ok(measure(&output, tokenizer))
+}
+
+/// Measures one file as the selected format renders it.
+///
+/// Renderers that buffer files until the end (templates) write nothing per file, so the raw
+/// contents are measured instead.
+pub fn measure_file(
+    context: &RenderContext<'_>,
+    path: &str,
+    metadata: &FileMetadata,
+    contents: &str,
+    tokenizer: &dyn Tokenizer,
+) -> std::io::Result<Cost> {
+    let mut renderer = context.options.renderer();
+    renderer.begin(&mut std::io::sink(), context)?;
+    let mut output = Vec::new();
+    renderer.write_file(
+        &mut output,
+        &RenderedFile {
+            path,
+            contents,
+            tokens: 0,
+            metadata,
+        },
+    )?;
+
+    if output.is_empty() {
+        Ok(measure(contents.as_bytes(), tokenizer))
+    } else {
+        Ok(measure(&output, tokenizer))
+    }
+}
+
+fn modified_nanos(path: &Path) -> u128 {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_nanos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::RenderOptions;
+    use crate::tokens::HeuristicTokenizer;
+    use std::path::PathBuf;
+
+    fn file(path: &str, contents: &str) -> LoadedFile {
+        LoadedFile {
+            path: PathBuf::from("project").join(path),
+            display_path: path.to_string(),
+            metadata: FileMetadata::default(),
+            contents: contents.to_string(),
+            tokens: HeuristicTokenizer.count_tokens(contents),
+        }
+    }
+
+    fn run(
+        files: Vec<LoadedFile>,
+        budget: OutputBudget,
+        priority: &[String],
+    ) -> std::io::Result<BudgetOutcome> {
+        let options = RenderOptions::default();
+        let context = RenderContext {
+            root_dir: Path::new("project"),
+            options: &options,
+            part: None,
+        };
+        let priority = PriorityPatterns::compile(priority).expect("patterns compile");
+        apply_budget(
+            &[Path::new("project")],
+            files,
+            &budget,
+            &context,
+            &priority,
+            &HeuristicTokenizer,
+        )
+    }
+
+    fn kept_paths(outcome: &BudgetOutcome) -> Vec<&str> {
+        outcome
+            .kept
+            .iter()
+            .map(|file| file.display_path.as_str())
+            .collect()
+    }
+
+    fn fixed_bytes() -> usize {
+        let options = RenderOptions::default();
+        let context = RenderContext {
+            root_dir: Path::new("project"),
+            options: &options,
+            part: None,
+        };
+        measure_document(&context, &HeuristicTokenizer)
+            .expect("measure document")
+            .bytes
+    }
+
+    #[test]
+    fn unlimited_budget_keeps_everything() -> std::io::Result<()> {
+        let outcome = run(
+            vec![file("a.rs", "a\n"), file("b.rs", "b\n")],
+            OutputBudget::default(),
+            &[],
+        )?;
+
+        assert_eq!(kept_paths(&outcome), vec!["a.rs", "b.rs"]);
+        assert!(outcome.dropped.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn smallest_rank_keeps_small_files_in_output_order() -> std::io::Result<()> {
+        // Each small file renders as "<a.rs>\na\n\n</a.rs>\n\n" (19 bytes).
+        let budget = OutputBudget {
+            max_bytes: Some(fixed_bytes() + 38),
+            rank: BudgetRank::Smallest,
+            ..OutputBudget::default()
+        };
+        let files = vec![
+            file("big.rs", &"x".repeat(100)),
+            file("b.rs", "b\n"),
+            file("a.rs", "a\n"),
+        ];
+
+        let outcome = run(files, budget, &[])?;
+
+        assert_eq!(kept_paths(&outcome), vec!["b.rs", "a.rs"]);
+        assert_eq!(outcome.dropped.len(), 1);
+        assert_eq!(outcome.dropped[0].path, "big.rs");
+        assert_eq!(outcome.dropped[0].bytes, 100);
+        Ok(())
+    }
+
+    #[test]
+    fn priority_globs_win_over_rank() -> std::io::Result<()> {
+        // main.rs renders as 36 bytes, leaving too little room for a.rs (19 bytes).
+        let budget = OutputBudget {
+            max_bytes: Some(fixed_bytes() + 40),
+            rank: BudgetRank::Smallest,
+            ..OutputBudget::default()
+        };
+        let files = vec![file("a.rs", "a\n"), file("main.rs", "fn main() {}\n")];
+
+        let outcome = run(files, budget, &["main.rs".to_string()])?;
+
+        assert_eq!(kept_paths(&outcome), vec!["main.rs"]);
+        assert_eq!(outcome.dropped[0].path, "a.rs");
+        Ok(())
+    }
+
+    #[test]
+    fn truncation_keeps_whole_lines_and_adds_marker() -> std::io::Result<()> {
+        // 23 bytes of tags, 21 for "[truncated 17 lines]\n", and room for three 5-byte lines.
+        let budget = OutputBudget {
+            max_bytes: Some(fixed_bytes() + 60),
+            truncate: true,
+            ..OutputBudget::default()
+        };
+        let files = vec![file("long.rs", &"line\n".repeat(20))];
+
+        let outcome = run(files, budget, &[])?;
+
+        assert!(outcome.dropped.is_empty());
+        assert_eq!(
+            outcome.truncated,
+            vec![TruncatedFile {
+                path: "long.rs".to_string(),
+                lines_removed: 17,
+            }]
+        );
+        assert_eq!(
+            outcome.kept[0].contents,
+            format!("{}[truncated 17 lines]\n", "line\n".repeat(3))
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,387 @@
+//! # Chunks Module
+//!
+//! Splits generated output into numbered parts (`tags_output.part1.txt`, `tags_output.part2.txt`,
+//! …) for chat interfaces that cap the size of a single message.
+//!
+//! # Splitting Rules
+//! - Files are packed into parts in output order; a file only moves to the next part when it does
+//!   not fit in the current one, so files are never split across parts unnecessarily.
+//! - A file that exceeds the limit on its own is split at line boundaries. Each piece keeps the
+//!   file's path and is marked with `[continued in next part]` / `[continued from previous part]`.
+//! - Every part is a complete document in the selected format, headed with "Part i of n". Only
+//!   the first part carries the directory tree, and only the final part carries the footer
+//!   instructions, presets, and additional commands.
+//!
+//! # Notes
+//! - Per-part overhead is measured as if every part were the final one, so parts stay within the
+//!   limit even though earlier parts omit the footer and commands.
+//! - A single line longer than the limit cannot be split further and produces an oversized part.
+//! - When everything fits in one part, the normal output path is written without a part header.
+
+use crate::budget::{measure_document, measure_file, Cost};
+use crate::file_ops::LoadedFile;
+use crate::formats::{OutputPart, RenderContext, RenderOptions};
+use crate::output_text::OutputText;
+use crate::tokens::Tokenizer;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Marker line appended to every piece of a split file except the last.
+const CONTINUED_IN_NEXT_PART: &str = "[continued in next part]\n";
+/// Marker line prepended to every piece of a split file except the first.
+const CONTINUED_FROM_PREVIOUS_PART: &str = "[continued from previous part]\n";
+
+/// Maximum size of each output part.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkLimit {
+    /// Maximum tokens per part, as counted by the selected tokenizer.
+    #[serde(default)]
+    pub max_tokens: Option<usize>,
+    /// Maximum bytes per part.
+    #[serde(default)]
+    pub max_bytes: Option<usize>,
+}
+
+impl ChunkLimit {
+    /// Returns `true` when neither limit is set, so the output is written as one file.
+    pub fn is_unlimited(&self) -> bool {
+        self.max_tokens.is_none() && self.max_bytes.is_none()
+    }
+}
+
+/// Returns the path of part `index` (1-based): `tags_output.txt` becomes `tags_output.part2.txt`.
+pub fn part_path(output_path: &Path, index: usize) -> PathBuf {
+    let stem = output_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let file_name = match output_path.extension() {
+        Some(extension) => format!("{stem}.part{index}.{}", extension.to_string_lossy()),
+        None => format!("{stem}.part{index}"),
+    };
+    output_path.with_file_name(file_name)
+}
+
+/// Returns the render options for one part: only the first part keeps the directory tree, and
+/// only the final part keeps the diff, footer, and commands.
+pub fn part_options(render: &RenderOptions, part: OutputPart) -> RenderOptions {
+    let mut options = render.clone();
+    if part.index > 1 {
+        options.tree = None;
+    }
+    if part.index < part.count {
+        options.text = OutputText {
+            footer: Some(String::new()),
+            ..render.text.clone()
+        };
+        options.diff = None;
+        options.preset_texts = Vec::new();
+        options.additional_commands = String::new();
+    }
+    options
+}
+
+/// Packs `files` (in output order) into parts that each fit `limit`.
+///
+/// Returns a single part holding every file when `limit` is unlimited or everything fits.
+///
+/// # Errors
+/// Returns an error only if the renderer fails while measuring output.
+pub fn plan_chunks(
+    root_dir: &Path,
+    files: Vec<LoadedFile>,
+    limit: &ChunkLimit,
+    render: &RenderOptions,
+    tokenizer: &dyn Tokenizer,
+) -> std::io::Result<Vec<Vec<LoadedFile>>> {
+    if limit.is_unlimited() {
+        return Ok(vec![files]);
+    }
+
+    // Measure with the widest possible part numbers and the final part's footer and commands.
+    let widest = files.len().max(1);
+    let context = RenderContext {
+        root_dir,
+        options: render,
+        part: Some(OutputPart {
+            index: widest,
+            count: widest,
+        }),
+    };
+    let available = Cost::limit(limit.max_tokens, limit.max_bytes)
+        .saturating_sub(measure_document(&context, tokenizer)?);
+
+    let mut parts: Vec<Vec<LoadedFile>> = Vec::new();
+    let mut current = Vec::new();
+    let mut remaining = available;
+    for file in files {
+        let cost = measure_file(
+            &context,
+            &file.display_path,
+            &file.metadata,
+            &file.contents,
+            tokenizer,
+        )?;
+        if cost.fits_within(remaining) {
+            remaining = remaining.saturating_sub(cost);
+            current.push(file);
+            continue;
+        }
+
+        if cost.fits_within(available) {
+            parts.push(std::mem::take(&mut current));
+            remaining = available.saturating_sub(cost);
+            current.push(file);
+            continue;
+        }
+
+        // Too large for any part: fill the current part, then continue in fresh parts.
+        let mut rest = file.contents.as_str();
+        let mut first_piece = true;
+        while !rest.is_empty() {
+            let (piece, cost) =
+                split_piece(&context, &file, rest, first_piece, remaining, tokenizer)?;
+            if piece.is_empty() && !current.is_empty() {
+                parts.push(std::mem::take(&mut current));
+                remaining = available;
+                continue;
+            }
+
+            let length = piece.len().max(first_line_length(rest));
+            let is_last_piece = length >= rest.len();
+            let contents = piece_contents(&rest[..length], first_piece, is_last_piece);
+            let cost = if piece.is_empty() {
+                // A single line larger than a whole part is forced in; measure it so nothing
+                // else is packed into the part it overfills.
+                measure_file(
+                    &context,
+                    &file.display_path,
+                    &file.metadata,
+                    &contents,
+                    tokenizer,
+                )?
+            } else {
+                cost
+            };
+            remaining = remaining.saturating_sub(cost);
+            current.push(LoadedFile {
+                path: file.path.clone(),
+                display_path: file.display_path.clone(),
+                metadata: file.metadata.clone(),
+                tokens: tokenizer.count_tokens(&contents),
+                contents,
+            });
+            rest = &rest[length..];
+            first_piece = false;
+            if !is_last_piece {
+                parts.push(std::mem::take(&mut current));
+                remaining = available;
+            }
+        }
+    }
+
+    if !current.is_empty() || parts.is_empty() {
+        parts.push(current);
+    }
+
+    Ok(parts)
+}
+
+/// Finds the longest prefix of whole lines of `rest` that fits `remaining` with its markers.
+///
+/// Returns an empty prefix when not even one line fits.
+fn split_piece<'a>(
+    context: &RenderContext<'_>,
+    file: &LoadedFile,
+    rest: &'a str,
+    first_piece: bool,
+    remaining: Cost,
+    tokenizer: &dyn Tokenizer,
+) -> std::io::Result<(&'a str, Cost)> {
+    let line_ends: Vec<usize> = rest
+        .split_inclusive('\n')
+        .scan(0, |end, line| {
+            *end += line.len();
+            Some(*end)
+        })
+        .collect();
+
+    let mut best = ("", Cost::default());
+    let (mut low, mut high) = (1, line_ends.len());
+    while low <= high {
+        let middle = low + (high - low) / 2;
+        let end = line_ends[middle - 1];
+        let contents = piece_contents(&rest[..end], first_piece, end == rest.len());
+        let cost = measure_file(
+            context,
+            &file.display_path,
+            &file.metadata,
+            &contents,
+            tokenizer,
+        )?;
+        if cost.fits_within(remaining) {
+            best = (&rest[..end], cost);
+            low = middle + 1;
+        } else {
+            high = middle - 1;
+        }
+    }
+
+    Ok(best)
+}
+
+/// Adds the continuation markers for one piece of a split file.
+fn piece_contents(piece: &str, first_piece: bool, last_piece: bool) -> String {
+    let mut contents = String::new();
+    if !first_piece {
+        contents.push_str(CONTINUED_FROM_PREVIOUS_PART);
+    }
+    contents.push_str(piece);
+    if !last_piece {
+        if !contents.ends_with('\n') {
+            contents.push('\n');
+        }
+        contents.push_str(CONTINUED_IN_NEXT_PART);
+    }
+    contents
+}
+
+fn first_line_length(text: &str) -> usize {
+    text.find('\n').map_or(text.len(), |index| index + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::FileMetadata;
+    use crate::tokens::HeuristicTokenizer;
+
+    fn file(path: &str, contents: &str) -> LoadedFile {
+        LoadedFile {
+            path: PathBuf::from("project").join(path),
+            display_path: path.to_string(),
+            metadata: FileMetadata::default(),
+            contents: contents.to_string(),
+            tokens: HeuristicTokenizer.count_tokens(contents),
+        }
+    }
+
+    fn overhead_bytes(render: &RenderOptions, files: usize) -> usize {
+        let context = RenderContext {
+            root_dir: Path::new("project"),
+            options: render,
+            part: Some(OutputPart {
+                index: files,
+                count: files,
+            }),
+        };
+        measure_document(&context, &HeuristicTokenizer)
+            .expect("measure document")
+            .bytes
+    }
+
+    fn plan(files: Vec<LoadedFile>, max_bytes: usize) -> Vec<Vec<String>> {
+        let render = RenderOptions::default();
+        let limit = ChunkLimit {
+            max_tokens: None,
+            max_bytes: Some(overhead_bytes(&render, files.len()) + max_bytes),
+        };
+        plan_chunks(
+            Path::new("project"),
+            files,
+            &limit,
+            &render,
+            &HeuristicTokenizer,
+        )
+        .expect("plan chunks")
+        .into_iter()
+        .map(|part| part.into_iter().map(|file| file.contents).collect())
+        .collect()
+    }
+
+    #[test]
+    fn part_paths_number_before_the_extension() {
+        assert_eq!(
+            part_path(Path::new("out/tags_output.txt"), 2),
+            Path::new("out/tags_output.part2.txt")
+        );
+        assert_eq!(
+            part_path(Path::new("context"), 1),
+            Path::new("context.part1")
+        );
+    }
+
+    #[test]
+    fn files_move_to_the_next_part_instead_of_splitting() {
+        // "<a.rs>\naaaa\n\n</a.rs>\n\n" is 22 bytes; two fit in 50, three do not.
+        let files = vec![
+            file("a.rs", "aaaa\n"),
+            file("b.rs", "bbbb\n"),
+            file("c.rs", "cccc\n"),
+        ];
+
+        let parts = plan(files, 50);
+
+        assert_eq!(parts, vec![vec!["aaaa\n", "bbbb\n"], vec!["cccc\n"]]);
+    }
+
+    #[test]
+    fn oversized_file_is_split_at_lines_with_markers() {
+        let files = vec![file("big.rs", &"line\n".repeat(12))];
+
+        let parts = plan(files, 70);
+
+        assert!(parts.len() > 1);
+        assert!(parts[0][0].ends_with(CONTINUED_IN_NEXT_PART));
+        assert!(parts[1][0].starts_with(CONTINUED_FROM_PREVIOUS_PART));
+        let rejoined: String = parts
+            .iter()
+            .flatten()
+            .map(|piece| {
+                piece
+                    .replace(CONTINUED_IN_NEXT_PART, "")
+                    .replace(CONTINUED_FROM_PREVIOUS_PART, "")
+            })
+            .collect();
+        assert_eq!(rejoined, "line\n".repeat(12));
+    }
+
+    #[test]
+    fn file_after_an_oversized_line_starts_a_new_part() {
+        let files = vec![file("long.rs", &"x".repeat(100)), file("b.rs", "b\n")];
+
+        let parts = plan(files, 50);
+
+        assert_eq!(parts, vec![vec!["x".repeat(100)], vec!["b\n".to_string()]]);
+    }
+
+    #[test]
+    fn earlier_parts_omit_footer_and_commands_and_later_parts_omit_tree() {
+        let render = RenderOptions {
+            additional_commands: "Add tests".to_string(),
+            preset_texts: vec!["Review".to_string()],
+            tree: Some("project/\n".to_string()),
+            diff: Some("diff --git a/main.rs b/main.rs\n".to_string()),
+            ..RenderOptions::default()
+        };
+        let part = |index| OutputPart { index, count: 3 };
+
+        let first = part_options(&render, part(1));
+        let middle = part_options(&render, part(2));
+        let last = part_options(&render, part(3));
+
+        assert_eq!(first.tree, render.tree);
+        assert_eq!(first.text.footer.as_deref(), Some(""));
+        assert!(first.combined_additional_commands().trim().is_empty());
+        assert_eq!(first.diff, None);
+        assert_eq!(middle.tree, None);
+        assert_eq!(middle.text.footer.as_deref(), Some(""));
+        assert_eq!(
+            last,
+            RenderOptions {
+                tree: None,
+                ..render.clone()
+            }
+        );
+    }
+}
@@ -7,11 +7,14 @@
 //! # Defaults and Overrides
 //! - `run` defaults `--output` to `tags_output.txt`.
 //! - Supplying `--output <path>` overrides the default output file.
-//! - Callers must provide either `--file-type <group>` or one or more `--ext <extension>` values.
+//! - Callers must provide `--file-type <group>`, one or more `--ext <extension>` values, or one or
+//!   more `--filename <name>` values.
 //!
 //! # Error Behavior
 //! - Unknown file type groups return a message with the available groups.
-//! - Missing extension selection returns a message asking for `--file-type` or `--ext`.
+//! - Missing file type selection returns a message asking for `--file-type`, `--ext`, or
+//!   `--filename`.
+//! - Invalid `--filename` globs are rejected before generation.
 //! - Invalid directories are rejected before generation.
 //! - Output paths that already point to directories are rejected by `generate_tag_output`.
 //!
@@ -19,13 +22,27 @@
 //! The CLI does not write tagged output directly. It builds a request, then `main.rs` passes that
 //! request to `generate_tag_output`, matching the GUI architecture and avoiding duplicate generation paths.
 
-use crate::filetypes::{find_filetype_group, format_available_filetype_groups, FileTypeGroup};
-use crate::generation::TagGenerationRequest;
+use crate::budget::{BudgetRank, OutputBudget};
+use crate::chunks::ChunkLimit;
+use crate::content::{TextEncoding, DEFAULT_MAX_FILE_SIZE};
+use crate::file_list::{read_file_list, STDIN_SOURCE};
+use crate::filetypes::{
+    find_filetype_group, format_available_filetype_groups, normalize_extensions, FileTypeGroup,
+};
+use crate::formats::{OutputFormat, PathStyle};
+use crate::generation::{SourceRoot, TagGenerationRequest};
+use crate::git::{DiffBase, GitFileMode, GitSelection};
+use crate::metadata::MetadataField;
+use crate::ordering::FileOrder;
+use crate::output_text::OutputText;
+use crate::patterns::{FileNamePatterns, PathPatterns, PriorityPatterns};
 use crate::presets::PresetCommand;
+use crate::roots::RootSpec;
+use crate::tree::TreeOptions;
 use clap::{Args, Parser, Subcommand};
 use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Parser)]
 #[command(name = "code-file-wrapper")]
@@ -41,6 +58,8 @@ pub enum Command {
     Gui,
     /// Generate output from command-line arguments.
     Run(RunArgs),
+    /// Preview which files a run would include, without writing any output.
+    List(RunArgs),
     /// Generate output from a saved profile.
     RunProfile { name: String },
     /// Print saved run profiles.
@@ -66,16 +85,142 @@ pub struct SaveProfileArgs {
 pub struct RunArgs {
     #[arg(long)]
     pub dir: PathBuf,
+    /// Write --dir's files under this name in the output (e.g. `backend/src/main.rs`).
+    #[arg(long = "alias", value_name = "NAME")]
+    pub alias: Option<String>,
+    /// Also wrap this folder into the same output (repeatable), given as
+    /// `PATH[,alias=NAME][,file-type=GROUP][,ext=EXT][,filename=NAME][,ignore=FOLDER]`.
+    #[arg(long = "root", value_name = "SPEC", conflicts_with = "files_from")]
+    pub roots: Vec<RootSpec>,
     #[arg(long = "file-type")]
     pub file_type: Option<String>,
     #[arg(long = "ext")]
     pub extensions: Vec<String>,
+    /// Match extensions case-sensitively, so `--ext rs` no longer selects `MAIN.RS`.
+    #[arg(long = "strict-extension-case")]
+    pub strict_extension_case: bool,
+    /// Include files with this exact name or file-name glob, whatever their extension (repeatable).
+    #[arg(long = "filename")]
+    pub filenames: Vec<String>,
+    /// Include extensionless scripts whose shebang interpreter matches a selected extension
+    /// (e.g. `#!/usr/bin/env python` as `py`).
+    #[arg(long = "shebang")]
+    pub detect_shebangs: bool,
+    /// Wrap exactly the files listed in this file (one path per line, relative to --dir), in
+    /// order, instead of scanning; `-` reads the list from stdin.
+    #[arg(
+        long = "files-from",
+        value_name = "PATH|-",
+        conflicts_with_all = ["file_type", "extensions", "filenames"]
+    )]
+    pub files_from: Option<PathBuf>,
     #[arg(long)]
     pub recursive: bool,
     #[arg(long = "ignore")]
     pub ignored_folders: Vec<String>,
+    /// Skip files excluded by .gitignore, .ignore, and git exclude files.
+    #[arg(long = "respect-gitignore")]
+    pub respect_gitignore: bool,
+    /// Only include tracked, staged, or untracked (but not ignored) files of the git repository.
+    #[arg(long = "git-files", value_enum)]
+    pub git_files: Option<GitFileMode>,
+    /// Only include files that differ between this git ref and the working tree.
+    #[arg(
+        long = "changed-since",
+        value_name = "REF",
+        conflicts_with = "git_files"
+    )]
+    pub changed_since: Option<String>,
+    /// Only include files whose relative path matches this glob (repeatable).
+    #[arg(long = "include")]
+    pub include_patterns: Vec<String>,
+    /// Exclude files whose relative path matches this glob (repeatable).
+    #[arg(long = "exclude")]
+    pub exclude_patterns: Vec<String>,
+    /// Skip any file that starts with the wrapper's output marker.
+    #[arg(long = "skip-previous-outputs")]
+    pub skip_previous_outputs: bool,
+    /// Order in which files are written.
+    #[arg(long = "order", value_enum, default_value_t = FileOrder::Path)]
+    pub file_order: FileOrder,
+    /// Write files matching this glob first, in the order given (repeatable).
+    #[arg(long = "priority")]
+    pub priority_patterns: Vec<String>,
     #[arg(long, default_value = "tags_output.txt")]
     pub output: PathBuf,
+    /// Layout of the generated output.
+    #[arg(long = "format", value_enum, default_value_t = OutputFormat::Tags)]
+    pub output_format: OutputFormat,
+    /// Separator used in file paths written to the output.
+    #[arg(long = "path-style", value_enum, default_value_t = PathStyle::Native)]
+    pub path_style: PathStyle,
+    /// Render the whole document with this minijinja template instead of --format.
+    #[arg(long = "template")]
+    pub template: Option<PathBuf>,
+    /// Count tokens with a local tiktoken-format BPE vocabulary instead of the heuristic estimate.
+    #[arg(long = "token-vocab")]
+    pub token_vocab: Option<PathBuf>,
+    /// Keep the output within this many tokens, dropping lower-ranked files that do not fit.
+    #[arg(long = "max-tokens")]
+    pub max_tokens: Option<usize>,
+    /// Keep the output within this many bytes, dropping lower-ranked files that do not fit.
+    #[arg(long = "max-bytes")]
+    pub max_bytes: Option<usize>,
+    /// Truncate files that exceed the remaining budget instead of dropping them.
+    #[arg(long = "truncate")]
+    pub truncate: bool,
+    /// Which files a budget keeps first, after --priority globs.
+    #[arg(long = "budget-rank", value_enum, default_value_t = BudgetRank::Recent)]
+    pub budget_rank: BudgetRank,
+    /// Split the output into numbered parts of at most this many tokens each.
+    #[arg(long = "chunk-tokens")]
+    pub chunk_tokens: Option<usize>,
+    /// Split the output into numbered parts of at most this many bytes each.
+    #[arg(long = "chunk-bytes")]
+    pub chunk_bytes: Option<usize>,
+    /// Write an ASCII directory tree of the project before the file contents.
+    #[arg(long = "tree")]
+    pub tree: bool,
+    /// Expand at most this many folder levels in the directory tree (implies --tree).
+    #[arg(long = "tree-depth")]
+    pub tree_depth: Option<usize>,
+    /// Add a [Diff] section with the changes between HEAD and the working tree.
+    #[arg(long = "diff")]
+    pub diff: bool,
+    /// Compare the working tree with this git ref instead of HEAD (implies --diff).
+    #[arg(long = "diff-base", value_name = "REF")]
+    pub diff_base: Option<String>,
+    /// Write this metadata with every file (repeatable or comma-separated).
+    #[arg(long = "metadata", value_enum, value_delimiter = ',')]
+    pub metadata: Vec<MetadataField>,
+    /// Prefix every line of file contents with its line number (tags, Markdown, and XML formats).
+    #[arg(long = "line-numbers")]
+    pub line_numbers: bool,
+    /// Skip files larger than this many bytes (default 1 MiB; 0 for no limit).
+    #[arg(long = "max-file-size")]
+    pub max_file_size: Option<u64>,
+    /// Read files that are not UTF-8 (and have no byte order mark) in this encoding; tried in
+    /// the order given (repeatable or comma-separated).
+    #[arg(long = "fallback-encoding", value_enum, value_delimiter = ',')]
+    pub fallback_encodings: Vec<TextEncoding>,
+    /// Text written before the first file (overrides output_text.json).
+    #[arg(long = "header", conflicts_with = "no_header")]
+    pub header: Option<String>,
+    /// Omit the header, even if output_text.json sets one.
+    #[arg(long = "no-header")]
+    pub no_header: bool,
+    /// Instruction text written after the last file (overrides output_text.json).
+    #[arg(long = "footer", conflicts_with = "no_footer")]
+    pub footer: Option<String>,
+    /// Omit the footer instructions.
+    #[arg(long = "no-footer")]
+    pub no_footer: bool,
+    /// Title line written above the additional commands (overrides output_text.json).
+    #[arg(long = "additional-title", conflicts_with = "no_additional_title")]
+    pub additional_title: Option<String>,
+    /// Omit the title line above the additional commands.
+    #[arg(long = "no-additional-title")]
+    pub no_additional_title: bool,
     #[arg(long)]
     pub copy: bool,
     #[arg(long)]
@@ -86,12 +231,19 @@ pub struct RunArgs {
     pub additional_commands: Option<String>,
     #[arg(long = "additional-file")]
     pub additional_commands_file: Option<PathBuf>,
+    /// Print which files would be included or skipped, and why, instead of writing output.
+    #[arg(long = "dry-run")]
+    pub dry_run: bool,
+    /// Print the --dry-run or list preview as JSON instead of a table.
+    #[arg(long = "json")]
+    pub json: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct BuiltRunRequest {
     pub request: TagGenerationRequest,
     pub extensions_used: Vec<String>,
+    pub filenames_used: Vec<String>,
 }
 
 pub fn build_run_request(
@@ -100,21 +252,108 @@ pub fn build_run_request(
     presets: &[PresetCommand],
 ) -> Result<BuiltRunRequest, String> {
     validate_run_directory(&args.dir)?;
-    let extensions = resolve_extensions(&args, file_type_groups)?;
+    let file_list = args.files_from.as_deref().map(read_file_list).transpose()?;
+    if file_list.as_ref().is_some_and(Vec::is_empty) {
+        return Err("The file list given to --files-from names no files.".to_string());
+    }
+    build_request(args, file_list, file_type_groups, presets)
+}
+
+/// Checks `args` the way [`build_run_request`] does, without reading the `--files-from` list.
+///
+/// Used when saving a profile: a list read from stdin (`-`) is only available when the profile
+/// runs, so only a list given as a path is checked, and only for existence.
+pub fn validate_run_args(
+    args: RunArgs,
+    file_type_groups: &[FileTypeGroup],
+    presets: &[PresetCommand],
+) -> Result<(), String> {
+    validate_run_directory(&args.dir)?;
+    let file_list = match args.files_from.as_deref() {
+        Some(source) if source != Path::new(STDIN_SOURCE) && !source.is_file() => {
+            return Err(format!(
+                "File list '{}' does not exist or is not a file.",
+                source.display()
+            ));
+        }
+        Some(_) => Some(Vec::new()),
+        None => None,
+    };
+    build_request(args, file_list, file_type_groups, presets).map(|_| ())
+}
+
+fn build_request(
+    args: RunArgs,
+    file_list: Option<Vec<PathBuf>>,
+    file_type_groups: &[FileTypeGroup],
+    presets: &[PresetCommand],
+) -> Result<BuiltRunRequest, String> {
+    let FileTypeSelection {
+        extensions,
+        filenames,
+    } = match &file_list {
+        Some(_) => FileTypeSelection::default(),
+        None => resolve_file_types(
+            args.file_type.as_deref(),
+            &args.extensions,
+            &args.filenames,
+            args.strict_extension_case,
+            file_type_groups,
+        )?,
+    };
+    let additional_roots = resolve_roots(&args, &extensions, &filenames, file_type_groups)?;
+    FileNamePatterns::compile(&filenames)?;
+    PathPatterns::compile(&args.include_patterns, &args.exclude_patterns)?;
+    PriorityPatterns::compile(&args.priority_patterns)?;
+    validate_template_path(args.template.as_deref())?;
     let preset_texts = resolve_presets(&args.presets, presets)?;
     let additional_commands = resolve_additional_commands(
         args.additional_commands_file.as_ref(),
         args.additional_commands.as_deref(),
     )?;
+    let output_text = output_text_overrides(&args);
+    let budget = output_budget(&args);
+    let chunk_limit = chunk_limit(&args);
+    let tree = tree_options(&args);
+    let max_file_size = file_size_limit(&args);
+    let git_selection = git_selection(&args);
+    let diff = diff_base(&args);
 
     Ok(BuiltRunRequest {
         extensions_used: extensions.clone(),
+        filenames_used: filenames.clone(),
         request: TagGenerationRequest {
             root_dir: args.dir,
+            root_alias: args.alias,
+            additional_roots,
             extensions,
+            strict_extension_case: args.strict_extension_case,
+            filenames,
+            detect_shebangs: args.detect_shebangs,
+            file_list,
             recursive: args.recursive,
             ignored_folders: args.ignored_folders,
+            respect_gitignore: args.respect_gitignore,
+            git_selection,
+            include_patterns: args.include_patterns,
+            exclude_patterns: args.exclude_patterns,
+            skip_previous_outputs: args.skip_previous_outputs,
+            file_order: args.file_order,
+            priority_patterns: args.priority_patterns,
             output_path: args.output,
+            output_format: args.output_format,
+            path_style: args.path_style,
+            output_text,
+            template_path: args.template,
+            token_vocab: args.token_vocab,
+            budget,
+            chunk_limit,
+            tree,
+            diff,
+            metadata: args.metadata,
+            line_numbers: args.line_numbers,
+            max_file_size,
+            fallback_encodings: args.fallback_encodings,
             additional_commands,
             preset_texts,
             copy_to_clipboard: args.copy,
@@ -123,7 +362,96 @@ pub fn build_run_request(
     })
 }
 
-fn validate_run_directory(dir: &PathBuf) -> Result<(), String> {
+/// Collects the header, footer, and section title flags into overrides for `output_text.json`.
+///
+/// Unset flags stay `None` so the config file (or built-in default) applies; `--no-*` flags
+/// become empty strings, which disable that part of the output.
+pub fn output_text_overrides(args: &RunArgs) -> OutputText {
+    let text_override = |value: &Option<String>, disabled: bool| {
+        if disabled {
+            Some(String::new())
+        } else {
+            value.clone()
+        }
+    };
+
+    OutputText {
+        header: text_override(&args.header, args.no_header),
+        footer: text_override(&args.footer, args.no_footer),
+        additional_commands_title: text_override(&args.additional_title, args.no_additional_title),
+    }
+}
+
+/// Collects the budget flags into an [`OutputBudget`].
+pub fn output_budget(args: &RunArgs) -> OutputBudget {
+    OutputBudget {
+        max_tokens: args.max_tokens,
+        max_bytes: args.max_bytes,
+        truncate: args.truncate,
+        rank: args.budget_rank,
+    }
+}
+
+/// Collects the chunk flags into a [`ChunkLimit`].
+pub fn chunk_limit(args: &RunArgs) -> ChunkLimit {
+    ChunkLimit {
+        max_tokens: args.chunk_tokens,
+        max_bytes: args.chunk_bytes,
+    }
+}
+
+/// Collects the directory tree flags into [`TreeOptions`]; a depth limit enables the tree.
+pub fn tree_options(args: &RunArgs) -> TreeOptions {
+    TreeOptions {
+        enabled: args.tree || args.tree_depth.is_some(),
+        max_depth: args.tree_depth,
+    }
+}
+
+/// Resolves `--max-file-size`: unset means [`DEFAULT_MAX_FILE_SIZE`], `0` means no limit.
+pub fn file_size_limit(args: &RunArgs) -> Option<u64> {
+    match args.max_file_size {
+        None => Some(DEFAULT_MAX_FILE_SIZE),
+        Some(0) => None,
+        Some(limit) => Some(limit),
+    }
+}
+
+/// Resolves `--git-files` and `--changed-since` into the git selection they request.
+pub fn git_selection(args: &RunArgs) -> Option<GitSelection> {
+    match &args.changed_since {
+        Some(base) => Some(GitSelection::ChangedSince { base: base.clone() }),
+        None => args.git_files.map(GitSelection::from),
+    }
+}
+
+/// Resolves `--diff` and `--diff-base`; a base ref enables the diff.
+pub fn diff_base(args: &RunArgs) -> Option<DiffBase> {
+    match &args.diff_base {
+        Some(base) => Some(DiffBase::Ref(base.clone())),
+        None => args.diff.then_some(DiffBase::Head),
+    }
+}
+
+/// Rejects `run --json` without `--dry-run`, since only the preview has a JSON form.
+pub fn validate_run_flags(args: &RunArgs) -> Result<(), String> {
+    if args.json && !args.dry_run {
+        return Err("--json only applies to a preview; add --dry-run or use list.".to_string());
+    }
+    Ok(())
+}
+
+fn validate_template_path(template: Option<&Path>) -> Result<(), String> {
+    match template {
+        Some(path) if !path.is_file() => Err(format!(
+            "Template file '{}' does not exist or is not a file.",
+            path.display()
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn validate_run_directory(dir: &Path) -> Result<(), String> {
     if !dir.is_dir() {
         return Err(format!(
             "Directory '{}' does not exist or is not a folder.",
@@ -134,48 +462,100 @@ fn validate_run_directory(dir: &PathBuf) -> Result<(), String> {
     Ok(())
 }
 
-fn resolve_extensions(
-    args: &RunArgs,
+/// Extensions and file names selected by `--file-type`, `--ext`, and `--filename`.
+#[derive(Default)]
+struct FileTypeSelection {
+    extensions: Vec<String>,
+    filenames: Vec<String>,
+}
+
+fn resolve_file_types(
+    file_type: Option<&str>,
+    selected_extensions: &[String],
+    selected_filenames: &[String],
+    strict_case: bool,
     file_type_groups: &[FileTypeGroup],
-) -> Result<Vec<String>, String> {
+) -> Result<FileTypeSelection, String> {
     let mut extensions = Vec::new();
+    let mut filenames = Vec::new();
 
-    if let Some(file_type) = args.file_type.as_deref() {
+    if let Some(file_type) = file_type {
         let group = find_filetype_group(file_type_groups, file_type).ok_or_else(|| {
             format!(
                 "Unknown file type group '{file_type}'. Available file type groups:\n{}",
                 format_available_filetype_groups(file_type_groups)
             )
         })?;
-        extensions.extend(
-            group
-                .extensions
-                .iter()
-                .map(|extension| normalize_extension(extension)),
-        );
+        extensions.extend(group.extensions.iter().cloned());
+        filenames.extend(group.filenames.iter().map(|name| name.trim().to_string()));
     }
 
-    extensions.extend(
-        args.extensions
+    extensions.extend(selected_extensions.iter().cloned());
+    let extensions = normalize_extensions(extensions.iter().map(String::as_str), strict_case);
+
+    filenames.extend(
+        selected_filenames
             .iter()
-            .map(|extension| normalize_extension(extension)),
+            .map(|name| name.trim().to_string()),
     );
 
-    extensions.retain(|extension| !extension.is_empty());
-    deduplicate_preserving_order(&mut extensions);
+    filenames.retain(|name| !name.is_empty());
+    deduplicate_preserving_order(&mut filenames);
 
-    if extensions.is_empty() {
+    if extensions.is_empty() && filenames.is_empty() {
         return Err(
-            "No extensions selected. Provide --file-type <group> or one or more --ext <extension> values."
+            "No file types selected. Provide --file-type <group>, one or more --ext <extension> values, or --filename <name>."
                 .to_string(),
         );
     }
 
-    Ok(extensions)
+    Ok(FileTypeSelection {
+        extensions,
+        filenames,
+    })
 }
 
-fn normalize_extension(extension: &str) -> String {
-    extension.trim().trim_start_matches('.').to_string()
+/// Resolves each `--root` into a request root. Roots that select no file types use the main
+/// root's `extensions` and `filenames`, and roots without `ignore` use its ignored folders.
+fn resolve_roots(
+    args: &RunArgs,
+    extensions: &[String],
+    filenames: &[String],
+    file_type_groups: &[FileTypeGroup],
+) -> Result<Vec<SourceRoot>, String> {
+    args.roots
+        .iter()
+        .map(|root| {
+            validate_run_directory(&root.dir)?;
+            let file_types = if root.selects_file_types() {
+                resolve_file_types(
+                    root.file_type.as_deref(),
+                    &root.extensions,
+                    &root.filenames,
+                    args.strict_extension_case,
+                    file_type_groups,
+                )?
+            } else {
+                FileTypeSelection {
+                    extensions: extensions.to_vec(),
+                    filenames: filenames.to_vec(),
+                }
+            };
+            FileNamePatterns::compile(&file_types.filenames)?;
+            let ignored_folders = if root.ignored_folders.is_empty() {
+                args.ignored_folders.clone()
+            } else {
+                root.ignored_folders.clone()
+            };
+            Ok(SourceRoot {
+                dir: root.dir.clone(),
+                alias: root.alias.clone(),
+                extensions: file_types.extensions,
+                filenames: file_types.filenames,
+                ignored_folders,
+            })
+        })
+        .collect()
 }
 
 fn deduplicate_preserving_order(values: &mut Vec<String>) {
@@ -252,6 +632,7 @@ mod tests {
         vec![FileTypeGroup {
             name: "Rust".to_string(),
             extensions: vec!["rs".to_string()],
+            filenames: vec!["build.rs".to_string()],
         }]
     }
 
@@ -326,6 +707,294 @@ mod tests {
         assert_eq!(args.ignored_folders, vec!["target", ".git"]);
     }
 
+    #[test]
+    fn respect_gitignore_flag_parses_and_reaches_request() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let dir = temp.path().to_str().expect("temp path is UTF-8");
+        let cli = Cli::try_parse_from([
+            "code-file-wrapper",
+            "run",
+            "--dir",
+            dir,
+            "--ext",
+            "rs",
+            "--respect-gitignore",
+        ])
+        .expect("CLI should parse");
+        let Some(Command::Run(args)) = cli.command else {
+            panic!("expected run command");
+        };
+
+        assert!(args.respect_gitignore);
+        let built = build_run_request(args, &rust_group(), &[]).expect("request should build");
+        assert!(built.request.respect_gitignore);
+        Ok(())
+    }
+
+    #[test]
+    fn repeated_include_and_exclude_values_parse() {
+        let cli = Cli::try_parse_from([
+            "code-file-wrapper",
+            "run",
+            "--dir",
+            ".",
+            "--ext",
+            "rs",
+            "--include",
+            "crates/*/src/**/*.rs",
+            "--exclude",
+            "src/generated/**",
+            "--exclude",
+            "**/*_test.rs",
+        ])
+        .expect("CLI should parse");
+
+        let Some(Command::Run(args)) = cli.command else {
+            panic!("expected run command");
+        };
+
+        assert_eq!(args.include_patterns, vec!["crates/*/src/**/*.rs"]);
+        assert_eq!(
+            args.exclude_patterns,
+            vec!["src/generated/**", "**/*_test.rs"]
+        );
+    }
+
+    #[test]
+    fn order_and_priority_values_parse() {
+        let cli = Cli::try_parse_from([
+            "code-file-wrapper",
+            "run",
+            "--dir",
+            ".",
+            "--ext",
+            "rs",
+            "--order",
+            "dirs-first",
+            "--priority",
+            "Cargo.toml",
+            "--priority",
+            "src/main.rs",
+        ])
+        .expect("CLI should parse");
+
+        let Some(Command::Run(args)) = cli.command else {
+            panic!("expected run command");
+        };
+
+        assert_eq!(args.file_order, FileOrder::DirsFirst);
+        assert_eq!(args.priority_patterns, vec!["Cargo.toml", "src/main.rs"]);
+    }
+
+    #[test]
+    fn order_defaults_to_path() {
+        let cli = Cli::try_parse_from(["code-file-wrapper", "run", "--dir", ".", "--ext", "rs"])
+            .expect("CLI should parse");
+
+        let Some(Command::Run(args)) = cli.command else {
+            panic!("expected run command");
+        };
+
+        assert_eq!(args.file_order, FileOrder::Path);
+        assert_eq!(args.output_format, OutputFormat::Tags);
+        assert_eq!(args.path_style, PathStyle::Native);
+    }
+
+    #[test]
+    fn list_and_dry_run_parse_run_arguments() {
+        let cli = Cli::try_parse_from([
+            "code-file-wrapper",
+            "list",
+            "--dir",
+            ".",
+            "--ext",
+            "rs",
+            "--json",
+        ])
+        .expect("CLI should parse");
+        let Some(Command::List(args)) = cli.command else {
+            panic!("expected list command");
+        };
+        assert!(args.json);
+        assert!(!args.dry_run);
+
+        let cli = Cli::try_parse_from([
+            "code-file-wrapper",
+            "run",
+            "--dir",
+            ".",
+            "--ext",
+            "rs",
+            "--dry-run",
+        ])
+        .expect("CLI should parse");
+        let Some(Command::Run(args)) = cli.command else {
+            panic!("expected run command");
+        };
+        assert!(args.dry_run);
+        assert!(validate_run_flags(&args).is_ok());
+    }
+
+    #[test]
+    fn run_json_without_dry_run_is_rejected() {
+        let cli = Cli::try_parse_from([
+            "code-file-wrapper",
+            "run",
+            "--dir",
+            ".",
+            "--ext",
+            "rs",
+            "--json",
+        ])
+        .expect("CLI should parse");
+        let Some(Command::Run(args)) = cli.command else {
+            panic!("expected run command");
+        };
+        let error = validate_run_flags(&args).expect_err("--json needs --dry-run");
+        assert!(error.contains("--dry-run"), "{error}");
+    }
+
+    #[test]
+    fn max_file_size_defaults_to_one_mebibyte_and_zero_disables_it() {
+        let limit = |extra: &[&str]| {
+            let mut argv = vec!["code-file-wrapper", "run", "--dir", ".", "--ext", "rs"];
+            argv.extend_from_slice(extra);
+            let Some(Command::Run(args)) =
+                Cli::try_parse_from(argv).expect("CLI should parse").command
+            else {
+                panic!("expected run command");
+            };
+            file_size_limit(&args)
+        };
+
+        assert_eq!(limit(&[]), Some(DEFAULT_MAX_FILE_SIZE));
+        assert_eq!(limit(&["--max-file-size", "2048"]), Some(2048));
+        assert_eq!(limit(&["--max-file-size", "0"]), None);
+    }
+
+    #[test]
+    fn budget_flags_become_output_budget() {
+        let cli = Cli::try_parse_from([
+            "code-file-wrapper",
+            "run",
+            "--dir",
+            ".",
+            "--ext",
+            "rs",
+            "--max-tokens",
+            "120000",
+            "--truncate",
+            "--budget-rank",
+            "smallest",
+        ])
+        .expect("CLI should parse");
+
+        let Some(Command::Run(args)) = cli.command else {
+            panic!("expected run command");
+        };
+
+        assert_eq!(
+            output_budget(&args),
+            OutputBudget {
+                max_tokens: Some(120_000),
+                max_bytes: None,
+                truncate: true,
+                rank: BudgetRank::Smallest,
+            }
+        );
+    }
+
+    #[test]
+    fn text_flags_become_output_text_overrides() {
+        let cli = Cli::try_parse_from([
+            "code-file-wrapper",
+            "run",
+            "--dir",
+            ".",
+            "--ext",
+            "rs",
+            "--header",
+            "Snapshot",
+            "--no-footer",
+        ])
+        .expect("CLI should parse");
+
+        let Some(Command::Run(args)) = cli.command else {
+            panic!("expected run command");
+        };
+        let overrides = output_text_overrides(&args);
+
+        assert_eq!(overrides.header.as_deref(), Some("Snapshot"));
+        assert_eq!(overrides.footer.as_deref(), Some(""));
+        assert_eq!(overrides.additional_commands_title, None);
+    }
+
+    #[test]
+    fn footer_and_no_footer_conflict() {
+        let result = Cli::try_parse_from([
+            "code-file-wrapper",
+            "run",
+            "--dir",
+            ".",
+            "--ext",
+            "rs",
+            "--footer",
+            "text",
+            "--no-footer",
+        ]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn format_value_parses() {
+        let cli = Cli::try_parse_from([
+            "code-file-wrapper",
+            "run",
+            "--dir",
+            ".",
+            "--ext",
+            "rs",
+            "--format",
+            "markdown",
+            "--path-style",
+            "forward-slash",
+        ])
+        .expect("CLI should parse");
+
+        let Some(Command::Run(args)) = cli.command else {
+            panic!("expected run command");
+        };
+
+        assert_eq!(args.output_format, OutputFormat::Markdown);
+        assert_eq!(args.path_style, PathStyle::ForwardSlash);
+    }
+
+    #[test]
+    fn invalid_glob_is_rejected_before_generation() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let dir = temp.path().to_str().expect("temp path is UTF-8");
+        let cli = Cli::try_parse_from([
+            "code-file-wrapper",
+            "run",
+            "--dir",
+            dir,
+            "--ext",
+            "rs",
+            "--exclude",
+            "src/[",
+        ])
+        .expect("CLI should parse");
+        let Some(Command::Run(args)) = cli.command else {
+            panic!("expected run command");
+        };
+
+        let error = build_run_request(args, &rust_group(), &[]).expect_err("expected error");
+
+        assert!(error.contains("Invalid glob pattern 'src/['"));
+        Ok(())
+    }
+
     #[test]
     fn no_arg_cli_has_no_command() {
         let cli = Cli::try_parse_from(["code-file-wrapper"]).expect("CLI should parse");
@@ -348,6 +1017,8 @@ mod tests {
             ".toml",
             "--ext",
             "rs",
+            "--ext",
+            "RS",
         ])
         .expect("CLI should parse");
         let Some(Command::Run(args)) = args.command else {
@@ -358,10 +1029,248 @@ mod tests {
 
         assert_eq!(built.extensions_used, vec!["rs", "toml"]);
         assert_eq!(built.request.extensions, vec!["rs", "toml"]);
+        assert_eq!(built.request.filenames, vec!["build.rs"]);
         assert_eq!(built.request.root_dir, temp.path());
         Ok(())
     }
 
+    #[test]
+    fn strict_extension_case_keeps_the_configured_case() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let dir = temp.path().to_str().expect("temp path is UTF-8");
+        let args = Cli::try_parse_from([
+            "code-file-wrapper",
+            "run",
+            "--dir",
+            dir,
+            "--ext",
+            ".R",
+            "--ext",
+            "r",
+            "--strict-extension-case",
+        ])
+        .expect("CLI should parse");
+        let Some(Command::Run(args)) = args.command else {
+            panic!("expected run command");
+        };
+
+        let built = build_run_request(args, &rust_group(), &[]).expect("request should build");
+
+        assert_eq!(built.request.extensions, vec!["R", "r"]);
+        assert!(built.request.strict_extension_case);
+        Ok(())
+    }
+
+    #[test]
+    fn filenames_alone_select_file_types() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let dir = temp.path().to_str().expect("temp path is UTF-8");
+        let parse = |extra: &[&str]| {
+            let mut argv = vec!["code-file-wrapper", "run", "--dir", dir];
+            argv.extend_from_slice(extra);
+            let Some(Command::Run(args)) =
+                Cli::try_parse_from(argv).expect("CLI should parse").command
+            else {
+                panic!("expected run command");
+            };
+            args
+        };
+
+        let built = build_run_request(
+            parse(&[
+                "--filename",
+                "Makefile",
+                "--filename",
+                "Dockerfile.*",
+                "--shebang",
+            ]),
+            &rust_group(),
+            &[],
+        )
+        .expect("request should build");
+        assert!(built.request.extensions.is_empty());
+        assert_eq!(built.request.filenames, vec!["Makefile", "Dockerfile.*"]);
+        assert!(built.request.detect_shebangs);
+
+        let error = build_run_request(parse(&["--filename", "[Makefile"]), &rust_group(), &[])
+            .expect_err("invalid glob should fail");
+        assert!(error.contains("Invalid glob pattern '[Makefile'"));
+        Ok(())
+    }
+
+    #[test]
+    fn git_flags_select_one_git_selection() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let dir = temp.path().to_str().expect("temp path is UTF-8");
+        let parse = |extra: &[&str]| {
+            let mut argv = vec!["code-file-wrapper", "run", "--dir", dir, "--ext", "rs"];
+            argv.extend_from_slice(extra);
+            Cli::try_parse_from(argv).map(|cli| match cli.command {
+                Some(Command::Run(args)) => args,
+                _ => panic!("expected run command"),
+            })
+        };
+        let selection = |extra: &[&str]| {
+            let args = parse(extra).expect("CLI should parse");
+            build_run_request(args, &rust_group(), &[])
+                .expect("request should build")
+                .request
+                .git_selection
+        };
+
+        assert_eq!(selection(&[]), None);
+        assert_eq!(
+            selection(&["--git-files", "staged"]),
+            Some(GitSelection::Staged)
+        );
+        assert_eq!(
+            selection(&["--changed-since", "main"]),
+            Some(GitSelection::ChangedSince {
+                base: "main".to_string()
+            })
+        );
+        assert!(parse(&["--git-files", "tracked", "--changed-since", "main"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn files_from_replaces_the_file_type_selection() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let dir = temp.path().to_str().expect("temp path is UTF-8");
+        let list = temp.path().join("files.txt");
+        let list_arg = list.to_str().expect("temp path is UTF-8");
+        let parse = |extra: &[&str]| {
+            let mut argv = vec!["code-file-wrapper", "run", "--dir", dir];
+            argv.extend_from_slice(extra);
+            Cli::try_parse_from(argv).map(|cli| match cli.command {
+                Some(Command::Run(args)) => args,
+                _ => panic!("expected run command"),
+            })
+        };
+
+        std::fs::write(&list, "src/main.rs\r\nREADME.md\n")?;
+        let args = parse(&["--files-from", list_arg]).expect("CLI should parse");
+        let built = build_run_request(args, &rust_group(), &[]).expect("request should build");
+        assert_eq!(
+            built.request.file_list,
+            Some(vec![
+                PathBuf::from("src/main.rs"),
+                PathBuf::from("README.md")
+            ])
+        );
+        assert!(built.request.extensions.is_empty());
+
+        assert!(parse(&["--files-from", list_arg, "--ext", "rs"]).is_err());
+
+        std::fs::write(&list, "\n")?;
+        let args = parse(&["--files-from", list_arg]).expect("CLI should parse");
+        let error = build_run_request(args, &rust_group(), &[]).expect_err("empty list");
+        assert!(error.contains("names no files"));
+        Ok(())
+    }
+
+    #[test]
+    fn roots_inherit_file_types_and_ignored_folders_unless_given() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        fs::create_dir_all(temp.path().join("api"))?;
+        fs::create_dir_all(temp.path().join("web"))?;
+        let path = |name: &str| {
+            temp.path()
+                .join(name)
+                .to_str()
+                .expect("temp path is UTF-8")
+                .to_string()
+        };
+        let (api, web) = (path("api"), path("web"));
+        let web_root = format!("{web},alias=frontend,ext=ts,ignore=dist");
+        let args = Cli::try_parse_from([
+            "code-file-wrapper",
+            "run",
+            "--dir",
+            &api,
+            "--alias",
+            "backend",
+            "--file-type",
+            "Rust",
+            "--ignore",
+            "target",
+            "--root",
+            &web_root,
+            "--root",
+            &api,
+        ])
+        .expect("CLI should parse");
+        let Some(Command::Run(args)) = args.command else {
+            panic!("expected run command");
+        };
+
+        let built = build_run_request(args, &rust_group(), &[]).expect("request should build");
+
+        assert_eq!(built.request.root_alias.as_deref(), Some("backend"));
+        let roots = &built.request.additional_roots;
+        assert_eq!(roots.len(), 2);
+        assert_eq!(roots[0].alias.as_deref(), Some("frontend"));
+        assert_eq!(roots[0].extensions, vec!["ts"]);
+        assert!(roots[0].filenames.is_empty());
+        assert_eq!(roots[0].ignored_folders, vec!["dist"]);
+        assert_eq!(roots[1].extensions, vec!["rs"]);
+        assert_eq!(roots[1].filenames, vec!["build.rs"]);
+        assert_eq!(roots[1].ignored_folders, vec!["target"]);
+
+        let missing = format!("{},alias=gone", path("missing"));
+        let args = Cli::try_parse_from([
+            "code-file-wrapper",
+            "run",
+            "--dir",
+            &api,
+            "--ext",
+            "rs",
+            "--root",
+            &missing,
+        ])
+        .expect("CLI should parse");
+        let Some(Command::Run(args)) = args.command else {
+            panic!("expected run command");
+        };
+        let error = build_run_request(args, &rust_group(), &[]).expect_err("missing root");
+        assert!(error.contains("does not exist"));
+
+        assert!(Cli::try_parse_from([
+            "code-file-wrapper",
+            "run",
+            "--dir",
+            &api,
+            "--root",
+            "../web,colour=red",
+        ])
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn diff_flags_choose_the_diff_base() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let dir = temp.path().to_str().expect("temp path is UTF-8");
+        let diff = |extra: &[&str]| {
+            let mut argv = vec!["code-file-wrapper", "run", "--dir", dir, "--ext", "rs"];
+            argv.extend_from_slice(extra);
+            let Some(Command::Run(args)) =
+                Cli::try_parse_from(argv).expect("CLI should parse").command
+            else {
+                panic!("expected run command");
+            };
+            diff_base(&args)
+        };
+
+        assert_eq!(diff(&[]), None);
+        assert_eq!(diff(&["--diff"]), Some(DiffBase::Head));
+        assert_eq!(
+            diff(&["--diff-base", "origin/main"]),
+            Some(DiffBase::Ref("origin/main".to_string()))
+        );
+        Ok(())
+    }
+
     #[test]
     fn build_run_request_requires_file_type_or_ext_before_generation() -> std::io::Result<()> {
         let temp = tempdir()?;
@@ -0,0 +1,368 @@
+//! # File Contents Module
+//!
+//! Reads a selected file as text, refusing files that are too large or that look binary, so a
+//! misnamed binary or a huge minified asset never ends up in the output. Text in other encodings
+//! is transcoded to UTF-8.
+//!
+//! # Checks
+//! - Size: files larger than the scan's `max_file_size` are skipped before they are read.
+//! - Encoding: a byte order mark selects UTF-8, UTF-16LE, or UTF-16BE. Without one, contents must
+//!   be valid UTF-8 or decode cleanly with one of the scan's `fallback_encodings`, tried in order.
+//! - Binary sniffing: the first [`SNIFF_BYTES`] bytes of the decoded text are inspected; a NUL
+//!   character, or a share of control characters above [`MAX_CONTROL_RATIO`], marks the file as
+//!   binary. A fallback whose result looks binary is rejected and the next one is tried.
+//!
+//! # Notes
+//! - Tabs, line breaks, form feeds, and escape characters are treated as text.
+//! - Bytes above 0x7F are never counted as control characters, so text in legacy encodings is
+//!   reported as not UTF-8 rather than binary.
+//! - Byte order marks are removed; files decoded from anything but UTF-8 are reported as
+//!   transcoded, with their source encoding.
+//!
+//! # Shebangs
+//! [`shebang_extension`] maps the interpreter named on a script's `#!` line to the extension its
+//! scripts usually have (`#!/usr/bin/env python3` → `py`), so extensionless scripts can be
+//! selected by extension. Version suffixes (`python3.12`, `lua5.4`) are ignored.
+
+use crate::file_ops::SkipReason;
+use clap::ValueEnum;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Default size limit for a single file: 1 MiB.
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Number of leading bytes inspected when deciding whether a file is binary.
+pub const SNIFF_BYTES: usize = 8 * 1024;
+
+/// Share of control characters in the sniffed bytes above which a file counts as binary.
+pub const MAX_CONTROL_RATIO: f64 = 0.1;
+
+/// Number of leading bytes read when looking for a shebang line.
+const SHEBANG_SNIFF_BYTES: u64 = 256;
+
+/// Interpreters recognised on a shebang line, with the extension their scripts usually have.
+const SHEBANG_EXTENSIONS: &[(&str, &str)] = &[
+    ("python", "py"),
+    ("sh", "sh"),
+    ("bash", "sh"),
+    ("dash", "sh"),
+    ("ksh", "sh"),
+    ("zsh", "sh"),
+    ("fish", "fish"),
+    ("node", "js"),
+    ("deno", "ts"),
+    ("ruby", "rb"),
+    ("perl", "pl"),
+    ("php", "php"),
+    ("lua", "lua"),
+    ("pwsh", "ps1"),
+    ("Rscript", "r"),
+    ("tclsh", "tcl"),
+];
+
+/// A non-UTF-8 encoding that files can be transcoded from.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ValueEnum,
+)]
+pub enum TextEncoding {
+    /// UTF-16, little-endian.
+    #[serde(rename = "utf-16le")]
+    #[value(name = "utf-16le")]
+    Utf16Le,
+    /// UTF-16, big-endian.
+    #[serde(rename = "utf-16be")]
+    #[value(name = "utf-16be")]
+    Utf16Be,
+    /// Windows-1252, the superset of Latin-1 (ISO-8859-1) used by Windows editors.
+    #[serde(rename = "windows-1252", alias = "latin1")]
+    #[value(name = "windows-1252", alias = "latin1")]
+    Windows1252,
+}
+
+impl TextEncoding {
+    /// All encodings, in the order they are listed to users.
+    pub const ALL: [TextEncoding; 3] = [
+        TextEncoding::Utf16Le,
+        TextEncoding::Utf16Be,
+        TextEncoding::Windows1252,
+    ];
+
+    /// Display name, as used in summaries.
+    pub fn label(self) -> &'static str {
+        match self {
+            TextEncoding::Utf16Le => "UTF-16LE",
+            TextEncoding::Utf16Be => "UTF-16BE",
+            TextEncoding::Windows1252 => "Windows-1252",
+        }
+    }
+
+    fn encoding(self) -> &'static Encoding {
+        match self {
+            TextEncoding::Utf16Le => UTF_16LE,
+            TextEncoding::Utf16Be => UTF_16BE,
+            TextEncoding::Windows1252 => WINDOWS_1252,
+        }
+    }
+}
+
+/// Contents of a text file, as UTF-8.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextFile {
+    pub contents: String,
+    /// Encoding the contents were transcoded from; `None` for UTF-8 files.
+    pub encoding: Option<TextEncoding>,
+}
+
+/// Reads `path` as text, or returns why it must be skipped.
+///
+/// `max_file_size` is the largest accepted size in bytes (`None` accepts any size), and
+/// `fallback_encodings` are tried in order when a file without a byte order mark is not UTF-8.
+pub fn read_text_file(
+    path: &Path,
+    max_file_size: Option<u64>,
+    fallback_encodings: &[TextEncoding],
+) -> Result<TextFile, SkipReason> {
+    decode_text(&read_file_bytes(path, max_file_size)?, fallback_encodings)
+}
+
+/// Reads the raw bytes of `path`, or returns why it must be skipped.
+///
+/// `max_file_size` is the largest accepted size in bytes (`None` accepts any size).
+pub fn read_file_bytes(path: &Path, max_file_size: Option<u64>) -> Result<Vec<u8>, SkipReason> {
+    let unreadable = |error: std::io::Error| SkipReason::Unreadable {
+        error: error.to_string(),
+    };
+
+    if let Some(limit) = max_file_size {
+        let size = std::fs::metadata(path).map_err(unreadable)?.len();
+        if size > limit {
+            return Err(SkipReason::TooLarge { size, limit });
+        }
+    }
+
+    std::fs::read(path).map_err(unreadable)
+}
+
+/// Decodes file contents as described in the module docs.
+pub fn decode_text(
+    bytes: &[u8],
+    fallback_encodings: &[TextEncoding],
+) -> Result<TextFile, SkipReason> {
+    let text_file = |contents: String, encoding| {
+        if looks_binary(sample(contents.as_bytes())) {
+            Err(SkipReason::Binary)
+        } else {
+            Ok(TextFile { contents, encoding })
+        }
+    };
+
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        let contents = encoding
+            .decode_without_bom_handling_and_without_replacement(&bytes[bom_length..])
+            .ok_or(SkipReason::NotUtf8)?;
+        let transcoded = TextEncoding::ALL
+            .into_iter()
+            .find(|text_encoding| encoding != UTF_8 && text_encoding.encoding() == encoding);
+        return text_file(contents.into_owned(), transcoded);
+    }
+
+    if let Ok(contents) = std::str::from_utf8(bytes) {
+        return text_file(contents.to_string(), None);
+    }
+    for &fallback in fallback_encodings {
+        if let Some(contents) = fallback
+            .encoding()
+            .decode_without_bom_handling_and_without_replacement(bytes)
+        {
+            if let Ok(text_file) = text_file(contents.into_owned(), Some(fallback)) {
+                return Ok(text_file);
+            }
+        }
+    }
+
+    if looks_binary(sample(bytes)) {
+        Err(SkipReason::Binary)
+    } else {
+        Err(SkipReason::NotUtf8)
+    }
+}
+
+/// Returns the extension matching the interpreter on the first line of `path`, if it starts with
+/// a recognised `#!` line.
+pub fn shebang_extension(path: &Path) -> Option<&'static str> {
+    let mut head = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(SHEBANG_SNIFF_BYTES).read_to_end(&mut head))
+        .ok()?;
+    let line = head
+        .strip_prefix(b"#!")?
+        .split(|&byte| byte == b'\n')
+        .next()?;
+    let line = std::str::from_utf8(line).ok()?;
+
+    let mut words = line.split_whitespace();
+    let mut interpreter = program_name(words.next()?);
+    if interpreter == "env" {
+        interpreter = program_name(words.find(|word| !word.starts_with('-'))?);
+    }
+    let interpreter = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+
+    SHEBANG_EXTENSIONS
+        .iter()
+        .find(|(name, _)| *name == interpreter)
+        .map(|(_, extension)| *extension)
+}
+
+/// Returns the last component of an interpreter path such as `/usr/bin/python3`.
+fn program_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn sample(bytes: &[u8]) -> &[u8] {
+    &bytes[..bytes.len().min(SNIFF_BYTES)]
+}
+
+/// Returns `true` if `sample`, the start of a file, looks like binary data.
+pub fn looks_binary(sample: &[u8]) -> bool {
+    if sample.contains(&0) {
+        return true;
+    }
+
+    let control = sample.iter().filter(|&&byte| is_control_byte(byte)).count();
+    !sample.is_empty() && control as f64 / sample.len() as f64 > MAX_CONTROL_RATIO
+}
+
+fn is_control_byte(byte: u8) -> bool {
+    matches!(byte, 0x00..=0x08 | 0x0E..=0x1A | 0x1C..=0x1F | 0x7F)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn utf16(text: &str, little_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| {
+                if little_endian {
+                    unit.to_le_bytes()
+                } else {
+                    unit.to_be_bytes()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sniffing_flags_nul_bytes_and_control_characters() {
+        assert!(!looks_binary(
+            b"fn main() {\n\tprintln!(\"\x1b[1mhi\");\r\n}\n"
+        ));
+        assert!(!looks_binary("caf\u{e9} \u{2014} na\u{ef}ve".as_bytes()));
+        assert!(!looks_binary(b""));
+        assert!(looks_binary(b"PK\x03\x04\x14\x00\x00\x00"));
+        assert!(looks_binary(b"abc\x01\x02\x03\x04def"));
+    }
+
+    #[test]
+    fn read_text_file_reports_why_a_file_is_skipped() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let text = temp.path().join("text.rs");
+        let binary = temp.path().join("image.rs");
+        let latin1 = temp.path().join("latin1.rs");
+        fs::write(&text, "fn main() {}\n")?;
+        fs::write(&binary, [0x89, b'P', b'N', b'G', 0, 0, 0, 0x0D])?;
+        fs::write(&latin1, b"caf\xe9\n")?;
+
+        assert_eq!(
+            read_text_file(&text, None, &[]).map(|file| file.contents),
+            Ok("fn main() {}\n".to_string())
+        );
+        assert_eq!(
+            read_text_file(&text, Some(5), &[]),
+            Err(SkipReason::TooLarge { size: 13, limit: 5 })
+        );
+        assert_eq!(read_text_file(&binary, None, &[]), Err(SkipReason::Binary));
+        assert_eq!(read_text_file(&latin1, None, &[]), Err(SkipReason::NotUtf8));
+        assert!(matches!(
+            read_text_file(&temp.path().join("missing.rs"), None, &[]),
+            Err(SkipReason::Unreadable { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn shebang_interpreters_map_to_extensions() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let cases = [
+            ("#!/usr/bin/env python3\nprint()\n", Some("py")),
+            ("#!/usr/bin/env -S python3.12 -u\n", Some("py")),
+            ("#! /bin/bash -e\n", Some("sh")),
+            ("#!/usr/local/bin/node", Some("js")),
+            ("#!/usr/bin/lua5.4\n", Some("lua")),
+            ("#!/usr/bin/env unknown-tool\n", None),
+            ("# not a shebang\n", None),
+        ];
+        for (index, (contents, extension)) in cases.into_iter().enumerate() {
+            let path = temp.path().join(format!("script{index}"));
+            fs::write(&path, contents)?;
+            assert_eq!(shebang_extension(&path), extension, "{contents:?}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn byte_order_marks_select_the_encoding() {
+        let mut utf16le = vec![0xFF, 0xFE];
+        utf16le.extend(utf16("caf\u{e9}\r\n", true));
+        let mut utf16be = vec![0xFE, 0xFF];
+        utf16be.extend(utf16("caf\u{e9}\r\n", false));
+
+        for (bytes, encoding) in [
+            (utf16le, Some(TextEncoding::Utf16Le)),
+            (utf16be, Some(TextEncoding::Utf16Be)),
+            (b"\xEF\xBB\xBFcaf\xC3\xA9\r\n".to_vec(), None),
+        ] {
+            assert_eq!(
+                decode_text(&bytes, &[]),
+                Ok(TextFile {
+                    contents: "caf\u{e9}\r\n".to_string(),
+                    encoding,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn fallbacks_are_tried_in_order_and_must_look_like_text() {
+        let windows_1252 = b"\x93quoted\x94 caf\xe9\n";
+        assert_eq!(
+            decode_text(windows_1252, &[TextEncoding::Windows1252]),
+            Ok(TextFile {
+                contents: "\u{201c}quoted\u{201d} caf\u{e9}\n".to_string(),
+                encoding: Some(TextEncoding::Windows1252),
+            })
+        );
+
+        // Windows-1252 decodes any bytes, so a BOM-less UTF-16 fallback must be listed first.
+        let utf16le = utf16("na\u{ef}ve\n", true);
+        let fallbacks = [TextEncoding::Utf16Le, TextEncoding::Windows1252];
+        assert_eq!(
+            decode_text(&utf16le, &fallbacks).map(|file| file.encoding),
+            Ok(Some(TextEncoding::Utf16Le))
+        );
+        assert_eq!(
+            decode_text(&utf16le, &[TextEncoding::Windows1252]),
+            Err(SkipReason::Binary)
+        );
+        assert_eq!(
+            decode_text(b"\x00\x01\x02\x03\x04", &fallbacks),
+            Err(SkipReason::Binary)
+        );
+    }
+}
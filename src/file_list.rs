@@ -0,0 +1,132 @@
+//! # File List Module
+//!
+//! Reads an explicit list of files to wrap instead of scanning the root folder, for scripts that
+//! already know which files matter (`rg -l`, a compiler error list, a failing test).
+//!
+//! # Format
+//! One path per line, relative to the root folder; absolute paths inside the root are accepted
+//! too. Blank lines are ignored and a trailing `\r` is removed, so lists written on Windows work.
+//!
+//! # Notes
+//! - Files are written in list order; repeated entries are written once, at their first position.
+//! - Every entry must name an existing file inside the root after `..` and symlinks are resolved.
+//! - File type, folder, ignore, and pattern filters do not apply to listed files; the binary and
+//!   size checks in `content` still do when the files are read.
+
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Source name that reads the list from standard input.
+pub const STDIN_SOURCE: &str = "-";
+
+/// Reads a file list from `source`, or from standard input when it is [`STDIN_SOURCE`].
+///
+/// # Errors
+/// Returns a message when the list cannot be read.
+pub fn read_file_list(source: &Path) -> Result<Vec<PathBuf>, String> {
+    let text = if source == Path::new(STDIN_SOURCE) {
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|error| format!("Could not read the file list from stdin: {error}"))?;
+        text
+    } else {
+        std::fs::read_to_string(source).map_err(|error| {
+            format!(
+                "Could not read the file list '{}': {error}",
+                source.display()
+            )
+        })?
+    };
+    Ok(parse_file_list(&text))
+}
+
+/// Splits list text into paths, one per non-blank line.
+pub fn parse_file_list(text: &str) -> Vec<PathBuf> {
+    text.lines()
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .filter(|line| !line.trim().is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// Resolves a listed `entry` to its path relative to the root folder.
+///
+/// `canonical_root` is the canonical form of the root folder; relative entries are resolved
+/// against it.
+///
+/// # Errors
+/// Returns a message when the entry does not name an existing file, or resolves to a path outside
+/// the root.
+pub fn resolve_listed_file(canonical_root: &Path, entry: &Path) -> Result<PathBuf, String> {
+    let canonical = canonical_root
+        .join(entry)
+        .canonicalize()
+        .ok()
+        .filter(|path| path.is_file())
+        .ok_or_else(|| {
+            format!(
+                "Listed file '{}' does not exist or is not a file.",
+                entry.display()
+            )
+        })?;
+
+    canonical
+        .strip_prefix(canonical_root)
+        .map(Path::to_path_buf)
+        .map_err(|_| {
+            format!(
+                "Listed file '{}' is outside '{}'.",
+                entry.display(),
+                canonical_root.display()
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn lists_skip_blank_lines_and_carriage_returns() {
+        assert_eq!(
+            parse_file_list("src/main.rs\r\n\n  \nREADME.md\nsrc/main.rs"),
+            vec![
+                PathBuf::from("src/main.rs"),
+                PathBuf::from("README.md"),
+                PathBuf::from("src/main.rs"),
+            ]
+        );
+    }
+
+    #[test]
+    fn listed_files_must_exist_inside_the_root() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let root = temp.path().join("project");
+        fs::create_dir_all(root.join("src"))?;
+        fs::write(root.join("src").join("main.rs"), "fn main() {}")?;
+        fs::write(temp.path().join("secret.txt"), "secret")?;
+        let root = root.canonicalize()?;
+
+        assert_eq!(
+            resolve_listed_file(&root, Path::new("src/../src/main.rs")),
+            Ok(Path::new("src").join("main.rs"))
+        );
+        assert_eq!(
+            resolve_listed_file(&root, &root.join("src").join("main.rs")),
+            Ok(Path::new("src").join("main.rs"))
+        );
+        assert!(resolve_listed_file(&root, Path::new("../secret.txt"))
+            .expect_err("outside the root")
+            .contains("is outside"));
+        assert!(resolve_listed_file(&root, Path::new("src/missing.rs"))
+            .expect_err("missing file")
+            .contains("does not exist"));
+        assert!(resolve_listed_file(&root, Path::new("src"))
+            .expect_err("folder")
+            .contains("is not a file"));
+        Ok(())
+    }
+}
@@ -6,20 +6,33 @@
 //!
 //! # Features
 //! - Recursively or non-recursively scan directories.
-//! - Filter files by allowed extensions.
+//! - Filter files by allowed extensions, exact file names or file-name globs, and optionally by
+//!   the interpreter on a script's shebang line.
 //! - Skip hidden or user-specified folders.
-//! - Wrap file contents in XML-style tags based on relative path.
-//! - Append instructional or command-based sections to whichever output file the caller supplies.
+//! - Skip binary files and files over a size limit (see `content`).
+//! - Transcode UTF-16 and, optionally, other legacy encodings to UTF-8 (see `content`).
+//! - Optionally honor `.gitignore`, `.ignore`, and git exclude files (see `ignore_rules`).
+//! - Filter files with include/exclude glob patterns on their relative path (see `patterns`).
+//! - Never include the output file itself and, optionally, any earlier wrapper output.
+//! - Write files in a deterministic, configurable order (see `ordering`).
+//! - Keep the output within an optional token/byte budget (see `budget`).
+//! - Optionally split the output into numbered parts of limited size (see `chunks`).
+//! - Optionally write a directory tree overview before the files (see `tree`).
+//! - Optionally number every line of file contents (see `formats::number_lines`).
+//! - Optionally write per-file metadata such as size, hash, and last commit (see `metadata`).
+//! - Render file contents, footer, and additional commands in the requested format (see `formats`).
 //!
 //! # Key Functions
-//! - [`write_folder_tags`]: Creates/overwrites a caller-selected tagged output file.
-//! - [`write_folder_tags_recursive`]: Internal recursive helper for deep directory traversal.
-//! - [`append_additional_commands`]: Appends extra user-defined command blocks to a caller-selected file.
+//! - [`write_roots`]: Creates/overwrites a caller-selected tagged output file from one or more
+//!   root folders.
+//! - [`collect_matching_files`]: Scans a folder and returns the files that pass every filter.
+//! - [`collect_files_recursive`]: Internal recursive helper for deep directory traversal.
 //! - [`is_human_readable`]: Checks if a file has an allowed extension.
 //!
 //! # Output Behavior
 //! - Creates (or overwrites) the output file requested by the caller.
-//! - Each file is written in the format:
+//! - The first line is always [`OUTPUT_MARKER`] so later scans can recognise generated files.
+//! - With the default tags format, each file is written as:
 //!   ```xml
 //!   <relative\path\to\file.rs>
 //!   // file content
@@ -27,15 +40,223 @@
 //!   ```
 //!
 //! # Notes
-//! - File paths are rendered in Windows-style even on other platforms.
-//! - UTF-8 file reading is assumed; non-UTF8 files are skipped with a warning.
+//! - File paths are rendered with the separator chosen by `RenderOptions::path_style`.
+//! - Files are read as UTF-8 unless a byte order mark or `ScanOptions::fallback_encodings` says
+//!   otherwise; files that decode with none of them are skipped with a warning, and transcoded
+//!   files are listed in [`WriteFolderTagsSummary::transcoded_files`].
+//! - Files that look binary or exceed `ScanOptions::max_file_size` are skipped and listed in
+//!   [`WriteFolderTagsSummary::rejected_files`].
 
+use crate::budget::{apply_budget, DroppedFile, OutputBudget, TruncatedFile};
+use crate::chunks::{part_options, part_path, plan_chunks, ChunkLimit};
+use crate::content::{decode_text, read_file_bytes, shebang_extension, TextEncoding, TextFile};
+use crate::file_list::resolve_listed_file;
+use crate::filetypes::{extension_matches, has_extension};
+use crate::formats::{
+    number_lines, project_name, OutputPart, RenderContext, RenderOptions, RenderedFile,
+    OUTPUT_MARKER,
+};
+use crate::git::{last_commits, selected_files, unified_diff, DiffBase, GitFiles, GitSelection};
+use crate::ignore_rules::IgnoreRules;
+use crate::metadata::{FileMetadata, MetadataField};
+use crate::ordering::{sort_files, FileOrder};
+use crate::patterns::{
+    FileNamePatterns, PathPatterns, PatternDecision, PatternExclusion, PriorityPatterns,
+};
+use crate::tokens::{FileTokens, Tokenizer};
+use crate::tree::{DirectoryTree, TreeMark, TreeOptions};
+use std::collections::{HashMap, HashSet};
 use std::fs::{read_dir, File};
-use std::io::{ErrorKind, Write};
-use std::path::Path;
+use std::io::{BufWriter, Error, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Number of leading bytes inspected when looking for [`OUTPUT_MARKER`].
+const MARKER_SNIFF_BYTES: u64 = 512;
+
+/// Traversal rules that decide which files a folder scan visits and includes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanOptions {
+    /// Allowed file extensions, normalised and without leading dots (e.g., `["rs", "d.ts"]`).
+    pub valid_exts: Vec<String>,
+    /// Whether extensions must match case exactly, so `rs` does not select `MAIN.RS`.
+    pub strict_extension_case: bool,
+    /// Exact file names or file-name globs selected regardless of extension (e.g., `Makefile`).
+    pub filenames: Vec<String>,
+    /// Whether extensionless files are selected when their shebang interpreter maps to one of
+    /// `valid_exts` (see `content::shebang_extension`).
+    pub detect_shebangs: bool,
+    /// Files to select, in order, instead of scanning (see `file_list`); every other filter is
+    /// ignored when set.
+    pub file_list: Option<Vec<PathBuf>>,
+    /// Whether subdirectories are traversed.
+    pub recursive: bool,
+    /// Folder names (case-insensitive) skipped during recursive traversal.
+    pub ignored_folders: Vec<String>,
+    /// Whether `.gitignore`, `.ignore`, `.git/info/exclude`, and global git excludes are honored.
+    pub respect_gitignore: bool,
+    /// Limits the scan to tracked, staged, untracked, or changed files (`None` for every file).
+    pub git_selection: Option<GitSelection>,
+    /// Glob patterns a relative file path must match at least one of (ignored when empty).
+    pub include_patterns: Vec<String>,
+    /// Glob patterns that exclude any relative file path they match.
+    pub exclude_patterns: Vec<String>,
+    /// Canonical paths that are never included, such as the file currently being written.
+    pub excluded_paths: Vec<PathBuf>,
+    /// Whether files starting with [`OUTPUT_MARKER`] are skipped.
+    pub skip_previous_outputs: bool,
+    /// Order in which selected files are returned and written.
+    pub order: FileOrder,
+    /// Globs for files that are moved to the front, in the order listed.
+    pub priority_patterns: Vec<String>,
+    /// Whether the directory tree section is written, and how deep (see `tree`).
+    pub tree: TreeOptions,
+    /// Compares the working tree with this base for the diff section (`None` for no diff).
+    pub diff: Option<DiffBase>,
+    /// Folder name written before every relative path, so files from several roots stay apart
+    /// in one output (e.g. `backend` writes `src/main.rs` as `backend/src/main.rs`).
+    pub path_prefix: Option<String>,
+    /// Files larger than this many bytes are skipped without being read (`None` for no limit).
+    pub max_file_size: Option<u64>,
+    /// Encodings tried, in order, for files without a byte order mark that are not UTF-8.
+    pub fallback_encodings: Vec<TextEncoding>,
+}
+
+/// Files selected by a folder scan, in output order, with counts of pattern-filtered files.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FileSelection {
+    /// Paths of every file that passed all filters, prefixed with the scanned root.
+    pub files: Vec<PathBuf>,
+    /// Files skipped by each exclude pattern, in the order the patterns were supplied.
+    pub pattern_exclusions: Vec<PatternExclusion>,
+    /// Files skipped because include patterns were supplied and none matched.
+    pub files_not_included: usize,
+    /// Files skipped because they are the output file or an earlier wrapper output.
+    pub output_files_skipped: usize,
+    /// Every file and folder the scan passed over, with the reason, in traversal order.
+    pub skipped: Vec<SkippedPath>,
+}
+
+/// Why a scan left a file or folder out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// A folder whose name starts with `.`.
+    HiddenFolder,
+    /// A folder listed in `ignored_folders`.
+    IgnoredFolder,
+    /// A sub-folder of a non-recursive scan.
+    NotRecursive,
+    /// A file whose extension, name, and shebang match none of the selected file types.
+    ExtensionMismatch,
+    /// Excluded by `.gitignore`, `.ignore`, or git exclude files.
+    Gitignored,
+    /// Include patterns were given and none matched.
+    NotIncluded,
+    /// Matched an exclude pattern.
+    ExcludedByPattern { pattern: String },
+    /// The output file currently being written.
+    OutputFile,
+    /// An earlier wrapper output (with `skip_previous_outputs`).
+    PreviousOutput,
+    /// Contents are not valid UTF-8.
+    NotUtf8,
+    /// Contents could not be read.
+    Unreadable { error: String },
+    /// Contents look binary (see `content::looks_binary`).
+    Binary,
+    /// Larger than `ScanOptions::max_file_size`.
+    TooLarge { size: u64, limit: u64 },
+    /// Not among the files chosen by `ScanOptions::git_selection`.
+    OutsideGitSelection { selection: String },
+}
+
+impl SkipReason {
+    /// Short user-facing description.
+    pub fn label(&self) -> String {
+        match self {
+            SkipReason::HiddenFolder => "hidden folder".to_string(),
+            SkipReason::IgnoredFolder => "ignored folder".to_string(),
+            SkipReason::NotRecursive => "not recursive".to_string(),
+            SkipReason::ExtensionMismatch => "extension mismatch".to_string(),
+            SkipReason::Gitignored => "gitignored".to_string(),
+            SkipReason::NotIncluded => "no include pattern matched".to_string(),
+            SkipReason::ExcludedByPattern { pattern } => format!("excluded by '{pattern}'"),
+            SkipReason::OutputFile => "output file".to_string(),
+            SkipReason::PreviousOutput => "previous output".to_string(),
+            SkipReason::NotUtf8 => "not UTF-8".to_string(),
+            SkipReason::Unreadable { error } => format!("unreadable: {error}"),
+            SkipReason::Binary => "binary contents".to_string(),
+            SkipReason::TooLarge { size, limit } => {
+                format!("{size} bytes, over the {limit}-byte limit")
+            }
+            SkipReason::OutsideGitSelection { selection } => format!("not {selection}"),
+        }
+    }
+}
+
+/// A file or folder left out of the output, with the reason.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedPath {
+    /// Path prefixed with the scanned root.
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub reason: SkipReason,
+}
+
+/// A selected file left out after inspection because it looks binary or is too large.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedFile {
+    /// Relative path, rendered like the paths written to the output.
+    pub path: String,
+    pub reason: SkipReason,
+}
+
+/// A file whose contents were transcoded to UTF-8.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscodedFile {
+    /// Relative path, rendered like the paths written to the output.
+    pub path: String,
+    /// Encoding the file was read in.
+    pub encoding: TextEncoding,
+}
+
+/// A folder scanned into a multi-root output, with the options that apply to its files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanRoot {
+    pub dir: PathBuf,
+    /// Scan options for this folder; `path_prefix` keeps its files apart from other roots'.
+    pub options: ScanOptions,
+}
+
+/// Selected files read, measured, and fitted to the budget, ready to be rendered.
+#[derive(Debug, Default)]
+pub struct PreparedFiles {
+    /// Files to write, in output order.
+    pub files: Vec<LoadedFile>,
+    /// Counts and lists gathered while scanning, reading, and budgeting.
+    pub summary: WriteFolderTagsSummary,
+    /// Every file and folder that was passed over, including unreadable files.
+    pub skipped: Vec<SkippedPath>,
+    /// Rendered directory tree, when [`ScanOptions::tree`] enables it.
+    pub tree: Option<String>,
+    /// Unified diff, when [`ScanOptions::diff`] requests one and any selected file changed.
+    pub diff: Option<String>,
+}
+
+/// A selected file whose contents have been read and measured, ready to be rendered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadedFile {
+    /// Path prefixed with the scanned root.
+    pub path: PathBuf,
+    /// Relative path as written to the output.
+    pub display_path: String,
+    pub contents: String,
+    pub tokens: usize,
+    /// Attributes requested by `RenderOptions::metadata`, collected from the whole file.
+    pub metadata: FileMetadata,
+}
 
 /// Summary of files included and skipped while generating tagged folder output.
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct WriteFolderTagsSummary {
     /// Number of matching files whose contents were successfully written.
     pub files_written: usize,
@@ -43,24 +264,47 @@ pub struct WriteFolderTagsSummary {
     pub files_skipped: usize,
     /// Number of skipped files that failed specifically because they were not valid UTF-8.
     pub skipped_non_utf8_files: usize,
+    /// Files skipped because they look binary or exceed `ScanOptions::max_file_size`.
+    pub rejected_files: Vec<RejectedFile>,
+    /// Files read in another encoding and transcoded to UTF-8, in scan order.
+    pub transcoded_files: Vec<TranscodedFile>,
+    /// Files skipped by each exclude pattern.
+    pub pattern_exclusions: Vec<PatternExclusion>,
+    /// Files skipped because they matched no include pattern.
+    pub files_not_included: usize,
+    /// Files skipped because they are the output file or an earlier wrapper output.
+    pub output_files_skipped: usize,
+    /// Token count of each written file, in output order.
+    pub file_tokens: Vec<FileTokens>,
+    /// Files shortened to fit the output budget.
+    pub truncated_files: Vec<TruncatedFile>,
+    /// Files left out because they did not fit the output budget.
+    pub budget_dropped: Vec<DroppedFile>,
+    /// Every file written: the output path, or each numbered part when the output was split.
+    pub output_paths: Vec<PathBuf>,
 }
 
-/// Writes the contents of selected files in a directory into a tagged output file.
+/// Writes the contents of selected files in one or more directories into a tagged output file.
 ///
 /// # Purpose
-/// Collects all files matching specific extensions from a given directory, optionally recursively,
-/// and writes each file’s contents into `output_path`, wrapped in an XML-style tag that corresponds
-/// to its relative path.
+/// Collects all files matching specific extensions from the given directories, optionally
+/// recursively, and writes each file’s contents into `output_path` using the layout selected in
+/// `render` (by default, an XML-style tag that corresponds to its relative path).
 ///
 /// # Parameters
-/// - `dir`: Root directory to scan (`&Path`).
-/// - `valid_exts`: List of allowed file extensions (e.g., `["rs", "md"]`) — case-sensitive and without dots.
-/// - `recursive`: If `true`, traverses subdirectories. If `false`, only scans the top-level directory.
-/// - `ignored_folders`: List of folder names (case-insensitive) to skip during recursive traversal (e.g., `["target", ".git"]`).
-/// - `output_path`: File to create or overwrite with tagged output.
+/// - `roots`: Directories to scan, each a [`ScanRoot`] whose [`ScanOptions`] hold the allowed
+///   extensions (without dots), recursion flag, ignored folder names, gitignore handling, and
+///   include/exclude globs. The first root is the project the output describes.
+/// - `render`: [`RenderOptions`] with the output format (or template) and the additional commands text.
+/// - `budget`: [`OutputBudget`] limiting the output's tokens and bytes (see `budget`).
+/// - `chunk_limit`: [`ChunkLimit`] splitting the output into numbered parts (see `chunks`).
+/// - `tokenizer`: Counts the tokens of each written file for the summary, token-aware formats,
+///   and the budget.
+/// - `output_path`: File to create or overwrite with tagged output; split output is written to
+///   `<stem>.part1.<ext>`, `<stem>.part2.<ext>`, … next to it instead.
 ///
 /// # Output Format
-/// Each file is wrapped in tags representing its relative path:
+/// With the default tags format, each file is wrapped in tags representing its relative path:
 /// ```xml
 /// <src\main.rs>
 /// // file contents here...
@@ -73,14 +317,23 @@ pub struct WriteFolderTagsSummary {
 /// - Instructs users to carefully review appended command blocks.
 /// - Prepares the result for downstream AI-assisted editing or transformation.
 ///
+/// Non-blank `render.additional_commands` are then written as an Additional Commands section.
+///
 /// # Behavior
-/// - All matching files are assumed to be UTF-8.
+/// - Files are read as UTF-8, or transcoded when a byte order mark or one of
+///   `options.fallback_encodings` identifies another encoding.
 /// - Files with unreadable contents (non-UTF8 or access errors) are skipped with a warning to `stderr`.
-/// - Uses Windows-style `\` in tag paths, even on other operating systems.
+/// - Files over `options.max_file_size`, or whose first bytes look binary, are skipped and
+///   listed in the summary's `rejected_files`.
+/// - Writes paths with the separator chosen by `render.path_style` (native by default).
+/// - Reads every selected file before writing; when `budget` sets a limit, only the files chosen
+///   by `budget::apply_budget` are written (possibly truncated), still in scan order.
+/// - Roots are written one after another, in the order given; their files share one budget.
 ///
 /// # Errors
 /// Returns `Err(std::io::Error)` if:
-/// - The directory or any file fails to open/read.
+/// - `roots` is empty, or an include or exclude glob pattern is invalid (`ErrorKind::InvalidInput`).
+/// - A directory or any file fails to open/read.
 /// - The output file cannot be created or written.
 ///
 /// # Panics
@@ -88,58 +341,491 @@ pub struct WriteFolderTagsSummary {
 /// All filesystem I/O is handled using `?` or skipped safely.
 ///
 /// # Notes
-/// - Tag paths are relative to `dir`, even during recursion.
+/// - Tag paths are relative to their root, even during recursion, after the root's
+///   `path_prefix` when it has one.
 /// - Hidden directories (starting with `.`) are skipped automatically.
-/// - Case-insensitive folder matching is used for `ignored_folders`, but extension matching is case-sensitive.
+/// - Case-insensitive matching is used for `ignored_folders` and, unless `strict_extension_case` is
+///   set, for extensions.
+/// - When `respect_gitignore` is set, files and folders excluded by gitignore-style rules are skipped.
+/// - Include/exclude globs are evaluated against each file's relative path after the other filters.
+/// - Paths listed in `excluded_paths` (normally the output file) are never wrapped.
 ///
 /// # Example
 /// ```rust
-/// let dir = Path::new("src");
-/// let exts = vec!["rs".to_string(), "toml".to_string()];
-/// let ignored = vec!["target".to_string(), ".git".to_string()];
+/// let options = ScanOptions {
+///     valid_exts: vec!["rs".to_string(), "toml".to_string()],
+///     strict_extension_case: false,
+///     filenames: vec!["Makefile".to_string()],
+///     detect_shebangs: true,
+///     file_list: None,
+///     recursive: true,
+///     ignored_folders: vec!["target".to_string(), ".git".to_string()],
+///     respect_gitignore: true,
+///     git_selection: Some(GitSelection::Tracked),
+///     include_patterns: Vec::new(),
+///     exclude_patterns: vec!["src/generated/**".to_string()],
+///     excluded_paths: vec![canonical_output_path(Path::new("project_context.txt"))],
+///     skip_previous_outputs: true,
+///     order: FileOrder::Path,
+///     priority_patterns: vec!["Cargo.toml".to_string()],
+///     tree: TreeOptions::default(),
+///     diff: Some(DiffBase::Head),
+///     path_prefix: Some("backend".to_string()),
+///     max_file_size: Some(DEFAULT_MAX_FILE_SIZE),
+///     fallback_encodings: vec![TextEncoding::Windows1252],
+/// };
+/// let render = RenderOptions {
+///     format: OutputFormat::Markdown,
+///     path_style: PathStyle::ForwardSlash,
+///     text: OutputText::default(),
+///     template: None,
+///     tree: None,
+///     diff: None,
+///     metadata: vec![MetadataField::Sha256],
+///     line_numbers: false,
+///     preset_texts: Vec::new(),
+///     additional_commands: "TODO: Review all unwrap() usages.".to_string(),
+/// };
 /// let output_path = Path::new("project_context.txt");
-/// write_folder_tags(dir, &exts, true, &ignored, output_path)?;
+/// let root = ScanRoot {
+///     dir: PathBuf::from("src"),
+///     options,
+/// };
+/// write_roots(
+///     &[root],
+///     &render,
+///     &OutputBudget::default(),
+///     &ChunkLimit::default(),
+///     &HeuristicTokenizer,
+///     output_path,
+/// )?;
 /// ```
-pub fn write_folder_tags(
-    dir: &Path,
-    valid_exts: &[String],
-    recursive: bool,
-    ignored_folders: &[String],
+pub fn write_roots(
+    roots: &[ScanRoot],
+    render: &RenderOptions,
+    budget: &OutputBudget,
+    chunk_limit: &ChunkLimit,
+    tokenizer: &dyn Tokenizer,
     output_path: &Path,
 ) -> std::io::Result<WriteFolderTagsSummary> {
-    let mut output = File::create(output_path)?;
-    let mut summary = WriteFolderTagsSummary::default();
+    let dir = primary_root(roots)?.dir.as_path();
+    let PreparedFiles {
+        files,
+        mut summary,
+        tree,
+        diff,
+        ..
+    } = prepare_roots(roots, render, budget, tokenizer)?;
+    let render = &RenderOptions {
+        tree,
+        diff,
+        ..render.clone()
+    };
 
-    if recursive {
-        write_folder_tags_recursive(
-            dir,
+    let parts = plan_chunks(dir, files, chunk_limit, render, tokenizer)?;
+    let count = parts.len();
+    for (index, files) in parts.iter().enumerate() {
+        let part = OutputPart {
+            index: index + 1,
+            count,
+        };
+        let path = if count == 1 {
+            output_path.to_path_buf()
+        } else {
+            part_path(output_path, part.index)
+        };
+        let part_render = part_options(render, part);
+        let context = RenderContext {
+            root_dir: dir,
+            options: &part_render,
+            part: (count > 1).then_some(part),
+        };
+        write_document(&path, files, &context)?;
+        summary.output_paths.push(path);
+    }
+    // A single-file run leaves no part of its own, so every earlier part is stale.
+    remove_stale_parts(output_path, if count > 1 { count + 1 } else { 1 });
+
+    Ok(summary)
+}
+
+/// Runs every step of [`write_roots`] that decides what is written, without writing.
+///
+/// Scans each root, reads and measures its selected files, and applies `budget` to the files of
+/// all roots together. Unreadable files are reported with a warning on `stderr`, counted in the
+/// summary, and listed in [`PreparedFiles::skipped`] alongside everything the scans passed over.
+/// Binary and oversized files are listed there too, and in the summary's `rejected_files`. The
+/// directory tree holds one top-level folder per root.
+///
+/// # Errors
+/// - `ErrorKind::InvalidInput` if `roots` is empty, or an include, exclude, or priority pattern is
+///   not a valid glob.
+/// - Any error raised while reading a directory or measuring rendered output.
+pub fn prepare_roots(
+    roots: &[ScanRoot],
+    render: &RenderOptions,
+    budget: &OutputBudget,
+    tokenizer: &dyn Tokenizer,
+) -> std::io::Result<PreparedFiles> {
+    let primary = primary_root(roots)?;
+    let mut prepared = PreparedFiles::default();
+    let mut loaded = Vec::new();
+    let mut file_roots = Vec::new();
+    let mut trees = Vec::new();
+    let mut diff = String::new();
+
+    for (index, root) in roots.iter().enumerate() {
+        let (dir, options) = (root.dir.as_path(), &root.options);
+        let selection = collect_matching_files(dir, options)?;
+        if let Some(root_diff) = selection_diff(dir, options, &selection.files)? {
+            diff.push_str(&root_diff);
+        }
+        let summary = &mut prepared.summary;
+        add_pattern_exclusions(
+            &mut summary.pattern_exclusions,
+            selection.pattern_exclusions,
+        );
+        summary.files_not_included += selection.files_not_included;
+        summary.output_files_skipped += selection.output_files_skipped;
+        let first_skipped = prepared.skipped.len();
+        prepared.skipped.extend(selection.skipped);
+
+        let root_loaded = load_files(
             dir,
-            valid_exts,
+            &selection.files,
+            options,
+            render,
+            tokenizer,
+            &mut prepared,
+        );
+        trees.push(
+            options
+                .tree
+                .enabled
+                .then(|| directory_tree(dir, &root_loaded, &prepared.skipped[first_skipped..])),
+        );
+        file_roots.extend(std::iter::repeat_n(index, root_loaded.len()));
+        loaded.extend(root_loaded);
+    }
+    prepared.diff = (!diff.is_empty()).then_some(diff);
+
+    // Marks share one width, so the tree measured before budgeting is exactly as large as the
+    // tree written afterwards.
+    let render = &RenderOptions {
+        tree: render_trees(roots, &trees),
+        diff: prepared.diff.clone(),
+        ..render.clone()
+    };
+    let context = RenderContext {
+        root_dir: &primary.dir,
+        options: render,
+        part: None,
+    };
+
+    let loaded_paths: Vec<(String, usize, PathBuf)> = loaded
+        .iter()
+        .zip(file_roots)
+        .map(|(file, root)| (file.display_path.clone(), root, file.path.clone()))
+        .collect();
+    let priority = PriorityPatterns::compile(&primary.options.priority_patterns)
+        .map_err(|message| Error::new(ErrorKind::InvalidInput, message))?;
+    let root_dirs: Vec<&Path> = roots.iter().map(|root| root.dir.as_path()).collect();
+    let outcome = apply_budget(&root_dirs, loaded, budget, &context, &priority, tokenizer)?;
+
+    let mut mark_file = |display_path: &str, mark: TreeMark| {
+        let Some((_, root, path)) = loaded_paths.iter().find(|(path, ..)| path == display_path)
+        else {
+            return;
+        };
+        if let (Some(tree), Ok(relative_path)) =
+            (trees[*root].as_mut(), path.strip_prefix(&roots[*root].dir))
+        {
+            tree.set_file(relative_path, mark);
+        }
+    };
+    for truncated in &outcome.truncated {
+        mark_file(&truncated.path, TreeMark::Truncated);
+    }
+    for dropped in &outcome.dropped {
+        mark_file(&dropped.path, TreeMark::Filtered);
+    }
+    prepared.tree = render_trees(roots, &trees);
+
+    let summary = &mut prepared.summary;
+    summary.truncated_files = outcome.truncated;
+    summary.budget_dropped = outcome.dropped;
+    summary.files_written = outcome.kept.len();
+    summary.file_tokens = outcome
+        .kept
+        .iter()
+        .map(|file| FileTokens {
+            path: file.display_path.clone(),
+            tokens: file.tokens,
+        })
+        .collect();
+    prepared.files = outcome.kept;
+
+    Ok(prepared)
+}
+
+/// Returns the first of `roots`, whose folder the output describes.
+fn primary_root(roots: &[ScanRoot]) -> std::io::Result<&ScanRoot> {
+    roots
+        .first()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "No folder to scan."))
+}
+
+/// Adds the per-pattern counts of one root's scan to the running totals.
+///
+/// Counts are matched by pattern, so roots with different exclude lists are totalled correctly;
+/// patterns first seen in a later root are appended in that root's order.
+fn add_pattern_exclusions(totals: &mut Vec<PatternExclusion>, root: Vec<PatternExclusion>) {
+    for exclusion in root {
+        match totals
+            .iter_mut()
+            .find(|total| total.pattern == exclusion.pattern)
+        {
+            Some(total) => total.files_excluded += exclusion.files_excluded,
+            None => totals.push(exclusion),
+        }
+    }
+}
+
+/// Renders the directory tree of each root that has one, one after another, under the root's
+/// path prefix or folder name. Returns `None` when no root has a tree.
+fn render_trees(roots: &[ScanRoot], trees: &[Option<DirectoryTree>]) -> Option<String> {
+    let rendered: Vec<String> = roots
+        .iter()
+        .zip(trees)
+        .filter_map(|(root, tree)| {
+            let name = root
+                .options
+                .path_prefix
+                .clone()
+                .unwrap_or_else(|| project_name(&root.dir));
+            Some(tree.as_ref()?.render(&name, root.options.tree.max_depth))
+        })
+        .collect();
+    (!rendered.is_empty()).then(|| rendered.concat())
+}
+
+/// Renders the diff requested by `options.diff`, limited to the `selected` files and to deleted
+/// files that would have passed the scan's filters. Returns `None` when none of them changed.
+///
+/// # Errors
+/// Any error reported by `git::unified_diff`, such as `dir` not being inside a repository.
+fn selection_diff(
+    dir: &Path,
+    options: &ScanOptions,
+    selected: &[PathBuf],
+) -> std::io::Result<Option<String>> {
+    let Some(base) = &options.diff else {
+        return Ok(None);
+    };
+    let filters = FileFilters::compile(options)?;
+    let selected: HashSet<&Path> = selected
+        .iter()
+        .filter_map(|path| path.strip_prefix(dir).ok())
+        .collect();
+
+    let diff = unified_diff(dir, base, options.path_prefix.as_deref(), |relative_path| {
+        selected.contains(relative_path)
+            || (!dir.join(relative_path).exists()
+                && deleted_file_matches(dir, relative_path, options, &filters))
+    })
+    .map_err(Error::other)?;
+    Ok((!diff.is_empty()).then_some(diff))
+}
+
+/// Returns `true` if a deleted file would have passed the folder, file type, and include/exclude
+/// filters of a scan. Ignore rules and git selections are not consulted.
+fn deleted_file_matches(
+    dir: &Path,
+    relative_path: &Path,
+    options: &ScanOptions,
+    filters: &FileFilters,
+) -> bool {
+    let folders: Vec<_> = relative_path
+        .parent()
+        .map(|parent| parent.components().collect())
+        .unwrap_or_default();
+    if !options.recursive && !folders.is_empty() {
+        return false;
+    }
+    let skipped_folder = folders.iter().any(|folder| {
+        let name = folder.as_os_str().to_string_lossy();
+        name.starts_with('.')
+            || options
+                .ignored_folders
+                .iter()
+                .any(|ignored| ignored.eq_ignore_ascii_case(&name))
+    });
+
+    !skipped_folder
+        && matches_file_type(&dir.join(relative_path), options, &filters.filenames)
+        && filters.patterns.evaluate(relative_path) == PatternDecision::Included
+}
+
+/// Builds the directory tree from the files that were read and the paths the scan passed over.
+///
+/// Files and folders skipped by ignore rules, and earlier outputs, are left out of the tree.
+fn directory_tree(dir: &Path, loaded: &[LoadedFile], skipped: &[SkippedPath]) -> DirectoryTree {
+    let mut tree = DirectoryTree::default();
+    for file in loaded {
+        if let Ok(relative_path) = file.path.strip_prefix(dir) {
+            tree.set_file(relative_path, TreeMark::Included);
+        }
+    }
+    for skipped in skipped {
+        let Ok(relative_path) = skipped.path.strip_prefix(dir) else {
+            continue;
+        };
+        match skipped.reason {
+            SkipReason::NotRecursive => tree.add_unscanned_folder(relative_path),
+            SkipReason::ExtensionMismatch
+            | SkipReason::NotIncluded
+            | SkipReason::ExcludedByPattern { .. }
+            | SkipReason::NotUtf8
+            | SkipReason::Unreadable { .. }
+            | SkipReason::Binary
+            | SkipReason::TooLarge { .. }
+            | SkipReason::OutsideGitSelection { .. } => {
+                tree.set_file(relative_path, TreeMark::Filtered)
+            }
+            SkipReason::HiddenFolder
+            | SkipReason::IgnoredFolder
+            | SkipReason::Gitignored
+            | SkipReason::OutputFile
+            | SkipReason::PreviousOutput => {}
+        }
+    }
+    tree
+}
+
+/// Renders one complete document containing `files` to `path`.
+fn write_document(
+    path: &Path,
+    files: &[LoadedFile],
+    context: &RenderContext<'_>,
+) -> std::io::Result<()> {
+    let mut output = BufWriter::new(File::create(path)?);
+    let mut renderer = context.options.renderer();
+    renderer.begin(&mut output, context)?;
+    for file in files {
+        renderer.write_file(
             &mut output,
-            ignored_folders,
-            &mut summary,
+            &RenderedFile {
+                path: &file.display_path,
+                contents: &file.contents,
+                tokens: file.tokens,
+                metadata: &file.metadata,
+            },
         )?;
-    } else {
-        for entry in read_dir(dir)? {
-            let entry = entry?;
-            let path = entry.path();
+    }
 
-            if path.is_file() && is_human_readable(&path, valid_exts) {
-                write_tagged_file(dir, &path, &mut output, &mut summary)?;
-            }
+    // Append instructional text and additional commands
+    renderer.finish(&mut output, context)?;
+    output.flush()
+}
+
+/// Deletes parts left over from an earlier run that produced more parts (or any parts, when this
+/// run wrote a single file), starting at `first_index`.
+///
+/// Only files that start with [`OUTPUT_MARKER`] are removed, so unrelated files are never touched.
+fn remove_stale_parts(output_path: &Path, first_index: usize) {
+    let mut index = first_index;
+    loop {
+        let path = part_path(output_path, index);
+        if !path.is_file() || !is_previous_output(&path) {
+            break;
+        }
+        if let Err(error) = std::fs::remove_file(&path) {
+            eprintln!("⚠️ Could not remove stale part {:?}: {}", path, error);
+            break;
         }
+        index += 1;
+    }
+}
+
+/// Scans `dir` and returns the files that pass every filter in `options`, without reading them.
+///
+/// # Behavior
+/// - Visits only the top-level directory unless `options.recursive` is set.
+/// - Applies, in order: hidden/ignored folder names, extension filter, gitignore-style rules
+///   (when enabled), the git selection (when set), include/exclude glob patterns, then
+///   output-file detection.
+/// - Only files that pass the extension filter are counted against include/exclude patterns.
+/// - Sorts the result by priority globs, then `options.order`, so output never depends on the
+///   order in which the filesystem lists directory entries.
+/// - With `options.file_list`, no folder is scanned: exactly the listed files are returned, in
+///   list order, minus the output file (see `file_list`).
+///
+/// # Errors
+/// - `ErrorKind::InvalidInput` if a file name, include, exclude, or priority pattern is not a valid
+///   glob, or a listed file does not exist or lies outside `dir`.
+/// - Any error raised while reading a directory.
+pub fn collect_matching_files(dir: &Path, options: &ScanOptions) -> std::io::Result<FileSelection> {
+    if let Some(file_list) = &options.file_list {
+        return select_listed_files(dir, file_list, &options.excluded_paths);
     }
 
-    // Append instructional text
-    writeln!(output, "* The above is the current state of my project.")?;
-    writeln!(output, "* Each node above is an XML-wrapped code snippet using relative Windows-style file path tags.")?;
-    writeln!(output, "* Provide context above and below code changes to be explicit on where any change should occur.")?;
-    writeln!(
-        output,
-        "* Under text under [Additional Commands] should be read very carefully and followed absolutely"
+    let mut filters = FileFilters::compile(options)?;
+    let priority = PriorityPatterns::compile(&options.priority_patterns)
+        .map_err(|message| Error::new(ErrorKind::InvalidInput, message))?;
+    filters.git_files = options
+        .git_selection
+        .as_ref()
+        .map(|git_selection| selected_files(dir, git_selection))
+        .transpose()
+        .map_err(Error::other)?;
+    let mut ignore_rules = options.respect_gitignore.then(|| IgnoreRules::load(dir));
+    let mut selection = FileSelection {
+        pattern_exclusions: filters.patterns.empty_exclusions(),
+        ..FileSelection::default()
+    };
+
+    collect_files_recursive(
+        dir,
+        dir,
+        options,
+        &filters,
+        &mut ignore_rules,
+        &mut selection,
     )?;
+    sort_files(dir, &mut selection.files, options.order, &priority);
 
-    Ok(summary)
+    Ok(selection)
+}
+
+/// Returns the files named in `file_list`, prefixed with `dir`, in list order and without repeats.
+///
+/// The output file (one of `excluded_paths`) is skipped and counted like in a scan; every other
+/// entry must resolve to a file inside `dir`.
+fn select_listed_files(
+    dir: &Path,
+    file_list: &[PathBuf],
+    excluded_paths: &[PathBuf],
+) -> std::io::Result<FileSelection> {
+    let canonical_root = dir.canonicalize()?;
+    let mut selection = FileSelection::default();
+    let mut seen = HashSet::new();
+
+    for entry in file_list {
+        let relative_path = resolve_listed_file(&canonical_root, entry)
+            .map_err(|message| Error::new(ErrorKind::InvalidInput, message))?;
+        if !seen.insert(relative_path.clone()) {
+            continue;
+        }
+        let path = dir.join(relative_path);
+        if is_excluded_path(&path, excluded_paths) {
+            selection.output_files_skipped += 1;
+            skip(&mut selection, path, false, SkipReason::OutputFile);
+        } else {
+            selection.files.push(path);
+        }
+    }
+
+    Ok(selection)
 }
 
 /// Determines whether a file should be processed based on its extension.
@@ -150,27 +836,29 @@ pub fn write_folder_tags(
 ///
 /// # Parameters
 /// - `path`: A reference to the [`Path`] representing the file to evaluate.
-/// - `valid_exts`: A slice of valid file extensions (`&[String]`), such as `["rs", "json", "toml"]`.
-///   Extensions **must not** include leading dots (e.g., `"rs"`, not `".rs"`).
+/// - `valid_exts`: A slice of normalised file extensions (`&[String]`), such as `["rs", "d.ts"]`.
+///   Extensions **must not** include leading dots (e.g., `"rs"`, not `".rs"`); see
+///   [`crate::filetypes::normalize_extension`].
+/// - `strict_case`: Whether the file name must use the extension's exact case.
 ///
 /// # Returns
-/// - `true` if the file has a valid extension (case-sensitive match).
+/// - `true` if the file name ends in `.` followed by one of `valid_exts`.
 /// - `false` if:
-///   - The file has no extension,
-///   - The extension cannot be interpreted as UTF-8,
-///   - Or the extension is not in the list of `valid_exts`.
+///   - The file has no extension (a leading dot, as in `.gitignore`, does not count),
+///   - The file name cannot be interpreted as UTF-8,
+///   - Or no entry of `valid_exts` matches.
 ///
 /// # Behavior
-/// - Extracts the file extension using `Path::extension()`.
-/// - Converts the extension to a string using `.to_str()`.
-/// - Performs an exact string match against `valid_exts`.
+/// - Delegates to [`crate::filetypes::has_extension`].
+/// - Compound extensions match on the whole suffix: `index.d.ts` matches `"d.ts"` and `"ts"`.
+/// - ASCII case is ignored unless `strict_case` is set: `"rs"` matches `MAIN.RS` by default.
 ///
 /// # Panics
 /// This function does **not** panic under normal conditions.
 ///
 /// # Limitations
-/// - Does not distinguish between binary/text content.
-/// - Case-sensitive by design: `"RS"` is treated differently than `"rs"`.
+/// - Does not distinguish between binary/text content; `content::read_text_file` checks that
+///   when the file is read.
 /// - Does not inspect the actual content of the file or its MIME type.
 ///
 /// # Use Cases
@@ -179,211 +867,410 @@ pub fn write_folder_tags(
 ///
 /// # Example
 /// ```rust
-/// let path = Path::new("src/main.rs");
-/// assert!(is_human_readable(path, &["rs".to_string(), "txt".to_string()]));
+/// let path = Path::new("src/Main.RS");
+/// assert!(is_human_readable(path, &["rs".to_string(), "txt".to_string()], false));
+/// assert!(!is_human_readable(path, &["rs".to_string()], true));
 ///
 /// let path = Path::new("README");
-/// assert!(!is_human_readable(path, &["md".to_string()])); // has no extension
+/// assert!(!is_human_readable(path, &["md".to_string()], false)); // has no extension
 /// ```
+fn is_human_readable(path: &Path, valid_exts: &[String], strict_case: bool) -> bool {
+    has_extension(path, valid_exts, strict_case)
+}
+
+/// Returns `true` if `path` belongs to a selected file type: it has one of `options.valid_exts`,
+/// its name matches one of `filenames`, or, with `options.detect_shebangs`, it has no extension
+/// and its shebang interpreter maps to one of `options.valid_exts` (compared the same way as
+/// extensions, so case-insensitively unless `options.strict_extension_case` is set).
+fn matches_file_type(path: &Path, options: &ScanOptions, filenames: &FileNamePatterns) -> bool {
+    let matches_extension = |extension: &str| {
+        options
+            .valid_exts
+            .iter()
+            .any(|valid| extension_matches(extension, valid, options.strict_extension_case))
+    };
+    is_human_readable(path, &options.valid_exts, options.strict_extension_case)
+        || filenames.matches(path)
+        || (options.detect_shebangs
+            && path.extension().is_none()
+            && shebang_extension(path).is_some_and(matches_extension))
+}
+
+/// Resolves the absolute, symlink-free form of an output path that may not exist yet.
+///
+/// Existing files are canonicalized directly; otherwise the parent folder is canonicalized and the
+/// file name re-attached. Falls back to the path as given when the parent cannot be resolved.
+pub fn canonical_output_path(output_path: &Path) -> PathBuf {
+    if let Ok(canonical) = output_path.canonicalize() {
+        return canonical;
+    }
+
+    let parent = match output_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    match (parent.canonicalize(), output_path.file_name()) {
+        (Ok(parent), Some(file_name)) => parent.join(file_name),
+        _ => output_path.to_path_buf(),
+    }
+}
+
+/// Returns `true` if `path` is one of the canonical `excluded_paths`.
 ///
-/// # Future Enhancements
-/// - Optionally support case-insensitive matching.
-/// - Add content-based filtering (e.g., UTF-8 check or printable character ratio).
-fn is_human_readable(path: &Path, valid_exts: &[String]) -> bool {
-    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-        return valid_exts.iter().any(|valid_ext| valid_ext == ext);
+/// File names are compared first so only likely matches pay for canonicalization.
+fn is_excluded_path(path: &Path, excluded_paths: &[PathBuf]) -> bool {
+    let Some(file_name) = path.file_name() else {
+        return false;
+    };
+    if !excluded_paths
+        .iter()
+        .any(|excluded| excluded.file_name() == Some(file_name))
+    {
+        return false;
     }
-    false
+
+    path.canonicalize()
+        .is_ok_and(|canonical| excluded_paths.contains(&canonical))
 }
 
-fn write_tagged_file(
+/// Returns `true` if the start of the file contains [`OUTPUT_MARKER`].
+fn is_previous_output(path: &Path) -> bool {
+    let mut head = Vec::new();
+    let read =
+        File::open(path).and_then(|file| file.take(MARKER_SNIFF_BYTES).read_to_end(&mut head));
+    read.is_ok() && String::from_utf8_lossy(&head).contains(OUTPUT_MARKER)
+}
+
+fn is_ignored_by_rules(ignore_rules: &Option<IgnoreRules>, path: &Path, is_dir: bool) -> bool {
+    ignore_rules
+        .as_ref()
+        .is_some_and(|rules| rules.is_ignored(path, is_dir))
+}
+
+/// Reads and measures every selected file, recording transcoded, unreadable, binary, and oversized
+/// files in `prepared`.
+fn load_files(
     root_dir: &Path,
-    path: &Path,
-    output: &mut File,
-    summary: &mut WriteFolderTagsSummary,
-) -> std::io::Result<()> {
-    if let Ok(relative_path) = path.strip_prefix(root_dir) {
-        if let Some(rel_str) = relative_path.to_str() {
-            match std::fs::read_to_string(path) {
-                Ok(contents) => {
-                    writeln!(output, "<{}>", rel_str)?;
-                    writeln!(output, "{}", contents)?;
-                    writeln!(output, "</{}>\n", rel_str)?;
-                    summary.files_written += 1;
+    paths: &[PathBuf],
+    options: &ScanOptions,
+    render: &RenderOptions,
+    tokenizer: &dyn Tokenizer,
+    prepared: &mut PreparedFiles,
+) -> Vec<LoadedFile> {
+    let mut loaded = Vec::new();
+    let mut git_commits = if render.metadata.contains(&MetadataField::GitCommit) {
+        let relative_paths: Vec<PathBuf> = paths
+            .iter()
+            .filter_map(|path| path.strip_prefix(root_dir).ok())
+            .map(Path::to_path_buf)
+            .collect();
+        last_commits(root_dir, &relative_paths)
+    } else {
+        HashMap::new()
+    };
+    let metadata_fields = render.collected_metadata();
+
+    for path in paths {
+        let Ok(relative_path) = path.strip_prefix(root_dir) else {
+            continue;
+        };
+        let prefixed_path = match &options.path_prefix {
+            Some(prefix) => Path::new(prefix).join(relative_path),
+            None => relative_path.to_path_buf(),
+        };
+        let Some(display_path) = render.path_style.render(&prefixed_path) else {
+            continue;
+        };
+        let text_file = read_file_bytes(path, options.max_file_size).and_then(|bytes| {
+            decode_text(&bytes, &options.fallback_encodings).map(|text_file| (bytes, text_file))
+        });
+        match text_file {
+            Ok((bytes, TextFile { contents, encoding })) => {
+                if let Some(encoding) = encoding {
+                    prepared.summary.transcoded_files.push(TranscodedFile {
+                        path: display_path.clone(),
+                        encoding,
+                    });
                 }
-                Err(e) => {
-                    summary.files_skipped += 1;
-                    if e.kind() == ErrorKind::InvalidData {
-                        summary.skipped_non_utf8_files += 1;
+                let metadata = FileMetadata::collect(
+                    path,
+                    &bytes,
+                    &contents,
+                    &metadata_fields,
+                    git_commits.remove(relative_path),
+                );
+                // Numbering before the budget keeps real line numbers in truncated and split files.
+                let contents = if render.numbers_lines() {
+                    number_lines(&contents)
+                } else {
+                    contents
+                };
+                loaded.push(LoadedFile {
+                    path: path.clone(),
+                    display_path,
+                    tokens: tokenizer.count_tokens(&contents),
+                    metadata,
+                    contents,
+                });
+            }
+            Err(reason) => {
+                match reason {
+                    SkipReason::Binary | SkipReason::TooLarge { .. } => {
+                        prepared.summary.rejected_files.push(RejectedFile {
+                            path: display_path,
+                            reason: reason.clone(),
+                        });
+                    }
+                    _ => {
+                        prepared.summary.files_skipped += 1;
+                        if reason == SkipReason::NotUtf8 {
+                            prepared.summary.skipped_non_utf8_files += 1;
+                        }
+                        eprintln!("⚠️ Skipping {:?}: {}", path, reason.label());
                     }
-                    eprintln!("⚠️ Skipping {:?}: {}", path, e);
                 }
+                prepared.skipped.push(SkippedPath {
+                    path: path.clone(),
+                    is_dir: false,
+                    reason,
+                });
             }
         }
     }
 
-    Ok(())
-}
-
-/// Appends a block of custom user-provided text to the end of the output file,
-/// under a `[Additional Commands]` header.
-///
-/// # Purpose
-/// Allows users to supplement a tagged output file with additional
-/// instructions, prompts, or commands that should be interpreted after the code sections.
-///
-/// # Parameters
-/// - `file_path`: Path to the caller-selected output file to append to.
-/// - `additional_commands`: Multiline string of user-defined commands or instructions to include.
-///
-/// # Behavior
-/// - Opens the file in append mode, creating it if it does not exist.
-/// - Writes a new section labeled `[Additional Commands]` followed by the content of `additional_commands`.
-/// - Ensures newline padding before and after for readability and visual separation.
-///
-/// # Output Format
-/// The appended section will resemble:
-/// ```text
-/// [Additional Commands]
-/// user instructions...
-/// ```
-///
-/// # Returns
-/// - `Ok(())` if writing was successful.
-/// - `Err(std::io::Error)` if the file could not be opened or written to.
-///
-/// # Panics
-/// - This function does not panic. All I/O operations are fallible and propagated using `?`.
-///
-/// # Notes
-/// - If `additional_commands` is empty, the section is still written unless filtered externally.
-/// - Multiple calls will result in multiple `[Additional Commands]` sections unless deduplicated by the caller.
-/// - No sanitization or validation is performed on the `additional_commands` text.
-///
-/// # Use Cases
-/// - Appending detailed user instructions to aid post-processing of the file.
-/// - Integrating command prompts or AI instructions for tools like ChatGPT.
-///
-/// # Example
-/// ```rust
-/// append_additional_commands("project_context.txt", "TODO: Review all unwrap() usages.")?;
-/// ```
-///
-/// # See Also
-/// - [`write_folder_tags`]: Main output function that creates the initial tagged output file.
-/// - [`copy_to_clipboard`]: Can be used after appending to share the result.
-pub fn append_additional_commands(
-    file_path: &str,
-    additional_commands: &str,
-) -> std::io::Result<()> {
-    let mut file = File::options().create(true).append(true).open(file_path)?;
-
-    writeln!(file, "\n[Additional Commands]")?;
-    writeln!(file, "{}\n", additional_commands)?;
-
-    Ok(())
+    loaded
 }
 
-/// Recursively traverses a directory and writes the contents of matching files to an output file,
-/// wrapping each in XML-style tags based on its relative path.
+/// Recursively traverses a directory and records every file that passes the scan filters.
 ///
 /// # Purpose
 /// Processes all files with specified extensions within a directory tree, skipping ignored or hidden folders,
-/// and outputs their contents to the caller-provided file with clear path-based XML tags.
+/// and appends matching paths to `selection` in traversal order so the caller can write them afterwards.
 ///
 /// # Parameters
-/// - `root_dir`: The root directory of the traversal, used to compute relative paths for output tags.
+/// - `root_dir`: The root directory of the traversal, used to compute relative paths for glob matching.
 /// - `dir`: The current directory being visited (initially the same as `root_dir`).
-/// - `valid_exts`: List of file extensions (`&[String]`) that are allowed (e.g., `["rs", "json"]`).
-/// - `output`: A mutable reference to the output file.
-/// - `ignored_folders`: A list of folder names (case-insensitive) to skip during traversal
-///   (e.g., `[".git", "target"]`).
+/// - `options`: [`ScanOptions`] with the allowed extensions, recursion flag, and ignored folder names.
+/// - `filters`: [`FileFilters`] holding the compiled file names and file-name globs selected
+///   regardless of extension, the compiled include/exclude globs, and the git selection's files.
+/// - `ignore_rules`: Gitignore-style rules for the directories being visited, or `None` when disabled.
+/// - `selection`: Accumulates accepted paths and per-pattern exclusion counts.
 ///
 /// # Behavior
-/// - Walks the directory tree rooted at `dir`, following folders recursively.
+/// - Walks the directory tree rooted at `dir`, following folders only when `options.recursive` is set.
 /// - Skips:
 ///   - Hidden directories (names starting with `.`).
 ///   - Directories matching any entry in `ignored_folders`, case-insensitively.
-/// - For each file:
-///   - If it matches a valid extension (`is_human_readable`), the file is opened and read as UTF-8.
-///   - Its contents are written to `output`, surrounded by `<relative\path>` XML-style tags.
-///
-/// # Output Format
-/// For each valid file:
-/// ```xml
-/// <subdir\file.rs>
-/// // file contents...
-/// </subdir\file.rs>
-/// ```
+///   - Files and directories excluded by `ignore_rules`.
+///   - Files outside `filters.git_files`, when a git selection was requested.
+///   - Files rejected by `filters.patterns`, which are counted in `selection`.
+///   - The output file and, when enabled, earlier outputs, also counted in `selection`.
+/// - For each file, checks the file type (extension, file name, or shebang) before any other file
+///   filter.
 ///
 /// # Returns
-/// - `Ok(())` if all operations succeed.
-/// - `Err(std::io::Error)` if a fatal error occurs (e.g., directory read or file open failure).
+/// - `Ok(())` if all directories could be read.
+/// - `Err(std::io::Error)` if a fatal error occurs (e.g., a directory read failure).
 ///
 /// # Panics
-/// - This function does not panic. All I/O errors are propagated or skipped with logging.
+/// - This function does not panic. All I/O errors are propagated.
 ///
 /// # Notes
-/// - Invalid UTF-8 file paths or contents are skipped silently or with a warning to `stderr`.
-/// - Tag paths always use Windows-style `\` separators for cross-platform consistency.
-/// - Designed to integrate with `write_folder_tags`, not called directly by end users.
+/// - Designed to integrate with `collect_matching_files`, not called directly by end users.
 ///
 /// # Example
 /// ```rust
 /// let root = Path::new("src");
-/// let mut output = File::create("project_context.txt")?;
-/// let ignored = vec!["target".to_string(), ".git".to_string()];
-/// let valid_exts = vec!["rs".to_string()];
-/// let mut summary = WriteFolderTagsSummary::default();
-/// write_folder_tags_recursive(root, root, &valid_exts, &mut output, &ignored, &mut summary)?;
+/// let options = ScanOptions {
+///     valid_exts: vec!["rs".to_string()],
+///     recursive: true,
+///     ignored_folders: vec!["target".to_string(), ".git".to_string()],
+///     ..ScanOptions::default()
+/// };
+/// let filters = FileFilters::compile(&options)?;
+/// let mut selection = FileSelection::default();
+/// collect_files_recursive(root, root, &options, &filters, &mut None, &mut selection)?;
 /// ```
 ///
 /// # See Also
-/// - [`write_folder_tags`]: Top-level API that wraps this function and handles non-recursive behavior.
+/// - [`collect_matching_files`]: Top-level API that compiles the filters, lists the git selection,
+///   and loads ignore rules.
 /// - [`is_human_readable`]: Checks extension validity before file content is read.
-fn write_folder_tags_recursive(
+fn collect_files_recursive(
     root_dir: &Path,
     dir: &Path,
-    valid_exts: &[String],
-    output: &mut File,
-    ignored_folders: &[String],
-    summary: &mut WriteFolderTagsSummary,
+    options: &ScanOptions,
+    filters: &FileFilters,
+    ignore_rules: &mut Option<IgnoreRules>,
+    selection: &mut FileSelection,
 ) -> std::io::Result<()> {
     for entry in read_dir(dir)? {
         let entry = entry?;
         let path = entry.path();
 
         if path.is_dir() {
+            if !options.recursive {
+                skip(selection, path, true, SkipReason::NotRecursive);
+                continue;
+            }
             if let Some(folder_name) = path.file_name().and_then(|n| n.to_str()) {
-                if ignored_folders
+                if options
+                    .ignored_folders
                     .iter()
                     .any(|ignored| ignored.eq_ignore_ascii_case(folder_name))
-                    || folder_name.starts_with('.')
                 {
+                    skip(selection, path, true, SkipReason::IgnoredFolder);
                     continue;
                 }
+                if folder_name.starts_with('.') {
+                    skip(selection, path, true, SkipReason::HiddenFolder);
+                    continue;
+                }
+            }
+            if is_ignored_by_rules(ignore_rules, &path, true) {
+                skip(selection, path, true, SkipReason::Gitignored);
+                continue;
+            }
+
+            if let Some(rules) = ignore_rules.as_mut() {
+                rules.enter_dir(&path);
+            }
+            let result =
+                collect_files_recursive(root_dir, &path, options, filters, ignore_rules, selection);
+            if let Some(rules) = ignore_rules.as_mut() {
+                rules.leave_dir();
+            }
+            result?;
+        } else if path.is_file() {
+            if !matches_file_type(&path, options, &filters.filenames) {
+                skip(selection, path, false, SkipReason::ExtensionMismatch);
+                continue;
+            }
+            if is_ignored_by_rules(ignore_rules, &path, false) {
+                skip(selection, path, false, SkipReason::Gitignored);
+                continue;
+            }
+
+            let relative_path = path.strip_prefix(root_dir).unwrap_or(&path);
+            if let (Some(git_files), Some(git_selection)) =
+                (&filters.git_files, &options.git_selection)
+            {
+                if !git_files.contains(relative_path) {
+                    let reason = SkipReason::OutsideGitSelection {
+                        selection: git_selection.label(),
+                    };
+                    skip(selection, path, false, reason);
+                    continue;
+                }
+            }
+            match filters.patterns.evaluate(relative_path) {
+                PatternDecision::Included if is_excluded_path(&path, &options.excluded_paths) => {
+                    selection.output_files_skipped += 1;
+                    skip(selection, path, false, SkipReason::OutputFile);
+                }
+                PatternDecision::Included
+                    if options.skip_previous_outputs && is_previous_output(&path) =>
+                {
+                    selection.output_files_skipped += 1;
+                    skip(selection, path, false, SkipReason::PreviousOutput);
+                }
+                PatternDecision::Included => selection.files.push(path),
+                PatternDecision::NotIncluded => {
+                    selection.files_not_included += 1;
+                    skip(selection, path, false, SkipReason::NotIncluded);
+                }
+                PatternDecision::Excluded(index) => {
+                    let exclusion = &mut selection.pattern_exclusions[index];
+                    exclusion.files_excluded += 1;
+                    let pattern = exclusion.pattern.clone();
+                    skip(
+                        selection,
+                        path,
+                        false,
+                        SkipReason::ExcludedByPattern { pattern },
+                    );
+                }
             }
-            write_folder_tags_recursive(
-                root_dir,
-                &path,
-                valid_exts,
-                output,
-                ignored_folders,
-                summary,
-            )?;
-        } else if path.is_file() && is_human_readable(&path, valid_exts) {
-            write_tagged_file(root_dir, &path, output, summary)?;
         }
     }
     Ok(())
 }
 
+/// Compiled per-file filters shared by every level of a recursive scan.
+struct FileFilters {
+    filenames: FileNamePatterns,
+    patterns: PathPatterns,
+    /// Files chosen by `ScanOptions::git_selection`, when one was requested.
+    git_files: Option<GitFiles>,
+}
+
+impl FileFilters {
+    /// Compiles the file-name and path patterns of `options`, leaving `git_files` unset.
+    fn compile(options: &ScanOptions) -> std::io::Result<Self> {
+        let filenames = FileNamePatterns::compile(&options.filenames)
+            .map_err(|message| Error::new(ErrorKind::InvalidInput, message))?;
+        let patterns = PathPatterns::compile(&options.include_patterns, &options.exclude_patterns)
+            .map_err(|message| Error::new(ErrorKind::InvalidInput, message))?;
+        Ok(Self {
+            filenames,
+            patterns,
+            git_files: None,
+        })
+    }
+}
+
+fn skip(selection: &mut FileSelection, path: PathBuf, is_dir: bool, reason: SkipReason) {
+    selection.skipped.push(SkippedPath {
+        path,
+        is_dir,
+        reason,
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::formats::{OutputFormat, PathStyle};
+    use crate::tokens::HeuristicTokenizer;
     use std::fs;
     use tempfile::tempdir;
 
+    fn single_root(dir: &Path, options: &ScanOptions) -> [ScanRoot; 1] {
+        [ScanRoot {
+            dir: dir.to_path_buf(),
+            options: options.clone(),
+        }]
+    }
+
+    fn write_folder_tags(
+        dir: &Path,
+        options: &ScanOptions,
+        render: &RenderOptions,
+        budget: &OutputBudget,
+        chunk_limit: &ChunkLimit,
+        tokenizer: &dyn Tokenizer,
+        output_path: &Path,
+    ) -> std::io::Result<WriteFolderTagsSummary> {
+        write_roots(
+            &single_root(dir, options),
+            render,
+            budget,
+            chunk_limit,
+            tokenizer,
+            output_path,
+        )
+    }
+
+    fn prepare_folder_files(
+        dir: &Path,
+        options: &ScanOptions,
+        render: &RenderOptions,
+        budget: &OutputBudget,
+        tokenizer: &dyn Tokenizer,
+    ) -> std::io::Result<PreparedFiles> {
+        prepare_roots(&single_root(dir, options), render, budget, tokenizer)
+    }
+
     fn valid_exts() -> Vec<String> {
         vec!["rs".to_string()]
     }
@@ -392,6 +1279,31 @@ mod tests {
         vec!["target".to_string(), ".git".to_string()]
     }
 
+    fn scan_options(recursive: bool, ignored_folders: Vec<String>) -> ScanOptions {
+        ScanOptions {
+            valid_exts: valid_exts(),
+            strict_extension_case: false,
+            filenames: Vec::new(),
+            detect_shebangs: false,
+            file_list: None,
+            recursive,
+            ignored_folders,
+            respect_gitignore: false,
+            git_selection: None,
+            include_patterns: Vec::new(),
+            exclude_patterns: Vec::new(),
+            excluded_paths: Vec::new(),
+            skip_previous_outputs: false,
+            order: FileOrder::Path,
+            priority_patterns: Vec::new(),
+            tree: TreeOptions::default(),
+            diff: None,
+            path_prefix: None,
+            max_file_size: None,
+            fallback_encodings: Vec::new(),
+        }
+    }
+
     fn project_fixture() -> std::io::Result<(tempfile::TempDir, std::path::PathBuf)> {
         let temp = tempdir()?;
         let project = temp.path().join("project");
@@ -410,7 +1322,15 @@ mod tests {
         fs::write(project.join("src").join("main.rs"), "fn main() {}")?;
         let output_path = temp.path().join("project_context.txt");
 
-        let summary = write_folder_tags(&project, &valid_exts(), true, &[], &output_path)?;
+        let summary = write_folder_tags(
+            &project,
+            &scan_options(true, Vec::new()),
+            &RenderOptions::default(),
+            &OutputBudget::default(),
+            &ChunkLimit::default(),
+            &HeuristicTokenizer,
+            &output_path,
+        )?;
 
         assert!(output_path.exists());
         assert!(!temp.path().join("tags_output.txt").exists());
@@ -432,7 +1352,15 @@ mod tests {
         fs::write(project.join("src").join("lib.rs"), "pub fn lib() {}")?;
         let output_path = temp.path().join("recursive_output.txt");
 
-        let summary = write_folder_tags(&project, &valid_exts(), true, &[], &output_path)?;
+        let summary = write_folder_tags(
+            &project,
+            &scan_options(true, Vec::new()),
+            &RenderOptions::default(),
+            &OutputBudget::default(),
+            &ChunkLimit::default(),
+            &HeuristicTokenizer,
+            &output_path,
+        )?;
 
         assert_eq!(summary.files_written, 2);
         let output = fs::read_to_string(output_path)?;
@@ -454,7 +1382,15 @@ mod tests {
         fs::write(project.join("src").join("lib.rs"), "pub fn lib() {}")?;
         let output_path = temp.path().join("non_recursive_output.txt");
 
-        let summary = write_folder_tags(&project, &valid_exts(), false, &[], &output_path)?;
+        let summary = write_folder_tags(
+            &project,
+            &scan_options(false, Vec::new()),
+            &RenderOptions::default(),
+            &OutputBudget::default(),
+            &ChunkLimit::default(),
+            &HeuristicTokenizer,
+            &output_path,
+        )?;
 
         assert_eq!(summary.files_written, 1);
         let output = fs::read_to_string(output_path)?;
@@ -482,9 +1418,11 @@ mod tests {
 
         let summary = write_folder_tags(
             &project,
-            &valid_exts(),
-            true,
-            &ignored_folders(),
+            &scan_options(true, ignored_folders()),
+            &RenderOptions::default(),
+            &OutputBudget::default(),
+            &ChunkLimit::default(),
+            &HeuristicTokenizer,
             &output_path,
         )?;
 
@@ -500,4 +1438,818 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn respect_gitignore_skips_ignored_files_and_folders() -> std::io::Result<()> {
+        let (temp, project) = project_fixture()?;
+        fs::write(
+            project.join(".gitignore"),
+            "generated/\n/root_only.rs\n*.rs\n!src/*.rs\n",
+        )?;
+        fs::create_dir_all(project.join("src"))?;
+        fs::write(project.join("src").join("main.rs"), "fn main() {}")?;
+        fs::create_dir_all(project.join("generated"))?;
+        fs::write(
+            project.join("generated").join("mod.rs"),
+            "pub fn generated() {}",
+        )?;
+        fs::write(project.join("root_only.rs"), "pub fn root_only() {}")?;
+        fs::create_dir_all(project.join("vendor"))?;
+        fs::write(project.join("vendor").join("dep.rs"), "pub fn dep() {}")?;
+        let output_path = temp.path().join("gitignore_output.txt");
+        let mut options = scan_options(true, Vec::new());
+        options.respect_gitignore = true;
+
+        let summary = write_folder_tags(
+            &project,
+            &options,
+            &RenderOptions::default(),
+            &OutputBudget::default(),
+            &ChunkLimit::default(),
+            &HeuristicTokenizer,
+            &output_path,
+        )?;
+
+        assert_eq!(summary.files_written, 1);
+        let output = fs::read_to_string(output_path)?;
+        assert!(output.contains("fn main() {}"));
+        assert!(!output.contains("pub fn generated() {}"));
+        assert!(!output.contains("pub fn root_only() {}"));
+        assert!(!output.contains("pub fn dep() {}"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn gitignore_rules_are_ignored_unless_enabled() -> std::io::Result<()> {
+        let (temp, project) = project_fixture()?;
+        fs::write(project.join(".gitignore"), "*.rs\n")?;
+        fs::write(project.join("main.rs"), "fn main() {}")?;
+        let output_path = temp.path().join("plain_output.txt");
+
+        let summary = write_folder_tags(
+            &project,
+            &scan_options(false, Vec::new()),
+            &RenderOptions::default(),
+            &OutputBudget::default(),
+            &ChunkLimit::default(),
+            &HeuristicTokenizer,
+            &output_path,
+        )?;
+
+        assert_eq!(summary.files_written, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn include_and_exclude_patterns_filter_by_relative_path() -> std::io::Result<()> {
+        let (temp, project) = project_fixture()?;
+        fs::create_dir_all(project.join("src").join("generated"))?;
+        fs::create_dir_all(project.join("tools").join("generated"))?;
+        fs::write(project.join("src").join("main.rs"), "fn main() {}")?;
+        fs::write(
+            project.join("src").join("generated").join("mod.rs"),
+            "pub fn generated() {}",
+        )?;
+        fs::write(
+            project.join("tools").join("generated").join("tool.rs"),
+            "pub fn tool() {}",
+        )?;
+        fs::write(project.join("build.rs"), "fn build() {}")?;
+        let output_path = temp.path().join("pattern_output.txt");
+        let mut options = scan_options(true, Vec::new());
+        options.include_patterns = vec!["src/**".to_string(), "tools/**".to_string()];
+        options.exclude_patterns = vec!["src/generated/**".to_string()];
+
+        let summary = write_folder_tags(
+            &project,
+            &options,
+            &RenderOptions::default(),
+            &OutputBudget::default(),
+            &ChunkLimit::default(),
+            &HeuristicTokenizer,
+            &output_path,
+        )?;
+
+        assert_eq!(summary.files_written, 2);
+        assert_eq!(summary.files_not_included, 1);
+        assert_eq!(
+            summary.pattern_exclusions,
+            vec![PatternExclusion {
+                pattern: "src/generated/**".to_string(),
+                files_excluded: 1,
+            }]
+        );
+        let output = fs::read_to_string(output_path)?;
+        assert!(output.contains("fn main() {}"));
+        assert!(output.contains("pub fn tool() {}"));
+        assert!(!output.contains("pub fn generated() {}"));
+        assert!(!output.contains("fn build() {}"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_glob_pattern_returns_invalid_input() -> std::io::Result<()> {
+        let (temp, project) = project_fixture()?;
+        let mut options = scan_options(true, Vec::new());
+        options.exclude_patterns = vec!["src/[".to_string()];
+
+        let error = write_folder_tags(
+            &project,
+            &options,
+            &RenderOptions::default(),
+            &OutputBudget::default(),
+            &ChunkLimit::default(),
+            &HeuristicTokenizer,
+            &temp.path().join("out.txt"),
+        )
+        .expect_err("invalid glob should fail");
+
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+
+        Ok(())
+    }
+
+    #[test]
+    fn output_file_inside_scanned_folder_is_never_wrapped() -> std::io::Result<()> {
+        let (_temp, project) = project_fixture()?;
+        fs::write(project.join("notes.txt"), "keep me")?;
+        let output_path = project.join("tags_output.txt");
+        let mut options = scan_options(false, Vec::new());
+        options.valid_exts = vec!["txt".to_string()];
+        options.excluded_paths = vec![canonical_output_path(&output_path)];
+
+        write_folder_tags(
+            &project,
+            &options,
+            &RenderOptions::default(),
+            &OutputBudget::default(),
+            &ChunkLimit::default(),
+            &HeuristicTokenizer,
+            &output_path,
+        )?;
+        let summary = write_folder_tags(
+            &project,
+            &options,
+            &RenderOptions::default(),
+            &OutputBudget::default(),
+            &ChunkLimit::default(),
+            &HeuristicTokenizer,
+            &output_path,
+        )?;
+
+        assert_eq!(summary.files_written, 1);
+        assert_eq!(summary.output_files_skipped, 1);
+        let output = fs::read_to_string(output_path)?;
+        assert!(output.starts_with(OUTPUT_MARKER));
+        assert!(!output.contains("<tags_output.txt>"));
+        assert_eq!(output.matches(OUTPUT_MARKER).count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn previous_outputs_are_skipped_only_when_requested() -> std::io::Result<()> {
+        let (temp, project) = project_fixture()?;
+        fs::write(project.join("notes.txt"), "keep me")?;
+        fs::write(
+            project.join("old_context.txt"),
+            format!("{OUTPUT_MARKER}\n<notes.txt>\nkeep me\n</notes.txt>\n"),
+        )?;
+        let output_path = temp.path().join("context.txt");
+        let mut options = scan_options(false, Vec::new());
+        options.valid_exts = vec!["txt".to_string()];
+
+        let summary = write_folder_tags(
+            &project,
+            &options,
+            &RenderOptions::default(),
+            &OutputBudget::default(),
+            &ChunkLimit::default(),
+            &HeuristicTokenizer,
+            &output_path,
+        )?;
+        assert_eq!(summary.files_written, 2);
+
+        options.skip_previous_outputs = true;
+        let summary = write_folder_tags(
+            &project,
+            &options,
+            &RenderOptions::default(),
+            &OutputBudget::default(),
+            &ChunkLimit::default(),
+            &HeuristicTokenizer,
+            &output_path,
+        )?;
+        assert_eq!(summary.files_written, 1);
+        assert_eq!(summary.output_files_skipped, 1);
+        let output = fs::read_to_string(output_path)?;
+        assert!(!output.contains("<old_context.txt>"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn filenames_and_shebangs_select_extensionless_files() -> std::io::Result<()> {
+        let (_temp, project) = project_fixture()?;
+        fs::create_dir_all(project.join("docker"))?;
+        fs::create_dir_all(project.join("bin"))?;
+        fs::write(project.join("Makefile"), "all:\n")?;
+        fs::write(project.join("LICENSE"), "MIT\n")?;
+        fs::write(project.join("docker").join("Dockerfile.dev"), "FROM rust\n")?;
+        fs::write(
+            project.join("bin").join("deploy"),
+            "#!/usr/bin/env python3\n",
+        )?;
+        fs::write(project.join("bin").join("setup"), "#!/bin/bash\n")?;
+        let mut options = scan_options(true, Vec::new());
+        options.valid_exts = vec!["py".to_string()];
+        options.filenames = vec!["Makefile".to_string(), "Dockerfile.*".to_string()];
+
+        let relative_files = |options: &ScanOptions| -> std::io::Result<Vec<PathBuf>> {
+            Ok(collect_matching_files(&project, options)?
+                .files
+                .iter()
+                .map(|path| {
+                    path.strip_prefix(&project)
+                        .expect("inside root")
+                        .to_path_buf()
+                })
+                .collect())
+        };
+
+        assert_eq!(
+            relative_files(&options)?,
+            vec![
+                PathBuf::from("Makefile"),
+                Path::new("docker").join("Dockerfile.dev")
+            ]
+        );
+
+        options.detect_shebangs = true;
+        assert_eq!(
+            relative_files(&options)?,
+            vec![
+                PathBuf::from("Makefile"),
+                Path::new("bin").join("deploy"),
+                Path::new("docker").join("Dockerfile.dev")
+            ]
+        );
+
+        options.valid_exts = vec!["PY".to_string()];
+        options.filenames.clear();
+        assert_eq!(
+            relative_files(&options)?,
+            vec![Path::new("bin").join("deploy")]
+        );
+        options.strict_extension_case = true;
+        assert!(relative_files(&options)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn extensions_match_compound_suffixes_and_ignore_case_unless_strict() -> std::io::Result<()> {
+        let (_temp, project) = project_fixture()?;
+        fs::write(project.join("main.rs"), "fn main() {}")?;
+        fs::write(project.join("LEGACY.RS"), "fn legacy() {}")?;
+        fs::write(project.join("index.d.ts"), "export {};")?;
+        fs::write(project.join("index.ts"), "export {};")?;
+        let mut options = scan_options(false, Vec::new());
+        options.valid_exts = vec!["rs".to_string(), "d.ts".to_string()];
+
+        let file_names = |options: &ScanOptions| -> std::io::Result<Vec<String>> {
+            let mut names: Vec<String> = collect_matching_files(&project, options)?
+                .files
+                .iter()
+                .filter_map(|path| path.file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            Ok(names)
+        };
+
+        assert_eq!(
+            file_names(&options)?,
+            vec!["LEGACY.RS", "index.d.ts", "main.rs"]
+        );
+        options.strict_extension_case = true;
+        assert_eq!(file_names(&options)?, vec!["index.d.ts", "main.rs"]);
+        Ok(())
+    }
+
+    #[test]
+    fn listed_files_keep_their_order_and_still_pass_content_checks() -> std::io::Result<()> {
+        let (_temp, project) = project_fixture()?;
+        fs::create_dir_all(project.join("src"))?;
+        fs::create_dir_all(project.join("target"))?;
+        fs::write(project.join("src").join("main.rs"), "fn main() {}")?;
+        fs::write(project.join("target").join("notes.md"), "# Notes")?;
+        fs::write(project.join("README"), "Read me")?;
+        fs::write(
+            project.join("logo.png"),
+            [0x89, b'P', b'N', b'G', 0, 0, 0, 0],
+        )?;
+        fs::write(project.join("out.txt"), "previous output")?;
+        let mut options = scan_options(false, ignored_folders());
+        options.excluded_paths = vec![project.join("out.txt").canonicalize()?];
+        options.file_list = Some(
+            [
+                "README",
+                "target/notes.md",
+                "logo.png",
+                "src/main.rs",
+                "README",
+                "out.txt",
+            ]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect(),
+        );
+
+        let selection = collect_matching_files(&project, &options)?;
+        assert_eq!(
+            selection.files,
+            vec![
+                project.join("README"),
+                project.join("target").join("notes.md"),
+                project.join("logo.png"),
+                project.join("src").join("main.rs"),
+            ]
+        );
+        assert_eq!(selection.output_files_skipped, 1);
+
+        let prepared = prepare_folder_files(
+            &project,
+            &options,
+            &RenderOptions::default(),
+            &OutputBudget::default(),
+            &HeuristicTokenizer,
+        )?;
+        let written: Vec<&str> = prepared
+            .files
+            .iter()
+            .map(|file| file.display_path.as_str())
+            .collect();
+        assert_eq!(written.len(), 3);
+        assert_eq!(written[0], "README");
+        assert_eq!(
+            prepared.summary.rejected_files[0].reason,
+            SkipReason::Binary
+        );
+
+        options.file_list = Some(vec![PathBuf::from("../outside.rs")]);
+        let error = collect_matching_files(&project, &options).expect_err("outside the root");
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        Ok(())
+    }
+
+    #[test]
+    fn git_selection_combines_with_the_extension_filter() -> std::io::Result<()> {
+        let (_temp, project) = project_fixture()?;
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(&project)
+                .output()
+                .is_ok_and(|output| output.status.success())
+        };
+        if !git(&["init", "-q"]) {
+            return Ok(());
+        }
+        fs::write(project.join("tracked.rs"), "fn tracked() {}")?;
+        fs::write(project.join("tracked.toml"), "[tracked]")?;
+        assert!(git(&["add", "."]));
+        fs::write(project.join("untracked.rs"), "fn untracked() {}")?;
+        let mut options = scan_options(false, Vec::new());
+        options.git_selection = Some(GitSelection::Tracked);
+
+        let selection = collect_matching_files(&project, &options)?;
+
+        assert_eq!(selection.files, vec![project.join("tracked.rs")]);
+        assert!(selection.skipped.contains(&SkippedPath {
+            path: project.join("untracked.rs"),
+            is_dir: false,
+            reason: SkipReason::OutsideGitSelection {
+                selection: "tracked".to_string()
+            },
+        }));
+
+        options.git_selection = Some(GitSelection::ChangedSince {
+            base: "no-such-ref".to_string(),
+        });
+        assert!(collect_matching_files(&project, &options).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn diff_is_limited_to_files_that_pass_the_scan_filters() -> std::io::Result<()> {
+        let (temp, project) = project_fixture()?;
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(&project)
+                .output()
+                .is_ok_and(|output| output.status.success())
+        };
+        if !git(&["init", "-q"]) {
+            return Ok(());
+        }
+        fs::create_dir_all(project.join("target"))?;
+        fs::write(project.join("main.rs"), "fn main() {}\n")?;
+        fs::write(project.join("lib.rs"), "pub fn old() {}\n")?;
+        fs::write(project.join("Cargo.toml"), "[package]\n")?;
+        fs::write(project.join("target").join("gen.rs"), "// v1\n")?;
+        assert!(git(&["add", "."]));
+        assert!(git(&["commit", "-q", "-m", "base"]));
+        fs::write(project.join("main.rs"), "fn main() { run() }\n")?;
+        fs::write(project.join("Cargo.toml"), "[package]\nname = \"app\"\n")?;
+        fs::write(project.join("target").join("gen.rs"), "// v2\n")?;
+        fs::remove_file(project.join("lib.rs"))?;
+        let mut options = scan_options(true, ignored_folders());
+        options.diff = Some(DiffBase::Head);
+        let render = RenderOptions::default();
+
+        let prepared = prepare_folder_files(
+            &project,
+            &options,
+            &render,
+            &OutputBudget::default(),
+            &HeuristicTokenizer,
+        )?;
+
+        let diff = prepared.diff.expect("main.rs and lib.rs changed");
+        assert!(diff.contains("+fn main() { run() }"));
+        assert!(diff.contains("-pub fn old() {}"));
+        assert!(!diff.contains("Cargo.toml"));
+        assert!(!diff.contains("gen.rs"));
+
+        let output_path = temp.path().join("out.txt");
+        write_folder_tags(
+            &project,
+            &options,
+            &render,
+            &OutputBudget::default(),
+            &ChunkLimit::default(),
+            &HeuristicTokenizer,
+            &output_path,
+        )?;
+        assert!(fs::read_to_string(&output_path)?.contains("[Diff]\ndiff --git"));
+        Ok(())
+    }
+
+    #[test]
+    fn prepared_files_record_why_paths_were_skipped() -> std::io::Result<()> {
+        let (_temp, project) = project_fixture()?;
+        fs::create_dir_all(project.join("target"))?;
+        fs::create_dir_all(project.join(".cache"))?;
+        fs::write(project.join("main.rs"), "fn main() {}")?;
+        fs::write(project.join("notes.txt"), "notes")?;
+        fs::write(project.join("latin1.rs"), [0xE9, 0x0A])?;
+        let mut options = scan_options(true, ignored_folders());
+        options.exclude_patterns = vec!["gen_*.rs".to_string()];
+        fs::write(project.join("gen_code.rs"), "// generated")?;
+
+        let prepared = prepare_folder_files(
+            &project,
+            &options,
+            &RenderOptions::default(),
+            &OutputBudget::default(),
+            &HeuristicTokenizer,
+        )?;
+
+        let mut reasons: Vec<(String, SkipReason)> = prepared
+            .skipped
+            .iter()
+            .map(|skipped| {
+                let name = skipped.path.file_name().expect("file name");
+                (name.to_string_lossy().into_owned(), skipped.reason.clone())
+            })
+            .collect();
+        reasons.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            reasons,
+            vec![
+                (".cache".to_string(), SkipReason::HiddenFolder),
+                (
+                    "gen_code.rs".to_string(),
+                    SkipReason::ExcludedByPattern {
+                        pattern: "gen_*.rs".to_string()
+                    }
+                ),
+                ("latin1.rs".to_string(), SkipReason::NotUtf8),
+                ("notes.txt".to_string(), SkipReason::ExtensionMismatch),
+                ("target".to_string(), SkipReason::IgnoredFolder),
+            ]
+        );
+        assert_eq!(prepared.files.len(), 1);
+        assert!(!project.join("tags_output.txt").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn legacy_encodings_are_transcoded_and_reported() -> std::io::Result<()> {
+        let (temp, project) = project_fixture()?;
+        let mut resource = vec![0xFF, 0xFE];
+        resource.extend("// caf\u{e9}\n".encode_utf16().flat_map(u16::to_le_bytes));
+        fs::write(project.join("resource.rs"), resource)?;
+        fs::write(project.join("legacy.rs"), b"// na\xefve\n")?;
+        fs::write(project.join("main.rs"), "fn main() {}\n")?;
+        let output_path = temp.path().join("transcoded_output.txt");
+        let mut options = scan_options(false, Vec::new());
+        options.fallback_encodings = vec![TextEncoding::Windows1252];
+
+        let summary = write_folder_tags(
+            &project,
+            &options,
+            &RenderOptions::default(),
+            &OutputBudget::default(),
+            &ChunkLimit::default(),
+            &HeuristicTokenizer,
+            &output_path,
+        )?;
+
+        assert_eq!(summary.files_written, 3);
+        assert_eq!(summary.skipped_non_utf8_files, 0);
+        assert_eq!(
+            summary.transcoded_files,
+            vec![
+                TranscodedFile {
+                    path: "legacy.rs".to_string(),
+                    encoding: TextEncoding::Windows1252,
+                },
+                TranscodedFile {
+                    path: "resource.rs".to_string(),
+                    encoding: TextEncoding::Utf16Le,
+                },
+            ]
+        );
+        let output = fs::read_to_string(output_path)?;
+        assert!(output.contains("// na\u{ef}ve\n"));
+        assert!(output.contains("<resource.rs>\n// caf\u{e9}\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn binary_and_oversized_files_are_rejected_with_a_reason() -> std::io::Result<()> {
+        let (temp, project) = project_fixture()?;
+        fs::write(project.join("main.rs"), "fn main() {}")?;
+        fs::write(
+            project.join("logo.rs"),
+            [0x89, b'P', b'N', b'G', 0, 0, 0, 0],
+        )?;
+        fs::write(project.join("bundle.rs"), "x".repeat(200))?;
+        let output_path = temp.path().join("rejected_output.txt");
+        let mut options = scan_options(false, Vec::new());
+        options.max_file_size = Some(100);
+        options.tree.enabled = true;
+
+        let summary = write_folder_tags(
+            &project,
+            &options,
+            &RenderOptions::default(),
+            &OutputBudget::default(),
+            &ChunkLimit::default(),
+            &HeuristicTokenizer,
+            &output_path,
+        )?;
+
+        assert_eq!(summary.files_written, 1);
+        assert_eq!(
+            (summary.files_skipped, summary.skipped_non_utf8_files),
+            (0, 0)
+        );
+        assert_eq!(
+            summary.rejected_files,
+            vec![
+                RejectedFile {
+                    path: "bundle.rs".to_string(),
+                    reason: SkipReason::TooLarge {
+                        size: 200,
+                        limit: 100
+                    },
+                },
+                RejectedFile {
+                    path: "logo.rs".to_string(),
+                    reason: SkipReason::Binary,
+                },
+            ]
+        );
+        let output = fs::read_to_string(output_path)?;
+        assert!(output.contains("[-] bundle.rs"));
+        assert!(output.contains("[-] logo.rs"));
+        assert!(!output.contains("<logo.rs>"));
+        assert!(!output.contains("xxxx"));
+        Ok(())
+    }
+
+    #[test]
+    fn files_are_written_in_sorted_order_with_priorities_first() -> std::io::Result<()> {
+        let (temp, project) = project_fixture()?;
+        fs::create_dir_all(project.join("src"))?;
+        fs::write(project.join("src").join("main.rs"), "// main")?;
+        fs::write(project.join("src").join("lib.rs"), "// lib")?;
+        fs::write(project.join("build.rs"), "// build")?;
+        fs::write(project.join("zeta.rs"), "// zeta")?;
+        let output_path = temp.path().join("ordered_output.txt");
+        let mut options = scan_options(true, Vec::new());
+        options.priority_patterns = vec!["src/main.rs".to_string()];
+
+        write_folder_tags(
+            &project,
+            &options,
+            &RenderOptions::default(),
+            &OutputBudget::default(),
+            &ChunkLimit::default(),
+            &HeuristicTokenizer,
+            &output_path,
+        )?;
+
+        let output = fs::read_to_string(output_path)?;
+        let positions: Vec<usize> = ["// main", "// build", "// lib", "// zeta"]
+            .iter()
+            .map(|marker| output.find(marker).expect("file is written"))
+            .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn markdown_format_writes_fenced_blocks_and_additional_commands() -> std::io::Result<()> {
+        let (temp, project) = project_fixture()?;
+        fs::write(project.join("main.rs"), "fn main() {}\n")?;
+        let output_path = temp.path().join("markdown_output.md");
+        let render = RenderOptions {
+            format: OutputFormat::Markdown,
+            path_style: PathStyle::Native,
+            text: Default::default(),
+            template: None,
+            tree: None,
+            diff: None,
+            metadata: Vec::new(),
+            line_numbers: false,
+            preset_texts: Vec::new(),
+            additional_commands: "Explain main".to_string(),
+        };
+
+        let summary = write_folder_tags(
+            &project,
+            &scan_options(false, Vec::new()),
+            &render,
+            &OutputBudget::default(),
+            &ChunkLimit::default(),
+            &HeuristicTokenizer,
+            &output_path,
+        )?;
+
+        assert_eq!(summary.files_written, 1);
+        let output = fs::read_to_string(output_path)?;
+        assert!(output.starts_with(OUTPUT_MARKER));
+        assert!(output.contains("### main.rs\n\n```rust\nfn main() {}\n```\n"));
+        assert!(output.contains("## Additional Commands\n\nExplain main\n"));
+        assert!(!output.contains("<main.rs>"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn path_style_is_applied_to_written_paths() -> std::io::Result<()> {
+        let (temp, project) = project_fixture()?;
+        fs::create_dir_all(project.join("src"))?;
+        fs::write(project.join("src").join("main.rs"), "fn main() {}")?;
+        let output_path = temp.path().join("styled_output.txt");
+        let render = RenderOptions {
+            path_style: PathStyle::Backslash,
+            ..RenderOptions::default()
+        };
+
+        write_folder_tags(
+            &project,
+            &scan_options(true, Vec::new()),
+            &render,
+            &OutputBudget::default(),
+            &ChunkLimit::default(),
+            &HeuristicTokenizer,
+            &output_path,
+        )?;
+
+        let output = fs::read_to_string(output_path)?;
+        assert!(output.contains("<src\\main.rs>\nfn main() {}\n</src\\main.rs>"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn pattern_exclusions_are_totalled_by_pattern() {
+        let exclusion = |pattern: &str, files_excluded| PatternExclusion {
+            pattern: pattern.to_string(),
+            files_excluded,
+        };
+        let mut totals = Vec::new();
+
+        add_pattern_exclusions(
+            &mut totals,
+            vec![exclusion("*.lock", 1), exclusion("gen/**", 2)],
+        );
+        add_pattern_exclusions(
+            &mut totals,
+            vec![exclusion("dist/**", 4), exclusion("*.lock", 3)],
+        );
+
+        assert_eq!(
+            totals,
+            vec![
+                exclusion("*.lock", 4),
+                exclusion("gen/**", 2),
+                exclusion("dist/**", 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn roots_are_written_in_order_under_their_prefixes() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let api = temp.path().join("api");
+        let web = temp.path().join("web");
+        fs::create_dir_all(api.join("src"))?;
+        fs::create_dir_all(api.join("target"))?;
+        fs::create_dir_all(web.join("dist"))?;
+        fs::write(api.join("src").join("main.rs"), "fn main() {}")?;
+        fs::write(api.join("target").join("gen.rs"), "// generated")?;
+        fs::write(api.join("client.ts"), "export {}")?;
+        fs::write(web.join("index.ts"), "export {}")?;
+        fs::write(web.join("dist").join("bundle.ts"), "// bundled")?;
+        fs::write(web.join("server.rs"), "fn serve() {}")?;
+        let roots = [
+            ScanRoot {
+                dir: api.clone(),
+                options: ScanOptions {
+                    path_prefix: Some("backend".to_string()),
+                    tree: TreeOptions {
+                        enabled: true,
+                        max_depth: None,
+                    },
+                    ..scan_options(true, vec!["target".to_string()])
+                },
+            },
+            ScanRoot {
+                dir: web.clone(),
+                options: ScanOptions {
+                    valid_exts: vec!["ts".to_string()],
+                    path_prefix: Some("web".to_string()),
+                    tree: TreeOptions {
+                        enabled: true,
+                        max_depth: None,
+                    },
+                    ..scan_options(true, vec!["dist".to_string()])
+                },
+            },
+        ];
+        let render = RenderOptions {
+            path_style: PathStyle::ForwardSlash,
+            ..RenderOptions::default()
+        };
+
+        let prepared = prepare_roots(
+            &roots,
+            &render,
+            &OutputBudget::default(),
+            &HeuristicTokenizer,
+        )?;
+        let written: Vec<&str> = prepared
+            .files
+            .iter()
+            .map(|file| file.display_path.as_str())
+            .collect();
+        assert_eq!(written, vec!["backend/src/main.rs", "web/index.ts"]);
+        let tree = prepared.tree.expect("both roots have a tree");
+        assert!(tree.starts_with("backend/\n"));
+        assert!(tree.contains("[-] client.ts"));
+        assert!(tree.contains("\nweb/\n"));
+        assert!(tree.contains("[-] server.rs"));
+        assert!(!tree.contains("bundle.ts"));
+
+        let output_path = temp.path().join("out.txt");
+        let summary = write_roots(
+            &roots,
+            &render,
+            &OutputBudget::default(),
+            &ChunkLimit::default(),
+            &HeuristicTokenizer,
+            &output_path,
+        )?;
+        assert_eq!(summary.files_written, 2);
+        let output = fs::read_to_string(output_path)?;
+        assert!(output.contains("<backend/src/main.rs>\nfn main() {}\n</backend/src/main.rs>"));
+        assert!(output.find("<backend/src/main.rs>") < output.find("<web/index.ts>"));
+
+        let error = prepare_roots(&[], &render, &OutputBudget::default(), &HeuristicTokenizer)
+            .expect_err("no roots");
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        Ok(())
+    }
 }
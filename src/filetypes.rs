@@ -8,6 +8,10 @@ const FILETYPES_FILE: &str = "filetypes.json";
 pub struct FileTypeGroup {
     pub name: String,
     pub extensions: Vec<String>,
+    /// Exact file names (`Makefile`) or file-name globs (`Dockerfile.*`) selected regardless of
+    /// extension.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filenames: Vec<String>,
 }
 
 /// Finds a file type group by name using case-insensitive exact matching.
@@ -34,6 +38,93 @@ pub fn format_available_filetype_groups(groups: &[FileTypeGroup]) -> String {
         .join("\n")
 }
 
+/// Normalises a user-entered extension: surrounding whitespace and leading dots are removed and,
+/// unless `keep_case` is set, ASCII letters are lowercased, so `.RS`, ` rs`, and `rs` all become
+/// `rs`. Compound extensions keep their inner dots (`.D.TS` becomes `d.ts`).
+///
+/// Strict-case scans keep the configured case, so an upper-case-only extension such as `R` can
+/// still be selected; case-insensitive scans can lowercase, since case no longer matters.
+pub fn normalize_extension(extension: &str, keep_case: bool) -> String {
+    let extension = extension.trim().trim_start_matches('.');
+    if keep_case {
+        extension.to_string()
+    } else {
+        extension.to_ascii_lowercase()
+    }
+}
+
+/// Normalises each of `extensions` (see [`normalize_extension`]), dropping empty entries and later
+/// duplicates.
+pub fn normalize_extensions<'a>(
+    extensions: impl IntoIterator<Item = &'a str>,
+    keep_case: bool,
+) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for extension in extensions
+        .into_iter()
+        .map(|extension| normalize_extension(extension, keep_case))
+    {
+        if !extension.is_empty() && !normalized.contains(&extension) {
+            normalized.push(extension);
+        }
+    }
+    normalized
+}
+
+/// Returns `true` if the file name of `path` ends in `.` followed by one of `extensions`, with at
+/// least one character before that dot.
+///
+/// Extensions are matched as whole name suffixes, so compound extensions work: `types.d.ts`
+/// matches both `ts` and `d.ts`, while `.gitignore` matches neither `gitignore` nor anything else.
+/// ASCII case is ignored unless `strict_case` is set, in which case `rs` no longer matches
+/// `MAIN.RS`. `extensions` are expected to be normalised (see [`normalize_extension`]), keeping
+/// their case when `strict_case` is set.
+pub fn has_extension(path: &Path, extensions: &[String], strict_case: bool) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+
+    extensions.iter().any(|extension| {
+        let Some(stem_length) = name.len().checked_sub(extension.len() + 1) else {
+            return false;
+        };
+        if stem_length == 0 || !name.is_char_boundary(stem_length) {
+            return false;
+        }
+        let Some(suffix) = name[stem_length..].strip_prefix('.') else {
+            return false;
+        };
+        extension_matches(suffix, extension, strict_case)
+    })
+}
+
+/// Compares an extension found on a file with a configured one, ignoring ASCII case unless
+/// `strict_case` is set.
+pub fn extension_matches(found: &str, configured: &str, strict_case: bool) -> bool {
+    if strict_case {
+        found == configured
+    } else {
+        found.eq_ignore_ascii_case(configured)
+    }
+}
+
+/// Normalises the extensions of every group in place and returns `true` if any changed.
+///
+/// Used to migrate `filetypes.json` files written before extensions were normalised, which may
+/// hold entries such as `.rs` or ` rs`. The configured case is kept so strict-case runs can use
+/// it; case-insensitive runs lowercase the extensions when they resolve a group.
+pub fn normalize_groups(groups: &mut [FileTypeGroup]) -> bool {
+    let mut changed = false;
+    for group in groups {
+        let normalized = normalize_extensions(group.extensions.iter().map(String::as_str), true);
+        if normalized != group.extensions {
+            group.extensions = normalized;
+            changed = true;
+        }
+    }
+    changed
+}
+
 /// Loads file type groups from `filetypes.json` or initializes with a default set if the file is missing.
 ///
 /// # Purpose
@@ -45,6 +136,8 @@ pub fn format_available_filetype_groups(groups: &[FileTypeGroup]) -> String {
 /// - If the file exists:
 ///   - Attempts to read and deserialize its contents into a `Vec<FileTypeGroup>`.
 ///   - Returns an empty list if deserialization fails (malformed or incompatible structure).
+///   - Normalises every group's extensions (see [`normalize_groups`]) and saves the file back if
+///     any entry changed, so `.rs` written by older versions becomes `rs`.
 /// - If the file does not exist:
 ///   - Constructs a default list of file type groups:
 ///     - "Rust": `["rs"]`
@@ -58,6 +151,7 @@ pub fn format_available_filetype_groups(groups: &[FileTypeGroup]) -> String {
 ///
 /// # Side Effects
 /// - May create or overwrite `filetypes.json` with default values if it does not exist.
+/// - Rewrites `filetypes.json` when migrating extensions that were not normalised.
 ///
 /// # Panics
 /// - This function does not panic.
@@ -68,10 +162,13 @@ pub fn format_available_filetype_groups(groups: &[FileTypeGroup]) -> String {
 /// [
 ///   { "name": "Rust", "extensions": ["rs"] },
 ///   { "name": "JSON", "extensions": ["json"] },
-///   { "name": "Lua", "extensions": ["lua"] }
+///   { "name": "Lua", "extensions": ["lua"] },
+///   { "name": "Build", "extensions": ["mk"], "filenames": ["Makefile", "Dockerfile*"] }
 /// ]
 /// ```
 ///
+/// `filenames` is optional; groups saved before it existed load with no file names.
+///
 /// # Example
 /// ```rust
 /// let groups = get_filetypes();
@@ -86,20 +183,27 @@ pub fn format_available_filetype_groups(groups: &[FileTypeGroup]) -> String {
 pub fn get_filetypes() -> Vec<FileTypeGroup> {
     if Path::new(FILETYPES_FILE).exists() {
         let data = fs::read_to_string(FILETYPES_FILE).unwrap_or_default();
-        serde_json::from_str(&data).unwrap_or_default()
+        let mut groups: Vec<FileTypeGroup> = serde_json::from_str(&data).unwrap_or_default();
+        if normalize_groups(&mut groups) {
+            save_filetypes(&groups);
+        }
+        groups
     } else {
         let default = vec![
             FileTypeGroup {
                 name: "Rust".into(),
                 extensions: vec!["rs".into()],
+                filenames: Vec::new(),
             },
             FileTypeGroup {
                 name: "JSON".into(),
                 extensions: vec!["json".into()],
+                filenames: Vec::new(),
             },
             FileTypeGroup {
                 name: "Lua".into(),
                 extensions: vec!["lua".into()],
+                filenames: Vec::new(),
             },
         ];
         save_filetypes(&default);
@@ -118,6 +222,7 @@ pub fn get_filetypes() -> Vec<FileTypeGroup> {
 ///   - Each group includes:
 ///     - `name`: A descriptive label (e.g., `"Rust"`, `"Web"`).
 ///     - `extensions`: A list of associated file extensions, e.g., `["rs", "ron"]`.
+///     - `filenames`: Exact file names or file-name globs, e.g., `["Makefile"]` (omitted when empty).
 ///
 /// # Behavior
 /// - Serializes the list into pretty-printed JSON using `serde_json`.
@@ -156,6 +261,7 @@ pub fn get_filetypes() -> Vec<FileTypeGroup> {
 ///     FileTypeGroup {
 ///         name: "Web".to_string(),
 ///         extensions: vec!["html".to_string(), "css".to_string(), "js".to_string()],
+///         filenames: vec![".htaccess".to_string()],
 ///     }
 /// ];
 /// save_filetypes(&groups);
@@ -167,12 +273,17 @@ pub fn save_filetypes(groups: &[FileTypeGroup]) {
 
 #[cfg(test)]
 mod tests {
-    use super::{find_filetype_group, format_available_filetype_groups, FileTypeGroup};
+    use super::{
+        find_filetype_group, format_available_filetype_groups, has_extension, normalize_extensions,
+        normalize_groups, FileTypeGroup,
+    };
+    use std::path::Path;
 
     fn group(name: &str) -> FileTypeGroup {
         FileTypeGroup {
             name: name.to_string(),
             extensions: vec![name.to_lowercase()],
+            filenames: Vec::new(),
         }
     }
 
@@ -228,4 +339,50 @@ mod tests {
             "- Rust\n- JSON\n- Lua"
         );
     }
+
+    #[test]
+    fn extensions_are_normalised_and_deduplicated() {
+        assert_eq!(
+            normalize_extensions([".RS", " rs ", "..D.ts", "", ".", "toml"], false),
+            vec!["rs", "d.ts", "toml"]
+        );
+        assert_eq!(
+            normalize_extensions([".R", " r ", "R", "..D.ts"], true),
+            vec!["R", "r", "D.ts"]
+        );
+    }
+
+    #[test]
+    fn has_extension_matches_compound_suffixes_and_ignores_case_unless_strict() {
+        let extensions = vec!["rs".to_string(), "d.ts".to_string(), "tar.gz".to_string()];
+        let matches = |name: &str, strict| has_extension(Path::new(name), &extensions, strict);
+
+        assert!(matches("src/main.rs", false));
+        assert!(matches("src/MAIN.RS", false));
+        assert!(!matches("src/MAIN.RS", true));
+        assert!(matches("types/index.d.ts", true));
+        assert!(!matches("types/index.ts", false));
+        assert!(matches("release.TAR.GZ", false));
+        assert!(!matches("release.gz", false));
+        assert!(!matches(".rs", false));
+        assert!(!matches("d.ts", false));
+        assert!(!matches("crates", false));
+
+        let upper_case = vec!["R".to_string()];
+        assert!(has_extension(Path::new("plot.R"), &upper_case, true));
+        assert!(!has_extension(Path::new("plot.r"), &upper_case, true));
+    }
+
+    #[test]
+    fn normalize_groups_migrates_unnormalised_entries() {
+        let mut groups = vec![FileTypeGroup {
+            name: "Rust".to_string(),
+            extensions: vec![".rs".to_string(), " rs".to_string(), ".R".to_string()],
+            filenames: Vec::new(),
+        }];
+
+        assert!(normalize_groups(&mut groups));
+        assert_eq!(groups[0].extensions, vec!["rs", "R"]);
+        assert!(!normalize_groups(&mut groups));
+    }
 }
@@ -0,0 +1,1371 @@
+//! # Output Formats Module
+//!
+//! Renders the selected files, footer instructions, and additional commands into one of several
+//! document layouts. `file_ops` decides *which* files are written and reads them; the renderers in
+//! this module decide *how* each piece looks.
+//!
+//! # Formats
+//! - [`OutputFormat::Tags`] (default): each file wrapped in `<relative\path>` … `</relative\path>` tags.
+//! - [`OutputFormat::Markdown`]: a `### path` heading followed by a fenced code block per file.
+//! - [`OutputFormat::Json`]: a single JSON document with project metadata and a `files` array.
+//! - [`OutputFormat::Jsonl`]: one JSON record per line, starting with a project record.
+//! - [`OutputFormat::Xml`]: a well-formed XML document with `<file path="…">` elements and CDATA contents.
+//! - A user template (see `templates`) replaces the selected format entirely when supplied.
+//!
+//! # Architecture Notes
+//! - Every renderer implements [`OutputRenderer`], which is driven in three phases:
+//!   [`OutputRenderer::begin`], one [`OutputRenderer::write_file`] per file, then [`OutputRenderer::finish`].
+//! - Renderers receive a [`RenderContext`] describing the project and the presets/additional
+//!   commands, so structured formats can keep them as separate fields while text formats combine them.
+//! - With [`RenderOptions::line_numbers`], the tags, Markdown, and XML formats write numbered file
+//!   contents (see [`number_lines`]) and explain the numbering in the footer.
+//! - Optional per-file metadata (see `metadata`) is written as attributes, Markdown front matter,
+//!   or JSON fields, depending on the format.
+//! - File paths are rendered once, by [`PathStyle::render`], so every format uses the same separators.
+//! - When the directory tree is enabled, [`RenderOptions::tree`] holds the rendered tree and every
+//!   format writes it before the first file (in the first part only).
+//! - When a diff is requested, [`RenderOptions::diff`] holds the unified diff and every format
+//!   writes it after the last file, before the footer (in the final part only).
+//! - When the output is split into parts, [`RenderContext::part`] is set and every format records
+//!   "part i of n" right after the marker; only the final part carries the footer and commands.
+//! - Every format starts with [`OUTPUT_MARKER`] (as a line, or as the `generated_by` field of the
+//!   first JSON value) so later scans can recognise generated files. It is written on every run,
+//!   not only with `--skip-previous-outputs`, because outputs are recognised when a later run
+//!   scans them and stale parts are only removed when they carry it.
+
+use crate::metadata::{FileMetadata, MetadataField};
+use crate::output_text::{configured_text, OutputText};
+use crate::templates::TemplateRenderer;
+use crate::tree::TREE_LEGEND;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Header line written at the top of every output file.
+///
+/// Scans with `skip_previous_outputs` enabled treat any file containing this text near its start
+/// as an earlier wrapper output and leave it out, so context files never snowball.
+pub const OUTPUT_MARKER: &str = "<!-- Generated by code-file-wrapper -->";
+
+/// Footer line describing line-numbered contents, added when [`RenderOptions::line_numbers`] applies.
+const LINE_NUMBERS_NOTE: &str = "Every line of file contents starts with its line number, right-aligned and followed by \"| \"; use these numbers to refer to lines, and leave them out of any code you write back.";
+
+/// Layout used for the generated output document.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// XML-style `<relative\path>` tags around each file.
+    #[default]
+    Tags,
+    /// Markdown headings with fenced code blocks.
+    Markdown,
+    /// A single JSON document.
+    Json,
+    /// JSON Lines: one record per line.
+    Jsonl,
+    /// Well-formed XML with escaped attributes and CDATA contents.
+    Xml,
+}
+
+impl OutputFormat {
+    /// All formats, in the order they are offered to users.
+    pub const ALL: [OutputFormat; 5] = [
+        OutputFormat::Tags,
+        OutputFormat::Markdown,
+        OutputFormat::Json,
+        OutputFormat::Jsonl,
+        OutputFormat::Xml,
+    ];
+
+    /// Short user-facing label.
+    pub fn label(self) -> &'static str {
+        match self {
+            OutputFormat::Tags => "Tags",
+            OutputFormat::Markdown => "Markdown",
+            OutputFormat::Json => "JSON",
+            OutputFormat::Jsonl => "JSON Lines",
+            OutputFormat::Xml => "Strict XML",
+        }
+    }
+
+    /// Creates the renderer that produces this format.
+    pub fn renderer(self) -> Box<dyn OutputRenderer> {
+        match self {
+            OutputFormat::Tags => Box::new(TagsRenderer),
+            OutputFormat::Markdown => Box::new(MarkdownRenderer),
+            OutputFormat::Json => Box::new(JsonRenderer::default()),
+            OutputFormat::Jsonl => Box::new(JsonlRenderer),
+            OutputFormat::Xml => Box::new(XmlRenderer),
+        }
+    }
+}
+
+/// Separator used when writing relative file paths into the output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum PathStyle {
+    /// The current platform's separator (`\` on Windows, `/` elsewhere).
+    #[default]
+    Native,
+    /// Always `/`; produces identical output on every platform.
+    ForwardSlash,
+    /// Always `\`, matching Windows-style paths on every platform.
+    Backslash,
+}
+
+impl PathStyle {
+    /// All path styles, in the order they are offered to users.
+    pub const ALL: [PathStyle; 3] = [
+        PathStyle::Native,
+        PathStyle::ForwardSlash,
+        PathStyle::Backslash,
+    ];
+
+    /// Short user-facing label.
+    pub fn label(self) -> &'static str {
+        match self {
+            PathStyle::Native => "Native",
+            PathStyle::ForwardSlash => "Forward slash (/)",
+            PathStyle::Backslash => "Backslash (\\)",
+        }
+    }
+
+    /// Returns the separator this style writes between path components.
+    pub fn separator(self) -> char {
+        match self {
+            PathStyle::Native => std::path::MAIN_SEPARATOR,
+            PathStyle::ForwardSlash => '/',
+            PathStyle::Backslash => '\\',
+        }
+    }
+
+    /// Renders a relative path with this style's separator.
+    ///
+    /// Returns `None` when a component is not valid UTF-8.
+    pub fn render(self, relative_path: &Path) -> Option<String> {
+        let components = relative_path
+            .components()
+            .map(|component| component.as_os_str().to_str())
+            .collect::<Option<Vec<_>>>()?;
+        Some(components.join(&self.separator().to_string()))
+    }
+}
+
+/// Settings that control how the output document is rendered.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Document layout.
+    pub format: OutputFormat,
+    /// Separator used in rendered file paths.
+    pub path_style: PathStyle,
+    /// Header, footer, and section title wording.
+    pub text: OutputText,
+    /// Template source that replaces `format` when set (see `templates`).
+    pub template: Option<String>,
+    /// Rendered directory tree written before the first file (see `tree`), when enabled.
+    pub tree: Option<String>,
+    /// Unified diff written after the last file (see `git::unified_diff`), when requested and
+    /// not empty.
+    pub diff: Option<String>,
+    /// Per-file attributes written with each file (see `metadata`).
+    pub metadata: Vec<MetadataField>,
+    /// Prefixes every line of file contents with its line number (see [`number_lines`]).
+    pub line_numbers: bool,
+    /// Texts of the selected presets, in selection order.
+    pub preset_texts: Vec<String>,
+    /// Manually entered command text.
+    pub additional_commands: String,
+}
+
+impl RenderOptions {
+    /// Creates the renderer for these options: the template when one is set, otherwise `format`.
+    pub fn renderer(&self) -> Box<dyn OutputRenderer> {
+        match &self.template {
+            Some(source) => Box::new(TemplateRenderer::new(source.clone())),
+            None => self.format.renderer(),
+        }
+    }
+
+    /// Returns `true` when file contents are line-numbered: the option is set and the output is
+    /// written in the tags, Markdown, or XML format. JSON formats and templates receive the
+    /// contents verbatim.
+    pub fn numbers_lines(&self) -> bool {
+        self.line_numbers
+            && self.template.is_none()
+            && matches!(
+                self.format,
+                OutputFormat::Tags | OutputFormat::Markdown | OutputFormat::Xml
+            )
+    }
+
+    /// Metadata fields to collect for each file: the selected ones, plus size and line count
+    /// for JSON formats and templates, which always report them.
+    pub fn collected_metadata(&self) -> Vec<MetadataField> {
+        let mut fields = self.metadata.clone();
+        let always_sized = self.template.is_some()
+            || matches!(self.format, OutputFormat::Json | OutputFormat::Jsonl);
+        if always_sized {
+            for field in [MetadataField::Size, MetadataField::Lines] {
+                if !fields.contains(&field) {
+                    fields.push(field);
+                }
+            }
+        }
+        fields
+    }
+
+    /// Joins preset texts and manual commands into the single block used by text formats.
+    ///
+    /// Each non-blank piece is trimmed and surrounded by newlines; the result is blank when there
+    /// is nothing to add, in which case the Additional Commands section is omitted.
+    pub fn combined_additional_commands(&self) -> String {
+        let mut combined_additional = String::new();
+
+        for preset_text in &self.preset_texts {
+            combined_additional.push('\n');
+            combined_additional.push_str(preset_text.trim());
+            combined_additional.push('\n');
+        }
+
+        if !self.additional_commands.trim().is_empty() {
+            combined_additional.push('\n');
+            combined_additional.push_str(self.additional_commands.trim());
+            combined_additional.push('\n');
+        }
+
+        combined_additional
+    }
+}
+
+/// Document-level information shared by every phase of rendering.
+#[derive(Debug, Clone, Copy)]
+pub struct RenderContext<'a> {
+    /// Root folder that was scanned.
+    pub root_dir: &'a Path,
+    /// Format and command text for this document.
+    pub options: &'a RenderOptions,
+    /// Position of this document when the output is split into parts (see `chunks`).
+    pub part: Option<OutputPart>,
+}
+
+/// Position of one document among the numbered parts of a split output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct OutputPart {
+    /// 1-based part number.
+    pub index: usize,
+    /// Total number of parts.
+    pub count: usize,
+}
+
+impl RenderContext<'_> {
+    /// Name of the project, taken from the root folder name.
+    pub fn project_name(&self) -> String {
+        project_name(self.root_dir)
+    }
+}
+
+/// Returns the name of the folder `root_dir` refers to, resolving `.` and similar paths.
+pub fn project_name(root_dir: &Path) -> String {
+    root_dir
+        .canonicalize()
+        .unwrap_or_else(|_| root_dir.to_path_buf())
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// One file ready to be rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderedFile<'a> {
+    /// Path relative to the scanned root, as it should appear in the output.
+    pub path: &'a str,
+    /// Full UTF-8 contents of the file.
+    pub contents: &'a str,
+    /// Token count of `contents` according to the selected tokenizer.
+    pub tokens: usize,
+    /// Attributes requested by [`RenderOptions::metadata`]; unset fields are not written.
+    pub metadata: &'a FileMetadata,
+}
+
+impl RenderedFile<'_> {
+    /// Size in bytes of the whole file on disk, falling back to `contents` when not collected.
+    pub fn size(&self) -> u64 {
+        self.metadata.size.unwrap_or(self.contents.len() as u64)
+    }
+
+    /// Line count of the whole file, falling back to `contents` when not collected.
+    pub fn lines(&self) -> usize {
+        self.metadata
+            .lines
+            .unwrap_or_else(|| self.contents.lines().count())
+    }
+}
+
+/// Writes an output document in a specific format.
+pub trait OutputRenderer {
+    /// Writes everything that precedes the first file, starting with [`OUTPUT_MARKER`].
+    fn begin(&mut self, output: &mut dyn Write, context: &RenderContext<'_>) -> io::Result<()>;
+
+    /// Writes a single file.
+    fn write_file(&mut self, output: &mut dyn Write, file: &RenderedFile<'_>) -> io::Result<()>;
+
+    /// Writes the footer instructions and, unless blank, the presets and additional commands.
+    fn finish(&mut self, output: &mut dyn Write, context: &RenderContext<'_>) -> io::Result<()>;
+}
+
+/// Renders the original XML-style tag layout.
+///
+/// # Output Format
+/// ```text
+/// <!-- Generated by code-file-wrapper -->
+/// <src\main.rs>
+/// // file contents
+/// </src\main.rs>
+///
+/// [Diff]
+/// diff --git a/src/main.rs b/src/main.rs
+/// ...
+///
+/// * The above is the current state of my project.
+/// ...
+///
+/// [Additional Commands]
+/// user instructions...
+/// ```
+#[derive(Debug, Default)]
+pub struct TagsRenderer;
+
+impl OutputRenderer for TagsRenderer {
+    fn begin(&mut self, output: &mut dyn Write, context: &RenderContext<'_>) -> io::Result<()> {
+        writeln!(output, "{OUTPUT_MARKER}")?;
+        write_text_header(output, context)?;
+        if let Some(tree) = &context.options.tree {
+            writeln!(output, "[Project Structure] ({TREE_LEGEND})\n{tree}")?;
+        }
+        Ok(())
+    }
+
+    fn write_file(&mut self, output: &mut dyn Write, file: &RenderedFile<'_>) -> io::Result<()> {
+        writeln!(output, "<{}{}>", file.path, xml_attributes(file.metadata))?;
+        writeln!(output, "{}", file.contents)?;
+        writeln!(output, "</{}>\n", file.path)
+    }
+
+    fn finish(&mut self, output: &mut dyn Write, context: &RenderContext<'_>) -> io::Result<()> {
+        if let Some(diff) = &context.options.diff {
+            writeln!(output, "[Diff]\n{}\n", diff.trim_end())?;
+        }
+
+        let path_description = match context.options.path_style.separator() {
+            '\\' => "Windows-style",
+            _ => "forward-slash",
+        };
+        let default_footer = format!(
+            "* The above is the current state of my project.\n\
+             * Each node above is an XML-wrapped code snippet using relative {path_description} file path tags.\n\
+             * Provide context above and below code changes to be explicit on where any change should occur.\n\
+             * Under text under [Additional Commands] should be read very carefully and followed absolutely"
+        );
+
+        write_text_footer(output, context, &default_footer, "[Additional Commands]")
+    }
+}
+
+/// Renders Markdown with one heading and fenced code block per file.
+///
+/// # Output Format
+/// ````text
+/// ### src/main.rs
+///
+/// ```rust
+/// // file contents
+/// ```
+/// ````
+///
+/// # Notes
+/// - The fence is one backtick longer than the longest backtick run inside the file (minimum three),
+///   so files that contain Markdown code fences cannot terminate the block early.
+/// - The language tag comes from [`language_for_path`].
+/// - A diff is written after the files as a `## Diff` heading and a `diff` code block.
+#[derive(Debug, Default)]
+pub struct MarkdownRenderer;
+
+impl OutputRenderer for MarkdownRenderer {
+    fn begin(&mut self, output: &mut dyn Write, context: &RenderContext<'_>) -> io::Result<()> {
+        writeln!(output, "{OUTPUT_MARKER}\n")?;
+        write_text_header(output, context)?;
+        if let Some(tree) = &context.options.tree {
+            let fence = "`".repeat(longest_backtick_run(tree).max(2) + 1);
+            writeln!(output, "## Project Structure\n\n{TREE_LEGEND}\n")?;
+            writeln!(output, "{fence}text\n{tree}{fence}\n")?;
+        }
+        Ok(())
+    }
+
+    fn write_file(&mut self, output: &mut dyn Write, file: &RenderedFile<'_>) -> io::Result<()> {
+        let fence = "`".repeat(longest_backtick_run(file.contents).max(2) + 1);
+        let language = language_for_path(Path::new(file.path));
+
+        writeln!(output, "### {}\n", file.path)?;
+        let attributes = file.metadata.attributes();
+        if !attributes.is_empty() {
+            writeln!(output, "---")?;
+            for (name, value) in attributes {
+                writeln!(output, "{name}: {value}")?;
+            }
+            writeln!(output, "---\n")?;
+        }
+        writeln!(output, "{fence}{language}")?;
+        write!(output, "{}", file.contents)?;
+        if !file.contents.ends_with('\n') {
+            writeln!(output)?;
+        }
+        writeln!(output, "{fence}\n")
+    }
+
+    fn finish(&mut self, output: &mut dyn Write, context: &RenderContext<'_>) -> io::Result<()> {
+        if let Some(diff) = &context.options.diff {
+            let fence = "`".repeat(longest_backtick_run(diff).max(2) + 1);
+            writeln!(
+                output,
+                "## Diff\n\n{fence}diff\n{}\n{fence}\n",
+                diff.trim_end()
+            )?;
+        }
+
+        let default_footer = "* The above is the current state of my project.\n\
+             * Each section above is a relative file path heading followed by a fenced code block with that file's contents.\n\
+             * Provide context above and below code changes to be explicit on where any change should occur.\n\
+             * Text under the Additional Commands heading should be read very carefully and followed absolutely";
+
+        write_text_footer(output, context, default_footer, "## Additional Commands")
+    }
+}
+
+/// Writes the part line (for split output) and the configured header, each followed by a blank line.
+fn write_text_header(output: &mut dyn Write, context: &RenderContext<'_>) -> io::Result<()> {
+    if let Some(part) = context.part {
+        writeln!(output, "Part {} of {}\n", part.index, part.count)?;
+    }
+
+    match configured_text(&context.options.text.header, "") {
+        Some(header) => writeln!(output, "{header}\n"),
+        None => Ok(()),
+    }
+}
+
+/// Writes the footer instructions and the additional commands section shared by the text formats.
+///
+/// The section title is only written when enabled; the commands themselves are always written
+/// when non-blank.
+fn write_text_footer(
+    output: &mut dyn Write,
+    context: &RenderContext<'_>,
+    default_footer: &str,
+    default_title: &str,
+) -> io::Result<()> {
+    let text = &context.options.text;
+    if let Some(footer) = configured_text(&text.footer, default_footer) {
+        writeln!(output, "{footer}")?;
+        if context.options.numbers_lines() {
+            writeln!(output, "* {LINE_NUMBERS_NOTE}")?;
+        }
+    }
+
+    let additional_commands = context.options.combined_additional_commands();
+    if !additional_commands.trim().is_empty() {
+        writeln!(output)?;
+        if let Some(title) = configured_text(&text.additional_commands_title, default_title) {
+            writeln!(output, "{title}")?;
+        }
+        writeln!(output, "{}\n", additional_commands)?;
+    }
+
+    Ok(())
+}
+
+/// Renders a single JSON document for scripts and other tooling.
+///
+/// # Output Format
+/// ```json
+/// {"generated_by":"<!-- Generated by code-file-wrapper -->","project":{"name":"app","root":"/src/app"},
+/// "files":[
+/// {"path":"src/main.rs","language":"rust","size":13,"lines":1,"tokens":7,"content":"fn main() {}\n"}
+/// ],
+/// "total_tokens":7,
+/// "presets":["Review carefully."],
+/// "additional_commands":"Explain main"}
+/// ```
+///
+/// # Notes
+/// - Files are streamed one per line, so large projects are never buffered in memory.
+/// - `additional_commands` is `null` when no manual commands were supplied.
+/// - `tree` (after `project`) holds the rendered directory tree when it is enabled.
+/// - `diff` (after `total_tokens`) holds the unified diff when one was requested and is not empty.
+/// - `tokens` and `total_tokens` come from the tokenizer selected for the run; the total covers
+///   file contents only.
+/// - The header, footer instructions, and section titles of the text formats are omitted; they
+///   are aimed at human readers.
+#[derive(Debug, Default)]
+pub struct JsonRenderer {
+    files_written: usize,
+    total_tokens: usize,
+}
+
+impl OutputRenderer for JsonRenderer {
+    fn begin(&mut self, output: &mut dyn Write, context: &RenderContext<'_>) -> io::Result<()> {
+        self.files_written = 0;
+        self.total_tokens = 0;
+        write!(output, "{{\"generated_by\":")?;
+        serde_json::to_writer(&mut *output, OUTPUT_MARKER)?;
+        write!(output, ",\"project\":")?;
+        serde_json::to_writer(&mut *output, &ProjectInfo::new(context))?;
+        if let Some(part) = context.part {
+            write!(output, ",\"part\":")?;
+            serde_json::to_writer(&mut *output, &part)?;
+        }
+        if let Some(tree) = &context.options.tree {
+            write!(output, ",\n\"tree\":")?;
+            serde_json::to_writer(&mut *output, tree)?;
+        }
+        write!(output, ",\n\"files\":[")
+    }
+
+    fn write_file(&mut self, output: &mut dyn Write, file: &RenderedFile<'_>) -> io::Result<()> {
+        if self.files_written > 0 {
+            write!(output, ",")?;
+        }
+        writeln!(output)?;
+        serde_json::to_writer(&mut *output, &FileRecord::new(None, file))?;
+        self.files_written += 1;
+        self.total_tokens += file.tokens;
+        Ok(())
+    }
+
+    fn finish(&mut self, output: &mut dyn Write, context: &RenderContext<'_>) -> io::Result<()> {
+        write!(output, "\n],\n\"total_tokens\":{},", self.total_tokens)?;
+        if let Some(diff) = &context.options.diff {
+            write!(output, "\n\"diff\":")?;
+            serde_json::to_writer(&mut *output, diff)?;
+            write!(output, ",")?;
+        }
+        write!(output, "\n\"presets\":")?;
+        serde_json::to_writer(&mut *output, &preset_values(context.options))?;
+        write!(output, ",\n\"additional_commands\":")?;
+        serde_json::to_writer(&mut *output, &additional_commands_value(context.options))?;
+        writeln!(output, "}}")
+    }
+}
+
+/// Renders JSON Lines: a `project` record followed by one `file` record per file.
+///
+/// # Output Format
+/// ```text
+/// {"generated_by":"<!-- Generated by code-file-wrapper -->","type":"project","name":"app","root":"/src/app","presets":[],"additional_commands":null}
+/// {"type":"file","path":"src/main.rs","language":"rust","size":13,"lines":1,"tokens":7,"content":"fn main() {}\n"}
+/// ```
+///
+/// # Notes
+/// - Every record carries a `type` field, so consumers can filter file records without
+///   special-casing the first line.
+/// - The project record carries the rendered directory tree as `tree` when it is enabled.
+/// - A diff is written last, as a `{"type":"diff","content":…}` record.
+#[derive(Debug, Default)]
+pub struct JsonlRenderer;
+
+impl OutputRenderer for JsonlRenderer {
+    fn begin(&mut self, output: &mut dyn Write, context: &RenderContext<'_>) -> io::Result<()> {
+        let record = ProjectRecord {
+            generated_by: OUTPUT_MARKER,
+            record_type: "project",
+            project: ProjectInfo::new(context),
+            part: context.part,
+            tree: context.options.tree.as_deref(),
+            presets: preset_values(context.options),
+            additional_commands: additional_commands_value(context.options),
+        };
+        serde_json::to_writer(&mut *output, &record)?;
+        writeln!(output)
+    }
+
+    fn write_file(&mut self, output: &mut dyn Write, file: &RenderedFile<'_>) -> io::Result<()> {
+        serde_json::to_writer(&mut *output, &FileRecord::new(Some("file"), file))?;
+        writeln!(output)
+    }
+
+    fn finish(&mut self, output: &mut dyn Write, context: &RenderContext<'_>) -> io::Result<()> {
+        if let Some(diff) = &context.options.diff {
+            let record = DiffRecord {
+                record_type: "diff",
+                content: diff,
+            };
+            serde_json::to_writer(&mut *output, &record)?;
+            writeln!(output)?;
+        }
+        Ok(())
+    }
+}
+
+/// Renders a well-formed XML document that any XML parser can read.
+///
+/// # Output Format
+/// ```xml
+/// <?xml version="1.0" encoding="UTF-8"?>
+/// <!-- Generated by code-file-wrapper -->
+/// <project name="app" root="/src/app">
+/// <file path="src/main.rs"><![CDATA[fn main() {}
+/// ]]></file>
+/// <instructions>
+/// <instruction>The above is the current state of my project.</instruction>
+/// ...
+/// </instructions>
+/// <additional-commands><![CDATA[
+/// user instructions...
+/// ]]></additional-commands>
+/// </project>
+/// ```
+///
+/// # Notes
+/// - Attribute values are escaped, so any file name is safe.
+/// - Contents are wrapped in CDATA; a literal `]]>` is split across two CDATA sections.
+/// - Characters that XML 1.0 forbids (most ASCII control characters) are replaced with `U+FFFD`.
+/// - An enabled directory tree becomes a `<tree>` element after the header.
+/// - A diff becomes a `<diff>` element after the last file.
+/// - A configured header becomes a `<header>` element, each footer line an `<instruction>`, and a
+///   custom section title the `title` attribute of `<additional-commands>`.
+#[derive(Debug, Default)]
+pub struct XmlRenderer;
+
+impl OutputRenderer for XmlRenderer {
+    fn begin(&mut self, output: &mut dyn Write, context: &RenderContext<'_>) -> io::Result<()> {
+        let project = ProjectInfo::new(context);
+        writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(output, "{OUTPUT_MARKER}")?;
+        let part = context
+            .part
+            .map(|part| format!(r#" part="{}" parts="{}""#, part.index, part.count))
+            .unwrap_or_default();
+        writeln!(
+            output,
+            r#"<project name="{}" root="{}"{part}>"#,
+            escape_xml_attribute(&project.name),
+            escape_xml_attribute(&project.root)
+        )?;
+
+        if let Some(header) = configured_text(&context.options.text.header, "") {
+            writeln!(output, "<header>{}</header>", xml_cdata(header))?;
+        }
+        if let Some(tree) = &context.options.tree {
+            writeln!(
+                output,
+                r#"<tree legend="{}">{}</tree>"#,
+                escape_xml_attribute(TREE_LEGEND),
+                xml_cdata(tree)
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn write_file(&mut self, output: &mut dyn Write, file: &RenderedFile<'_>) -> io::Result<()> {
+        writeln!(
+            output,
+            r#"<file path="{}"{}>{}</file>"#,
+            escape_xml_attribute(file.path),
+            xml_attributes(file.metadata),
+            xml_cdata(file.contents)
+        )
+    }
+
+    fn finish(&mut self, output: &mut dyn Write, context: &RenderContext<'_>) -> io::Result<()> {
+        let text = &context.options.text;
+        let default_footer = "The above is the current state of my project.\n\
+             Each file element above holds one file's contents, with its relative path in the path attribute.\n\
+             Provide context above and below code changes to be explicit on where any change should occur.\n\
+             Text in the additional-commands element should be read very carefully and followed absolutely";
+        if let Some(diff) = &context.options.diff {
+            writeln!(output, "<diff>{}</diff>", xml_cdata(diff))?;
+        }
+        if let Some(footer) = configured_text(&text.footer, default_footer) {
+            writeln!(output, "<instructions>")?;
+            let note = context.options.numbers_lines().then_some(LINE_NUMBERS_NOTE);
+            for instruction in footer
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .chain(note)
+            {
+                writeln!(
+                    output,
+                    "<instruction>{}</instruction>",
+                    escape_xml_text(instruction)
+                )?;
+            }
+            writeln!(output, "</instructions>")?;
+        }
+
+        let additional_commands = context.options.combined_additional_commands();
+        if !additional_commands.trim().is_empty() {
+            let title = configured_text(&text.additional_commands_title, "")
+                .map(|title| format!(r#" title="{}""#, escape_xml_attribute(title)))
+                .unwrap_or_default();
+            writeln!(
+                output,
+                "<additional-commands{title}>{}</additional-commands>",
+                xml_cdata(&additional_commands)
+            )?;
+        }
+
+        writeln!(output, "</project>")
+    }
+}
+
+/// Formats the set metadata fields as ` name="value"` attributes, in field order.
+fn xml_attributes(metadata: &FileMetadata) -> String {
+    metadata
+        .attributes()
+        .into_iter()
+        .map(|(name, value)| format!(r#" {name}="{}""#, escape_xml_attribute(&value)))
+        .collect()
+}
+
+/// Escapes text for use inside a double-quoted XML attribute value.
+///
+/// Whitespace other than spaces is written as a character reference so parsers do not
+/// normalize it away.
+fn escape_xml_attribute(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' => escaped.push_str("&#9;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            c => escaped.push(xml_char(c)),
+        }
+    }
+    escaped
+}
+
+/// Escapes character data for use between XML tags.
+fn escape_xml_text(value: &str) -> String {
+    value
+        .chars()
+        .map(xml_char)
+        .collect::<String>()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Wraps text in one or more CDATA sections, splitting every `]]>` so it cannot end a section early.
+fn xml_cdata(text: &str) -> String {
+    let text: String = text.chars().map(xml_char).collect();
+    format!("<![CDATA[{}]]>", text.replace("]]>", "]]]]><![CDATA[>"))
+}
+
+/// Replaces characters that are not allowed anywhere in an XML 1.0 document.
+fn xml_char(c: char) -> char {
+    match c {
+        '\t' | '\n' | '\r' => c,
+        '\u{0}'..='\u{1F}' | '\u{FFFE}' | '\u{FFFF}' => char::REPLACEMENT_CHARACTER,
+        c => c,
+    }
+}
+
+#[derive(Serialize)]
+struct ProjectInfo {
+    name: String,
+    root: String,
+}
+
+impl ProjectInfo {
+    fn new(context: &RenderContext<'_>) -> Self {
+        Self {
+            name: context.project_name(),
+            root: context.root_dir.to_string_lossy().into_owned(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ProjectRecord<'a> {
+    generated_by: &'static str,
+    #[serde(rename = "type")]
+    record_type: &'static str,
+    #[serde(flatten)]
+    project: ProjectInfo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    part: Option<OutputPart>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tree: Option<&'a str>,
+    presets: Vec<&'a str>,
+    additional_commands: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct DiffRecord<'a> {
+    #[serde(rename = "type")]
+    record_type: &'static str,
+    content: &'a str,
+}
+
+#[derive(Serialize)]
+struct FileRecord<'a> {
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    record_type: Option<&'static str>,
+    path: &'a str,
+    language: &'a str,
+    size: u64,
+    lines: usize,
+    tokens: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    modified: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha256: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    git_commit: Option<&'a str>,
+    content: &'a str,
+}
+
+impl<'a> FileRecord<'a> {
+    fn new(record_type: Option<&'static str>, file: &RenderedFile<'a>) -> Self {
+        Self {
+            record_type,
+            path: file.path,
+            language: language_for_path(Path::new(file.path)),
+            size: file.size(),
+            lines: file.lines(),
+            tokens: file.tokens,
+            modified: file.metadata.modified.as_deref(),
+            sha256: file.metadata.sha256.as_deref(),
+            git_commit: file.metadata.git_commit.as_deref(),
+            content: file.contents,
+        }
+    }
+}
+
+fn preset_values(options: &RenderOptions) -> Vec<&str> {
+    options
+        .preset_texts
+        .iter()
+        .map(|text| text.trim())
+        .filter(|text| !text.is_empty())
+        .collect()
+}
+
+fn additional_commands_value(options: &RenderOptions) -> Option<&str> {
+    Some(options.additional_commands.trim()).filter(|text| !text.is_empty())
+}
+
+/// Prefixes each line with its 1-based number, right-aligned to the widest number in the file.
+///
+/// ```text
+///  9| fn main() {
+/// 10|     run();
+/// ```
+/// Line endings (including `\r\n`) and a missing final newline are preserved.
+pub fn number_lines(contents: &str) -> String {
+    let width = contents.lines().count().max(1).to_string().len();
+    let mut numbered = String::with_capacity(contents.len() + contents.len() / 8);
+    for (index, line) in contents.split_inclusive('\n').enumerate() {
+        numbered.push_str(&format!("{:>width$}| {line}", index + 1));
+    }
+    numbered
+}
+
+/// Returns the conventional code-fence language name for a file, based on its extension.
+///
+/// Unknown extensions are returned unchanged (most highlighters accept extensions as aliases);
+/// files without an extension get an empty tag.
+pub fn language_for_path(path: &Path) -> &str {
+    let Some(extension) = path.extension().and_then(|e| e.to_str()) else {
+        return "";
+    };
+
+    match extension.to_ascii_lowercase().as_str() {
+        "rs" => "rust",
+        "py" | "pyw" => "python",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "jsx",
+        "ts" | "mts" | "cts" => "typescript",
+        "tsx" => "tsx",
+        "json" => "json",
+        "toml" => "toml",
+        "yaml" | "yml" => "yaml",
+        "md" | "markdown" => "markdown",
+        "sh" | "bash" => "bash",
+        "ps1" | "psm1" => "powershell",
+        "bat" | "cmd" => "batch",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx" => "cpp",
+        "cs" => "csharp",
+        "go" => "go",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "lua" => "lua",
+        "rb" => "ruby",
+        "php" => "php",
+        "swift" => "swift",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "scss" => "scss",
+        "sql" => "sql",
+        "xml" => "xml",
+        "ahk" => "autohotkey",
+        "txt" => "text",
+        _ => extension,
+    }
+}
+
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(format: OutputFormat, files: &[RenderedFile<'_>], additional: &str) -> String {
+        let options = RenderOptions {
+            format,
+            path_style: PathStyle::Native,
+            text: OutputText::default(),
+            template: None,
+            tree: None,
+            diff: None,
+            metadata: Vec::new(),
+            line_numbers: false,
+            preset_texts: Vec::new(),
+            additional_commands: additional.to_string(),
+        };
+        render_with(&options, files)
+    }
+
+    fn render_with(options: &RenderOptions, files: &[RenderedFile<'_>]) -> String {
+        render_part(options, files, None)
+    }
+
+    fn render_part(
+        options: &RenderOptions,
+        files: &[RenderedFile<'_>],
+        part: Option<OutputPart>,
+    ) -> String {
+        let context = RenderContext {
+            root_dir: Path::new("project"),
+            options,
+            part,
+        };
+        let mut output = Vec::new();
+        let mut renderer = options.format.renderer();
+        renderer.begin(&mut output, &context).expect("begin");
+        for file in files {
+            renderer.write_file(&mut output, file).expect("file");
+        }
+        renderer.finish(&mut output, &context).expect("finish");
+        String::from_utf8(output).expect("UTF-8 output")
+    }
+
+    fn json_options(format: OutputFormat) -> RenderOptions {
+        RenderOptions {
+            format,
+            path_style: PathStyle::Native,
+            text: OutputText::default(),
+            template: None,
+            tree: None,
+            diff: None,
+            metadata: Vec::new(),
+            line_numbers: false,
+            preset_texts: vec!["  Be careful.  ".to_string()],
+            additional_commands: "Explain main".to_string(),
+        }
+    }
+
+    const MAIN_RS: RenderedFile<'static> = RenderedFile {
+        path: "src/main.rs",
+        contents: "fn main() {}\n// done\n",
+        tokens: 3,
+        metadata: &FileMetadata::NONE,
+    };
+
+    #[test]
+    fn markdown_uses_heading_and_language_fence() {
+        let output = render(
+            OutputFormat::Markdown,
+            &[RenderedFile {
+                path: "src/main.rs",
+                contents: "fn main() {}",
+                tokens: 3,
+                metadata: &FileMetadata::NONE,
+            }],
+            "",
+        );
+
+        assert!(output.starts_with(OUTPUT_MARKER));
+        assert!(output.contains("### src/main.rs\n\n```rust\nfn main() {}\n```\n"));
+        assert!(!output.contains("## Additional Commands"));
+    }
+
+    #[test]
+    fn markdown_fence_is_longer_than_backticks_in_contents() {
+        let output = render(
+            OutputFormat::Markdown,
+            &[RenderedFile {
+                path: "README.md",
+                contents: "````sh\nls\n````\n",
+                tokens: 3,
+                metadata: &FileMetadata::NONE,
+            }],
+            "",
+        );
+
+        assert!(output.contains("`````markdown\n````sh\nls\n````\n`````\n"));
+    }
+
+    #[test]
+    fn markdown_additional_commands_get_their_own_heading() {
+        let output = render(OutputFormat::Markdown, &[], "\nplease review\n");
+
+        assert!(output.contains("## Additional Commands\n\nplease review\n"));
+    }
+
+    #[test]
+    fn text_formats_combine_presets_before_manual_commands() {
+        let options = RenderOptions {
+            format: OutputFormat::Tags,
+            path_style: PathStyle::Native,
+            text: OutputText::default(),
+            template: None,
+            tree: None,
+            diff: None,
+            metadata: Vec::new(),
+            line_numbers: false,
+            preset_texts: vec!["preset one ".to_string()],
+            additional_commands: " manual\n".to_string(),
+        };
+
+        assert_eq!(
+            options.combined_additional_commands(),
+            "\npreset one\n\nmanual\n"
+        );
+    }
+
+    #[test]
+    fn json_document_has_metadata_files_and_separate_commands() {
+        let output = render_with(&json_options(OutputFormat::Json), &[MAIN_RS, MAIN_RS]);
+
+        let document: serde_json::Value = serde_json::from_str(&output).expect("valid JSON");
+        assert_eq!(document["generated_by"], OUTPUT_MARKER);
+        assert_eq!(document["project"]["name"], "project");
+        assert_eq!(document["files"].as_array().map(Vec::len), Some(2));
+        assert_eq!(document["files"][0]["path"], "src/main.rs");
+        assert_eq!(document["files"][0]["language"], "rust");
+        assert_eq!(document["files"][0]["size"], 21);
+        assert_eq!(document["files"][0]["lines"], 2);
+        assert_eq!(document["files"][0]["tokens"], 3);
+        assert_eq!(document["total_tokens"], 6);
+        assert_eq!(document["files"][0]["content"], MAIN_RS.contents);
+        assert_eq!(document["presets"], serde_json::json!(["Be careful."]));
+        assert_eq!(document["additional_commands"], "Explain main");
+    }
+
+    #[test]
+    fn json_document_without_files_or_commands_is_valid() {
+        let output = render(OutputFormat::Json, &[], "");
+
+        let document: serde_json::Value = serde_json::from_str(&output).expect("valid JSON");
+        assert_eq!(document["files"], serde_json::json!([]));
+        assert!(document["additional_commands"].is_null());
+    }
+
+    #[test]
+    fn jsonl_writes_project_record_then_one_record_per_file() {
+        let output = render_with(&json_options(OutputFormat::Jsonl), &[MAIN_RS, MAIN_RS]);
+
+        let records: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("valid JSON line"))
+            .collect();
+        assert_eq!(records.len(), 3);
+        assert!(output.starts_with(&format!("{{\"generated_by\":\"{OUTPUT_MARKER}\"")));
+        assert_eq!(records[0]["type"], "project");
+        assert_eq!(records[0]["additional_commands"], "Explain main");
+        assert_eq!(records[1]["type"], "file");
+        assert_eq!(records[2]["content"], MAIN_RS.contents);
+    }
+
+    #[test]
+    fn tags_format_matches_original_layout() {
+        let output = render(
+            OutputFormat::Tags,
+            &[RenderedFile {
+                path: "lib.rs",
+                contents: "pub fn lib() {}",
+                tokens: 3,
+                metadata: &FileMetadata::NONE,
+            }],
+            "\nextra\n",
+        );
+
+        assert!(output.contains("<lib.rs>\npub fn lib() {}\n</lib.rs>\n\n"));
+        assert!(output.ends_with("\n[Additional Commands]\n\nextra\n\n\n"));
+    }
+
+    #[test]
+    fn path_style_controls_separators() {
+        let path = Path::new("src").join("bin").join("tool.rs");
+
+        assert_eq!(
+            PathStyle::ForwardSlash.render(&path).as_deref(),
+            Some("src/bin/tool.rs")
+        );
+        assert_eq!(
+            PathStyle::Backslash.render(&path).as_deref(),
+            Some("src\\bin\\tool.rs")
+        );
+        assert_eq!(
+            PathStyle::Native.render(&path),
+            path.to_str().map(str::to_string)
+        );
+    }
+
+    #[test]
+    fn tags_footer_describes_path_style() {
+        let mut options = json_options(OutputFormat::Tags);
+        options.path_style = PathStyle::Backslash;
+        let backslash = render_with(&options, &[]);
+        options.path_style = PathStyle::ForwardSlash;
+        let forward = render_with(&options, &[]);
+
+        assert!(backslash.contains("using relative Windows-style file path tags."));
+        assert!(forward.contains("using relative forward-slash file path tags."));
+    }
+
+    #[test]
+    fn text_formats_use_configured_header_footer_and_title() {
+        let mut options = json_options(OutputFormat::Markdown);
+        options.text = OutputText {
+            header: Some("Snapshot for review".to_string()),
+            footer: Some("* Reply with full files.".to_string()),
+            additional_commands_title: Some("## Tasks".to_string()),
+        };
+
+        let output = render_with(&options, &[MAIN_RS]);
+
+        assert!(output.starts_with(&format!(
+            "{OUTPUT_MARKER}\n\nSnapshot for review\n\n### src/main.rs"
+        )));
+        assert!(output.contains("* Reply with full files.\n\n## Tasks\n\nBe careful.\n"));
+        assert!(!output.contains("current state of my project"));
+    }
+
+    #[test]
+    fn disabled_footer_and_title_leave_only_commands() {
+        let mut options = json_options(OutputFormat::Tags);
+        options.text = OutputText {
+            header: None,
+            footer: Some(String::new()),
+            additional_commands_title: Some(String::new()),
+        };
+
+        let output = render_with(&options, &[]);
+
+        assert_eq!(
+            output,
+            format!("{OUTPUT_MARKER}\n\n\nBe careful.\n\nExplain main\n\n\n")
+        );
+    }
+
+    #[test]
+    fn xml_renders_configured_text_as_elements() {
+        let mut options = json_options(OutputFormat::Xml);
+        options.text = OutputText {
+            header: Some("Intro".to_string()),
+            footer: Some("* one\n\n* two & three".to_string()),
+            additional_commands_title: Some("Tasks".to_string()),
+        };
+
+        let output = render_with(&options, &[]);
+
+        assert!(output.contains("<header><![CDATA[Intro]]></header>"));
+        assert!(output.contains(
+            "<instruction>* one</instruction>\n<instruction>* two &amp; three</instruction>"
+        ));
+        assert!(output.contains(r#"<additional-commands title="Tasks">"#));
+    }
+
+    #[test]
+    fn xml_escapes_attributes_and_splits_cdata_terminators() {
+        let output = render(
+            OutputFormat::Xml,
+            &[RenderedFile {
+                path: "a&b\"<c>.rs",
+                contents: "if a < b { s = \"]]>\"; }\u{0}",
+                tokens: 3,
+                metadata: &FileMetadata::NONE,
+            }],
+            "do <this>",
+        );
+
+        assert!(output.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+        assert!(output.contains(OUTPUT_MARKER));
+        assert!(output.contains(r#"<file path="a&amp;b&quot;&lt;c&gt;.rs">"#));
+        assert!(output.contains("<![CDATA[if a < b { s = \"]]]]><![CDATA[>\"; }\u{FFFD}]]></file>"));
+        assert!(
+            output.contains("<additional-commands><![CDATA[\ndo <this>\n]]></additional-commands>")
+        );
+        assert!(output.ends_with("</project>\n"));
+    }
+
+    #[test]
+    fn xml_omits_blank_additional_commands() {
+        let output = render(OutputFormat::Xml, &[MAIN_RS], "  ");
+
+        assert!(output.contains(r#"<project name="project" root="project">"#));
+        assert!(!output.contains("<additional-commands>"));
+    }
+
+    #[test]
+    fn parts_are_numbered_in_every_format() {
+        let part = Some(OutputPart { index: 2, count: 3 });
+        let render_as = |format| render_part(&json_options(format), &[MAIN_RS], part);
+
+        assert!(render_as(OutputFormat::Tags)
+            .starts_with(&format!("{OUTPUT_MARKER}\nPart 2 of 3\n\n<src")));
+        assert!(render_as(OutputFormat::Markdown).contains("Part 2 of 3\n\n### src"));
+        assert!(render_as(OutputFormat::Json).contains(r#","part":{"index":2,"count":3},"#));
+        assert!(render_as(OutputFormat::Jsonl).contains(r#""part":{"index":2,"count":3}"#));
+        assert!(render_as(OutputFormat::Xml)
+            .contains(r#"<project name="project" root="project" part="2" parts="3">"#));
+    }
+
+    #[test]
+    fn tree_is_written_before_files_in_every_format() {
+        let render_as = |format| {
+            let options = RenderOptions {
+                tree: Some("project/\n`-- [+] main.rs\n".to_string()),
+                ..json_options(format)
+            };
+            render_part(&options, &[MAIN_RS], None)
+        };
+
+        assert!(render_as(OutputFormat::Tags).contains(&format!(
+            "[Project Structure] ({TREE_LEGEND})\nproject/\n`-- [+] main.rs\n\n<src"
+        )));
+        assert!(render_as(OutputFormat::Markdown)
+            .contains("## Project Structure\n\n[+] included, [~] truncated, [-] filtered out\n\n```text\nproject/\n`-- [+] main.rs\n```\n\n### src"));
+        let json: serde_json::Value =
+            serde_json::from_str(&render_as(OutputFormat::Json)).expect("valid JSON");
+        assert_eq!(json["tree"], "project/\n`-- [+] main.rs\n");
+        let jsonl = render_as(OutputFormat::Jsonl);
+        let project: serde_json::Value =
+            serde_json::from_str(jsonl.lines().next().expect("project record"))
+                .expect("valid JSON");
+        assert_eq!(project["tree"], "project/\n`-- [+] main.rs\n");
+        assert!(render_as(OutputFormat::Xml).contains(
+            "<tree legend=\"[+] included, [~] truncated, [-] filtered out\"><![CDATA[project/"
+        ));
+    }
+
+    #[test]
+    fn diff_is_written_after_files_in_every_format() {
+        let diff = "diff --git a/src/main.rs b/src/main.rs\n-fn main() {}\n+fn main() { run() }\n";
+        let render_as = |format| {
+            let options = RenderOptions {
+                diff: Some(diff.to_string()),
+                ..json_options(format)
+            };
+            render_part(&options, &[MAIN_RS], None)
+        };
+
+        let tags = render_as(OutputFormat::Tags);
+        assert!(tags.contains(&format!("[Diff]\n{diff}\n* The above")));
+        assert!(tags.find("[Diff]") > tags.find("</src"));
+        assert!(render_as(OutputFormat::Markdown)
+            .contains(&format!("## Diff\n\n```diff\n{diff}```\n\n* The above")));
+        let json: serde_json::Value =
+            serde_json::from_str(&render_as(OutputFormat::Json)).expect("valid JSON");
+        assert_eq!(json["diff"], diff);
+        let jsonl = render_as(OutputFormat::Jsonl);
+        let last: serde_json::Value =
+            serde_json::from_str(jsonl.lines().last().expect("diff record")).expect("valid JSON");
+        assert_eq!(last["type"], "diff");
+        assert_eq!(last["content"], diff);
+        assert!(render_as(OutputFormat::Xml)
+            .contains("<diff><![CDATA[diff --git a/src/main.rs b/src/main.rs\n-fn main() {}"));
+        assert!(
+            !render_part(&json_options(OutputFormat::Tags), &[MAIN_RS], None).contains("[Diff]")
+        );
+    }
+
+    #[test]
+    fn metadata_is_written_as_attributes_front_matter_and_fields() {
+        let metadata = FileMetadata {
+            lines: Some(2),
+            sha256: Some("ab12".to_string()),
+            git_commit: Some("c0ffee".to_string()),
+            ..FileMetadata::NONE
+        };
+        let file = RenderedFile {
+            metadata: &metadata,
+            ..MAIN_RS
+        };
+        let render_as = |format| render_part(&json_options(format), &[file], None);
+
+        assert!(render_as(OutputFormat::Tags)
+            .contains(r#"<src/main.rs lines="2" sha256="ab12" git_commit="c0ffee">"#));
+        assert!(render_as(OutputFormat::Markdown).contains(
+            "### src/main.rs\n\n---\nlines: 2\nsha256: ab12\ngit_commit: c0ffee\n---\n\n```rust\n"
+        ));
+        assert!(render_as(OutputFormat::Xml)
+            .contains(r#"<file path="src/main.rs" lines="2" sha256="ab12" git_commit="c0ffee">"#));
+        let json: serde_json::Value =
+            serde_json::from_str(&render_as(OutputFormat::Json)).expect("valid JSON");
+        assert_eq!(json["files"][0]["sha256"], "ab12");
+        assert_eq!(json["files"][0]["git_commit"], "c0ffee");
+        assert!(json["files"][0].get("modified").is_none());
+    }
+
+    #[test]
+    fn number_lines_right_aligns_and_keeps_line_endings() {
+        let contents = format!("{}last", "x\r\n".repeat(9));
+
+        let numbered = number_lines(&contents);
+
+        assert!(numbered.starts_with(" 1| x\r\n 2| x\r\n"));
+        assert!(numbered.ends_with(" 9| x\r\n10| last"));
+        assert_eq!(number_lines(""), "");
+    }
+
+    #[test]
+    fn line_numbers_are_documented_in_text_footers_only() {
+        let options = |format| RenderOptions {
+            line_numbers: true,
+            ..json_options(format)
+        };
+
+        assert!(render_with(&options(OutputFormat::Tags), &[MAIN_RS]).contains(LINE_NUMBERS_NOTE));
+        assert!(
+            render_with(&options(OutputFormat::Markdown), &[MAIN_RS]).contains(LINE_NUMBERS_NOTE)
+        );
+        assert!(render_with(&options(OutputFormat::Xml), &[MAIN_RS])
+            .contains(&format!("<instruction>{LINE_NUMBERS_NOTE}</instruction>")));
+        assert!(!render_with(&options(OutputFormat::Json), &[MAIN_RS]).contains(LINE_NUMBERS_NOTE));
+        assert!(!options(OutputFormat::Jsonl).numbers_lines());
+        assert!(
+            !render_with(&json_options(OutputFormat::Tags), &[MAIN_RS]).contains(LINE_NUMBERS_NOTE)
+        );
+    }
+
+    #[test]
+    fn language_for_path_maps_common_extensions() {
+        assert_eq!(language_for_path(Path::new("a.rs")), "rust");
+        assert_eq!(language_for_path(Path::new("a.YML")), "yaml");
+        assert_eq!(language_for_path(Path::new("a.zig")), "zig");
+        assert_eq!(language_for_path(Path::new("Makefile")), "");
+    }
+}
@@ -2,29 +2,101 @@
 //!
 //! This module is the single generation path used by both GUI and CLI entry points. Each
 //! caller translates user input into a [`TagGenerationRequest`] and then calls
-//! [`generate_tag_output`] to scan files, write tagged output, append preset/manual text,
+//! [`generate_tag_output`] to scan files, write output in the requested format (including preset/manual text),
 //! and optionally copy the result to the clipboard.
 //!
 //! # Architecture Notes
 //! - GUI and CLI flows should build requests instead of generating output independently.
 //! - [`generate_tag_output`] validates generation-level inputs such as the root directory and output path.
+//! - The canonical output path is always excluded from the scan so outputs never wrap themselves.
+//! - [`TagGenerationRequest::additional_roots`] merges more folders into the same output; every
+//!   root's paths are then prefixed with its alias or folder name.
+//! - An optional [`OutputBudget`] caps the output's tokens/bytes; files that do not fit are
+//!   truncated or dropped and reported in the [`GenerationSummary`].
+//! - An optional [`ChunkLimit`] splits the output into numbered parts; the summary lists every
+//!   part and `copy_to_clipboard` copies the first one.
 //! - `file_ops.rs` remains limited to scanning and writing files; it does not own defaults or UI/CLI behavior.
 //! - Output paths are caller-selected: both current entry points default to `tags_output.txt`, but both can override it.
 //! - Callers remain responsible for presenting dialogs, printing summaries, or opening generated files.
 
-use crate::file_ops::{append_additional_commands, write_folder_tags};
+use crate::budget::{DroppedFile, OutputBudget, TruncatedFile};
+use crate::chunks::{part_path, ChunkLimit};
+use crate::content::TextEncoding;
+use crate::file_ops::{
+    canonical_output_path, prepare_roots, write_roots, RejectedFile, ScanOptions, ScanRoot,
+    TranscodedFile,
+};
+use crate::formats::{project_name, OutputFormat, PathStyle, RenderOptions};
+use crate::git::{DiffBase, GitSelection};
+use crate::metadata::MetadataField;
+use crate::ordering::FileOrder;
+use crate::output_text::OutputText;
+use crate::patterns::PatternExclusion;
+use crate::preview::SelectionPreview;
+use crate::templates::validate_template;
+use crate::tokens::{load_tokenizer, FileTokens, Tokenizer};
+use crate::tree::TreeOptions;
 use crate::utils::copy_to_clipboard;
+use std::collections::HashMap;
 use std::io::{Error, ErrorKind};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// A folder scanned into the same output as the request's `root_dir`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceRoot {
+    pub dir: PathBuf,
+    /// Prefix for this root's paths in the output; defaults to the folder name.
+    pub alias: Option<String>,
+    pub extensions: Vec<String>,
+    pub filenames: Vec<String>,
+    pub ignored_folders: Vec<String>,
+}
 
 /// Request data needed to generate a tagged output file.
 #[derive(Debug, Clone)]
 pub struct TagGenerationRequest {
     pub root_dir: PathBuf,
+    /// Prefix for `root_dir`'s paths in the output (e.g. `backend` writes `backend/src/main.rs`).
+    pub root_alias: Option<String>,
+    /// More folders written into the same output, after `root_dir`'s files. They share every
+    /// setting of the request except their file types and ignored folders.
+    pub additional_roots: Vec<SourceRoot>,
     pub extensions: Vec<String>,
+    /// Whether extensions must match case exactly (see `ScanOptions::strict_extension_case`).
+    pub strict_extension_case: bool,
+    /// Exact file names or file-name globs selected regardless of extension.
+    pub filenames: Vec<String>,
+    /// Whether extensionless scripts are selected by their shebang interpreter.
+    pub detect_shebangs: bool,
+    /// Files to wrap, in order, instead of scanning `root_dir` (see `file_list`).
+    pub file_list: Option<Vec<PathBuf>>,
     pub recursive: bool,
     pub ignored_folders: Vec<String>,
+    pub respect_gitignore: bool,
+    /// Limits the scan to tracked, staged, untracked, or changed files.
+    pub git_selection: Option<GitSelection>,
+    pub include_patterns: Vec<String>,
+    pub exclude_patterns: Vec<String>,
+    pub skip_previous_outputs: bool,
+    pub file_order: FileOrder,
+    pub priority_patterns: Vec<String>,
     pub output_path: PathBuf,
+    pub output_format: OutputFormat,
+    pub path_style: PathStyle,
+    pub output_text: OutputText,
+    pub template_path: Option<PathBuf>,
+    pub token_vocab: Option<PathBuf>,
+    pub budget: OutputBudget,
+    pub chunk_limit: ChunkLimit,
+    pub tree: TreeOptions,
+    /// Adds a `[Diff]` section comparing the working tree with this base.
+    pub diff: Option<DiffBase>,
+    pub metadata: Vec<MetadataField>,
+    pub line_numbers: bool,
+    /// Files larger than this many bytes are skipped (`None` for no limit).
+    pub max_file_size: Option<u64>,
+    /// Encodings tried, in order, for files that are not UTF-8 and have no byte order mark.
+    pub fallback_encodings: Vec<TextEncoding>,
     pub additional_commands: String,
     pub preset_texts: Vec<String>,
     pub copy_to_clipboard: bool,
@@ -34,15 +106,113 @@ pub struct TagGenerationRequest {
 /// Summary returned after tagged output generation completes.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GenerationSummary {
+    /// The output file, or the first part when the output was split.
     pub output_path: PathBuf,
+    /// Every file written, in part order (just `output_path` unless the output was split).
+    pub output_paths: Vec<PathBuf>,
     pub files_written: usize,
     pub files_skipped: usize,
     pub skipped_non_utf8_files: usize,
+    /// Files skipped because they look binary or exceed the request's `max_file_size`.
+    pub rejected_files: Vec<RejectedFile>,
+    /// Files transcoded to UTF-8, with the encoding they were read in.
+    pub transcoded_files: Vec<TranscodedFile>,
+    pub pattern_exclusions: Vec<PatternExclusion>,
+    pub files_not_included: usize,
+    pub output_files_skipped: usize,
     pub recursive: bool,
+    /// Name of the tokenizer used for the token counts.
+    pub tokenizer: String,
+    /// Token count of each written file, in output order.
+    pub file_tokens: Vec<FileTokens>,
+    /// Token count of the whole output document.
+    pub total_tokens: usize,
+    /// Files shortened to fit the output budget.
+    pub truncated_files: Vec<TruncatedFile>,
+    /// Files left out because they did not fit the output budget.
+    pub budget_dropped: Vec<DroppedFile>,
 }
 
 /// Generates tagged output for a request without displaying GUI dialogs.
 pub fn generate_tag_output(request: TagGenerationRequest) -> std::io::Result<GenerationSummary> {
+    let GenerationSetup {
+        roots,
+        render_options,
+        tokenizer,
+    } = generation_setup(&request)?;
+    let write_summary = write_roots(
+        &roots,
+        &render_options,
+        &request.budget,
+        &request.chunk_limit,
+        tokenizer.as_ref(),
+        &request.output_path,
+    )?;
+    let mut total_tokens = 0;
+    for path in &write_summary.output_paths {
+        total_tokens += tokenizer.count_tokens(&std::fs::read_to_string(path)?);
+    }
+    let output_path = write_summary
+        .output_paths
+        .first()
+        .cloned()
+        .unwrap_or_else(|| request.output_path.clone());
+
+    // Split output is copied part by part; callers prompt before copying each later part.
+    if request.copy_to_clipboard {
+        let output_path_string = output_path.to_string_lossy();
+        copy_to_clipboard(&output_path_string)?;
+    }
+
+    let _ = request.open_after;
+
+    Ok(GenerationSummary {
+        output_path,
+        output_paths: write_summary.output_paths,
+        files_written: write_summary.files_written,
+        files_skipped: write_summary.files_skipped,
+        skipped_non_utf8_files: write_summary.skipped_non_utf8_files,
+        rejected_files: write_summary.rejected_files,
+        transcoded_files: write_summary.transcoded_files,
+        pattern_exclusions: write_summary.pattern_exclusions,
+        files_not_included: write_summary.files_not_included,
+        output_files_skipped: write_summary.output_files_skipped,
+        recursive: request.recursive,
+        tokenizer: tokenizer.name(),
+        file_tokens: write_summary.file_tokens,
+        total_tokens,
+        truncated_files: write_summary.truncated_files,
+        budget_dropped: write_summary.budget_dropped,
+    })
+}
+
+/// Builds the selection preview for a request: the same scan, reading, and budget decisions as
+/// [`generate_tag_output`], without writing or copying anything.
+pub fn preview_tag_output(request: &TagGenerationRequest) -> std::io::Result<SelectionPreview> {
+    let GenerationSetup {
+        roots,
+        render_options,
+        tokenizer,
+    } = generation_setup(request)?;
+    let prepared = prepare_roots(&roots, &render_options, &request.budget, tokenizer.as_ref())?;
+
+    Ok(SelectionPreview::new(
+        &roots,
+        request.path_style,
+        &prepared,
+        tokenizer.name(),
+    ))
+}
+
+/// Everything derived from a request before files are scanned.
+struct GenerationSetup {
+    roots: Vec<ScanRoot>,
+    render_options: RenderOptions,
+    tokenizer: Box<dyn Tokenizer>,
+}
+
+/// Validates the request's paths and loads its tokenizer and template.
+fn generation_setup(request: &TagGenerationRequest) -> std::io::Result<GenerationSetup> {
     if !request.root_dir.is_dir() {
         return Err(Error::new(
             ErrorKind::InvalidInput,
@@ -50,6 +220,20 @@ pub fn generate_tag_output(request: TagGenerationRequest) -> std::io::Result<Gen
         ));
     }
 
+    if let Some(root) = request
+        .additional_roots
+        .iter()
+        .find(|root| !root.dir.is_dir())
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Root '{}' must point to an existing directory",
+                root.dir.display()
+            ),
+        ));
+    }
+
     if request.output_path.is_dir() {
         return Err(Error::new(
             ErrorKind::InvalidInput,
@@ -57,74 +241,212 @@ pub fn generate_tag_output(request: TagGenerationRequest) -> std::io::Result<Gen
         ));
     }
 
-    let write_summary = write_folder_tags(
-        &request.root_dir,
-        &request.extensions,
-        request.recursive,
-        &request.ignored_folders,
-        &request.output_path,
-    )?;
+    Ok(GenerationSetup {
+        roots: scan_roots_for(request)?,
+        tokenizer: load_tokenizer(request.token_vocab.as_deref())?,
+        render_options: render_options_for(request)?,
+    })
+}
 
-    let combined_additional = combine_additional_commands(
-        request.preset_texts.iter().map(String::as_str),
-        &request.additional_commands,
-    );
+/// Reads and validates the request's template, then builds its render options.
+fn render_options_for(request: &TagGenerationRequest) -> std::io::Result<RenderOptions> {
+    let template = match &request.template_path {
+        Some(template_path) => {
+            let source = std::fs::read_to_string(template_path).map_err(|error| {
+                Error::new(
+                    error.kind(),
+                    format!(
+                        "Could not read template '{}': {error}",
+                        template_path.display()
+                    ),
+                )
+            })?;
+            validate_template(&source)
+                .map_err(|error| Error::new(ErrorKind::InvalidInput, error))?;
+            Some(source)
+        }
+        None => None,
+    };
 
-    if !combined_additional.trim().is_empty() {
-        let output_path_string = request.output_path.to_string_lossy();
-        append_additional_commands(&output_path_string, &combined_additional)?;
-    }
+    Ok(RenderOptions {
+        format: request.output_format,
+        path_style: request.path_style,
+        text: request.output_text.clone(),
+        template,
+        tree: None,
+        diff: None,
+        metadata: request.metadata.clone(),
+        line_numbers: request.line_numbers,
+        preset_texts: request.preset_texts.clone(),
+        additional_commands: request.additional_commands.clone(),
+    })
+}
 
-    if request.copy_to_clipboard {
-        let output_path_string = request.output_path.to_string_lossy();
-        copy_to_clipboard(&output_path_string)?;
+/// Builds the scan options for a request; the request's output file is always excluded.
+pub fn scan_options_for(request: &TagGenerationRequest) -> ScanOptions {
+    ScanOptions {
+        excluded_paths: output_paths_to_exclude(&request.output_path),
+        ..selection_options_for(request)
     }
+}
 
-    let _ = request.open_after;
-
-    Ok(GenerationSummary {
-        output_path: request.output_path,
-        files_written: write_summary.files_written,
-        files_skipped: write_summary.files_skipped,
-        skipped_non_utf8_files: write_summary.skipped_non_utf8_files,
+/// Builds the scan options for a request without touching the disk, so `excluded_paths` is left
+/// empty; see [`output_paths_to_exclude`] for the paths [`scan_options_for`] fills in.
+pub fn selection_options_for(request: &TagGenerationRequest) -> ScanOptions {
+    ScanOptions {
+        valid_exts: request.extensions.clone(),
+        strict_extension_case: request.strict_extension_case,
+        filenames: request.filenames.clone(),
+        detect_shebangs: request.detect_shebangs,
+        file_list: request.file_list.clone(),
         recursive: request.recursive,
-    })
+        ignored_folders: request.ignored_folders.clone(),
+        respect_gitignore: request.respect_gitignore,
+        git_selection: request.git_selection.clone(),
+        include_patterns: request.include_patterns.clone(),
+        exclude_patterns: request.exclude_patterns.clone(),
+        excluded_paths: Vec::new(),
+        skip_previous_outputs: request.skip_previous_outputs,
+        order: request.file_order,
+        priority_patterns: request.priority_patterns.clone(),
+        tree: request.tree,
+        diff: request.diff.clone(),
+        path_prefix: None,
+        max_file_size: request.max_file_size,
+        fallback_encodings: request.fallback_encodings.clone(),
+    }
 }
 
-fn combine_additional_commands<'a>(
-    preset_texts: impl IntoIterator<Item = &'a str>,
-    additional_commands: &str,
-) -> String {
-    let mut combined_additional = String::new();
+/// Builds one scan root per folder of the request, `root_dir` first.
+///
+/// Additional roots share the main scan options, with their own file types, ignored folders, and
+/// path prefix. A file list only ever applies to `root_dir`.
+///
+/// # Errors
+/// `ErrorKind::InvalidInput` when an alias is blank or two roots share a path prefix.
+pub fn scan_roots_for(request: &TagGenerationRequest) -> std::io::Result<Vec<ScanRoot>> {
+    let main_options = scan_options_for(request);
+    let mut roots = vec![ScanRoot {
+        dir: request.root_dir.clone(),
+        options: ScanOptions {
+            path_prefix: root_prefix(
+                &request.root_dir,
+                request.root_alias.as_deref(),
+                !request.additional_roots.is_empty(),
+            )?,
+            ..main_options.clone()
+        },
+    }];
+    for root in &request.additional_roots {
+        roots.push(ScanRoot {
+            dir: root.dir.clone(),
+            options: ScanOptions {
+                valid_exts: root.extensions.clone(),
+                filenames: root.filenames.clone(),
+                file_list: None,
+                ignored_folders: root.ignored_folders.clone(),
+                path_prefix: root_prefix(&root.dir, root.alias.as_deref(), true)?,
+                ..main_options.clone()
+            },
+        });
+    }
 
-    for preset_text in preset_texts {
-        combined_additional.push('\n');
-        combined_additional.push_str(preset_text.trim());
-        combined_additional.push('\n');
+    let mut prefixes: HashMap<String, &Path> = HashMap::new();
+    for root in &roots {
+        let Some(prefix) = &root.options.path_prefix else {
+            continue;
+        };
+        if let Some(other) = prefixes.insert(prefix.to_lowercase(), &root.dir) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Roots '{}' and '{}' would both write paths under '{prefix}'; give one of them an alias.",
+                    other.display(),
+                    root.dir.display()
+                ),
+            ));
+        }
     }
+    Ok(roots)
+}
 
-    if !additional_commands.trim().is_empty() {
-        combined_additional.push('\n');
-        combined_additional.push_str(additional_commands.trim());
-        combined_additional.push('\n');
+/// Returns the path prefix for a root: its alias, or its folder name when `multi_root` is set.
+fn root_prefix(
+    dir: &Path,
+    alias: Option<&str>,
+    multi_root: bool,
+) -> std::io::Result<Option<String>> {
+    match alias {
+        Some(alias) => {
+            let alias = alias.trim().trim_matches(['/', '\\']);
+            if alias.is_empty() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("The alias for root '{}' must not be blank", dir.display()),
+                ));
+            }
+            Ok(Some(alias.to_string()))
+        }
+        None => Ok(multi_root
+            .then(|| project_name(dir))
+            .filter(|name| !name.is_empty())),
     }
+}
 
-    combined_additional
+/// Returns the canonical output path plus any numbered parts already next to it.
+pub fn output_paths_to_exclude(output_path: &Path) -> Vec<PathBuf> {
+    let mut excluded = vec![canonical_output_path(output_path)];
+    for index in 1.. {
+        let part = part_path(output_path, index);
+        if !part.is_file() {
+            break;
+        }
+        excluded.push(canonical_output_path(&part));
+    }
+    excluded
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::budget::BudgetRank;
+    use crate::formats::OUTPUT_MARKER;
     use std::fs;
     use tempfile::tempdir;
 
     fn request(root_dir: PathBuf, output_path: PathBuf) -> TagGenerationRequest {
         TagGenerationRequest {
             root_dir,
+            root_alias: None,
+            additional_roots: Vec::new(),
             extensions: vec!["rs".to_string()],
+            strict_extension_case: false,
+            filenames: Vec::new(),
+            detect_shebangs: false,
+            file_list: None,
             recursive: false,
             ignored_folders: Vec::new(),
+            respect_gitignore: false,
+            git_selection: None,
+            include_patterns: Vec::new(),
+            exclude_patterns: Vec::new(),
+            skip_previous_outputs: false,
+            file_order: FileOrder::Path,
+            priority_patterns: Vec::new(),
             output_path,
+            output_format: OutputFormat::Tags,
+            path_style: PathStyle::Native,
+            output_text: OutputText::default(),
+            template_path: None,
+            token_vocab: None,
+            budget: OutputBudget::default(),
+            chunk_limit: ChunkLimit::default(),
+            tree: TreeOptions::default(),
+            diff: None,
+            metadata: Vec::new(),
+            line_numbers: false,
+            max_file_size: None,
+            fallback_encodings: Vec::new(),
             additional_commands: String::new(),
             preset_texts: Vec::new(),
             copy_to_clipboard: false,
@@ -158,6 +480,47 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn additional_roots_are_merged_under_their_aliases() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let backend = temp.path().join("backend");
+        let frontend = temp.path().join("frontend");
+        fs::create_dir_all(&backend)?;
+        fs::create_dir_all(&frontend)?;
+        fs::write(backend.join("main.rs"), "fn main() {}")?;
+        fs::write(frontend.join("app.ts"), "export {}")?;
+        let output_path = temp.path().join("context.txt");
+        let mut request = request(backend.clone(), output_path.clone());
+        request.path_style = PathStyle::ForwardSlash;
+        request.additional_roots = vec![SourceRoot {
+            dir: frontend.clone(),
+            alias: Some("web".to_string()),
+            extensions: vec!["ts".to_string()],
+            filenames: Vec::new(),
+            ignored_folders: Vec::new(),
+        }];
+
+        let summary = generate_tag_output(request.clone())?;
+
+        assert_eq!(summary.files_written, 2);
+        let output = fs::read_to_string(&output_path)?;
+        assert!(output.contains("<backend/main.rs>"));
+        assert!(output.contains("<web/app.ts>\nexport {}\n</web/app.ts>"));
+
+        request.root_alias = Some("web/".to_string());
+        let error = generate_tag_output(request.clone()).expect_err("shared prefix");
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("give one of them an alias"));
+
+        request.root_alias = None;
+        request.additional_roots[0].dir = temp.path().join("missing");
+        let error = generate_tag_output(request).expect_err("missing root");
+        assert!(error
+            .to_string()
+            .contains("must point to an existing directory"));
+        Ok(())
+    }
+
     #[test]
     fn generate_tag_output_honors_recursive_and_ignore_options() -> std::io::Result<()> {
         let temp = tempdir()?;
@@ -188,6 +551,77 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn generate_tag_output_honors_respect_gitignore() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let project = temp.path().join("project");
+        fs::create_dir_all(project.join("generated"))?;
+        fs::write(project.join(".gitignore"), "generated/\n")?;
+        fs::write(project.join("main.rs"), "fn main() {}")?;
+        fs::write(
+            project.join("generated").join("bindings.rs"),
+            "pub fn bindings() {}",
+        )?;
+        let output_path = temp.path().join("context.txt");
+        let mut request = request(project, output_path.clone());
+        request.recursive = true;
+        request.respect_gitignore = true;
+
+        let summary = generate_tag_output(request)?;
+
+        assert_eq!(summary.files_written, 1);
+        let output = fs::read_to_string(output_path)?;
+        assert!(!output.contains("pub fn bindings() {}"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn generate_tag_output_reports_pattern_exclusions() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let project = temp.path().join("project");
+        fs::create_dir_all(project.join("src").join("generated"))?;
+        fs::write(project.join("src").join("main.rs"), "fn main() {}")?;
+        fs::write(
+            project.join("src").join("generated").join("api.rs"),
+            "pub fn api() {}",
+        )?;
+        let output_path = temp.path().join("context.txt");
+        let mut request = request(project, output_path);
+        request.recursive = true;
+        request.exclude_patterns = vec!["src/generated/**".to_string()];
+
+        let summary = generate_tag_output(request)?;
+
+        assert_eq!(summary.files_written, 1);
+        assert_eq!(summary.pattern_exclusions.len(), 1);
+        assert_eq!(summary.pattern_exclusions[0].pattern, "src/generated/**");
+        assert_eq!(summary.pattern_exclusions[0].files_excluded, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn relative_output_path_in_scanned_folder_is_excluded() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let root = temp.path().to_path_buf();
+        fs::write(root.join("notes.txt"), "notes")?;
+        let output_path = root.join("nested").join("..").join("tags_output.txt");
+        fs::create_dir_all(root.join("nested"))?;
+        let mut request = request(root.clone(), output_path);
+        request.extensions = vec!["txt".to_string()];
+
+        generate_tag_output(request.clone())?;
+        let summary = generate_tag_output(request)?;
+
+        assert_eq!(summary.files_written, 1);
+        assert_eq!(summary.output_files_skipped, 1);
+        let output = fs::read_to_string(root.join("tags_output.txt"))?;
+        assert!(!output.contains("<tags_output.txt>"));
+
+        Ok(())
+    }
+
     #[test]
     fn preset_text_appears_before_additional_commands() -> std::io::Result<()> {
         let temp = tempdir()?;
@@ -257,4 +691,337 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn generate_tag_output_writes_json_with_presets_and_commands() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let project = temp.path().join("project");
+        fs::create_dir_all(&project)?;
+        fs::write(project.join("main.rs"), "fn main() {}")?;
+        let output_path = temp.path().join("context.json");
+        let mut request = request(project, output_path.clone());
+        request.output_format = OutputFormat::Json;
+        request.preset_texts = vec!["Preset text".to_string()];
+        request.additional_commands = "Manual text".to_string();
+
+        let summary = generate_tag_output(request)?;
+
+        assert_eq!(summary.files_written, 1);
+        let output = fs::read_to_string(output_path)?;
+        let document: serde_json::Value =
+            serde_json::from_str(&output).expect("output is valid JSON");
+        assert_eq!(document["files"][0]["path"], "main.rs");
+        assert_eq!(document["presets"][0], "Preset text");
+        assert_eq!(document["additional_commands"], "Manual text");
+
+        Ok(())
+    }
+
+    #[test]
+    fn json_and_template_sizes_describe_the_file_on_disk() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let project = temp.path().join("project");
+        fs::create_dir_all(&project)?;
+        fs::write(project.join("main.rs"), "\u{feff}fn main() {}\n")?;
+        let output_path = temp.path().join("context.json");
+        let mut json_request = request(project.clone(), output_path.clone());
+        json_request.output_format = OutputFormat::Json;
+
+        generate_tag_output(json_request)?;
+
+        let document: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&output_path)?).expect("valid JSON");
+        assert_eq!(document["files"][0]["size"], 16);
+        assert_eq!(document["files"][0]["lines"], 1);
+        assert_eq!(document["files"][0]["content"], "fn main() {}\n");
+
+        let template_path = temp.path().join("layout.jinja");
+        fs::write(
+            &template_path,
+            "{% for file in files %}{{ file.size }}{% endfor %}",
+        )?;
+        let output_path = temp.path().join("context.txt");
+        let mut template_request = request(project, output_path.clone());
+        template_request.template_path = Some(template_path);
+
+        generate_tag_output(template_request)?;
+
+        assert_eq!(fs::read_to_string(output_path)?, "16");
+        Ok(())
+    }
+
+    #[test]
+    fn generate_tag_output_renders_template_file() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let project = temp.path().join("project");
+        fs::create_dir_all(&project)?;
+        fs::write(project.join("main.rs"), "fn main() {}")?;
+        let template_path = temp.path().join("layout.jinja");
+        fs::write(
+            &template_path,
+            "{% for file in files %}[{{ file.index }}] {{ file.path }}\n{% endfor %}",
+        )?;
+        let output_path = temp.path().join("context.txt");
+        let mut request = request(project, output_path.clone());
+        request.template_path = Some(template_path);
+
+        let summary = generate_tag_output(request)?;
+
+        assert_eq!(summary.files_written, 1);
+        assert_eq!(fs::read_to_string(output_path)?, "[1] main.rs\n");
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_template_fails_before_output_is_created() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let project = temp.path().join("project");
+        fs::create_dir_all(&project)?;
+        let template_path = temp.path().join("broken.jinja");
+        fs::write(&template_path, "{% for file in files %}")?;
+        let output_path = temp.path().join("context.txt");
+        let mut request = request(project, output_path.clone());
+        request.template_path = Some(template_path);
+
+        let error = generate_tag_output(request).expect_err("template should fail");
+
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("Invalid template"));
+        assert!(!output_path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn generate_tag_output_reports_file_and_total_tokens() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let project = temp.path().join("project");
+        fs::create_dir_all(&project)?;
+        fs::write(project.join("a.rs"), "fn a() {}\n")?;
+        fs::write(project.join("b.rs"), "fn b() {}\n// long_identifier\n")?;
+        let output_path = temp.path().join("context.txt");
+
+        let summary = generate_tag_output(request(project, output_path))?;
+
+        assert_eq!(summary.tokenizer, "heuristic");
+        assert_eq!(
+            summary.file_tokens,
+            vec![
+                FileTokens {
+                    path: "a.rs".to_string(),
+                    tokens: 7,
+                },
+                FileTokens {
+                    path: "b.rs".to_string(),
+                    tokens: 14,
+                },
+            ]
+        );
+        assert!(summary.total_tokens > 21);
+        Ok(())
+    }
+
+    #[test]
+    fn missing_token_vocabulary_fails_before_output_is_created() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let project = temp.path().join("project");
+        fs::create_dir_all(&project)?;
+        let output_path = temp.path().join("context.txt");
+        let mut request = request(project, output_path.clone());
+        request.token_vocab = Some(temp.path().join("missing.tiktoken"));
+
+        let error = generate_tag_output(request).expect_err("vocabulary should be missing");
+
+        assert!(error
+            .to_string()
+            .contains("Could not load token vocabulary"));
+        assert!(!output_path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn budget_drops_files_that_do_not_fit_and_reports_them() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let project = temp.path().join("project");
+        fs::create_dir_all(&project)?;
+        fs::write(project.join("a.rs"), "fn a() {}\n")?;
+        fs::write(project.join("big.rs"), "// filler\n".repeat(500))?;
+        let output_path = temp.path().join("context.txt");
+        let mut request = request(project, output_path.clone());
+        request.budget = OutputBudget {
+            max_tokens: Some(200),
+            rank: BudgetRank::Smallest,
+            ..OutputBudget::default()
+        };
+
+        let summary = generate_tag_output(request)?;
+        let output = fs::read_to_string(&output_path)?;
+
+        assert_eq!(summary.files_written, 1);
+        assert_eq!(summary.budget_dropped.len(), 1);
+        assert_eq!(summary.budget_dropped[0].path, "big.rs");
+        assert!(summary.total_tokens <= 200);
+        assert!(output.contains("<a.rs>"));
+        assert!(!output.contains("filler"));
+        Ok(())
+    }
+
+    #[test]
+    fn tree_marks_included_filtered_and_budget_dropped_files() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let project = temp.path().join("project");
+        fs::create_dir_all(project.join("src").join("deep"))?;
+        fs::write(project.join("src").join("a.rs"), "fn a() {}\n")?;
+        fs::write(project.join("src").join("deep").join("d.rs"), "fn d() {}\n")?;
+        fs::write(project.join("big.rs"), "// filler\n".repeat(500))?;
+        fs::write(project.join("notes.txt"), "notes")?;
+        let output_path = temp.path().join("context.txt");
+        let mut request = request(project, output_path.clone());
+        request.recursive = true;
+        request.tree = TreeOptions {
+            enabled: true,
+            max_depth: Some(2),
+        };
+        request.budget = OutputBudget {
+            max_tokens: Some(300),
+            rank: BudgetRank::Smallest,
+            ..OutputBudget::default()
+        };
+
+        let summary = generate_tag_output(request)?;
+        let output = fs::read_to_string(&output_path)?;
+
+        assert_eq!(summary.budget_dropped.len(), 1);
+        assert!(summary.total_tokens <= 300);
+        let tree_start = output.find("project/\n").expect("tree is written");
+        assert!(tree_start < output.find("<src").expect("files are written"));
+        assert!(output.contains(
+            "project/\n\
+             |-- src/\n\
+             |   |-- deep/ (1 file not shown)\n\
+             |   `-- [+] a.rs\n\
+             |-- [-] big.rs\n\
+             `-- [-] notes.txt\n"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn line_numbers_survive_truncation() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let project = temp.path().join("project");
+        fs::create_dir_all(&project)?;
+        let contents: String = (1..=30).map(|line| format!("line {line}\n")).collect();
+        fs::write(project.join("a.rs"), contents)?;
+        let output_path = temp.path().join("context.txt");
+        let mut request = request(project, output_path.clone());
+        request.line_numbers = true;
+        request.budget = OutputBudget {
+            max_bytes: Some(800),
+            truncate: true,
+            ..OutputBudget::default()
+        };
+
+        let summary = generate_tag_output(request)?;
+        let output = fs::read_to_string(&output_path)?;
+
+        assert_eq!(summary.truncated_files.len(), 1);
+        assert!(output.contains("<a.rs>\n 1| line 1\n 2| line 2\n"));
+        assert!(output.contains("[truncated "));
+        assert!(output.contains("line number"));
+        Ok(())
+    }
+
+    #[test]
+    fn chunk_limit_writes_numbered_parts_with_commands_in_the_last() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let project = temp.path().join("project");
+        fs::create_dir_all(&project)?;
+        for name in ["a.rs", "b.rs", "c.rs"] {
+            fs::write(project.join(name), "// some code\n".repeat(20))?;
+        }
+        let output_path = temp.path().join("context.txt");
+        fs::write(
+            temp.path().join("context.part9.txt"),
+            format!("{OUTPUT_MARKER}\nstale"),
+        )?;
+        let mut request = request(project, output_path.clone());
+        request.additional_commands = "Add tests".to_string();
+        request.chunk_limit = ChunkLimit {
+            max_tokens: None,
+            max_bytes: Some(800),
+        };
+
+        let summary = generate_tag_output(request)?;
+
+        assert_eq!(summary.files_written, 3);
+        assert_eq!(
+            summary.output_paths,
+            vec![
+                temp.path().join("context.part1.txt"),
+                temp.path().join("context.part2.txt"),
+                temp.path().join("context.part3.txt"),
+            ]
+        );
+        assert_eq!(summary.output_path, summary.output_paths[0]);
+        assert!(!output_path.exists());
+        assert!(temp.path().join("context.part9.txt").exists());
+
+        let first = fs::read_to_string(&summary.output_paths[0])?;
+        let last = fs::read_to_string(&summary.output_paths[2])?;
+        assert!(first.contains("Part 1 of 3"));
+        assert!(!first.contains("Add tests"));
+        assert!(last.contains("Part 3 of 3"));
+        assert!(last.contains("Add tests"));
+        for path in &summary.output_paths {
+            assert!(fs::metadata(path)?.len() <= 800);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn stale_parts_from_a_larger_split_are_removed() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let project = temp.path().join("project");
+        fs::create_dir_all(&project)?;
+        fs::write(project.join("a.rs"), "// a\n".repeat(40))?;
+        fs::write(project.join("b.rs"), "// b\n".repeat(40))?;
+        let output_path = temp.path().join("context.txt");
+        let stale = temp.path().join("context.part3.txt");
+        fs::write(&stale, format!("{OUTPUT_MARKER}\nstale"))?;
+        let mut request = request(project, output_path);
+        request.chunk_limit = ChunkLimit {
+            max_tokens: None,
+            max_bytes: Some(700),
+        };
+
+        let summary = generate_tag_output(request)?;
+
+        assert_eq!(summary.output_paths.len(), 2);
+        assert!(!stale.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn single_file_output_removes_parts_of_an_earlier_split() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let project = temp.path().join("project");
+        fs::create_dir_all(&project)?;
+        fs::write(project.join("a.rs"), "fn a() {}")?;
+        let output_path = temp.path().join("context.txt");
+        let stale: Vec<PathBuf> = (1..=2)
+            .map(|index| temp.path().join(format!("context.part{index}.txt")))
+            .collect();
+        for path in &stale {
+            fs::write(path, format!("{OUTPUT_MARKER}\nstale"))?;
+        }
+        let unrelated = temp.path().join("context.part3.txt");
+        fs::write(&unrelated, "notes")?;
+
+        let summary = generate_tag_output(request(project, output_path.clone()))?;
+
+        assert_eq!(summary.output_paths, vec![output_path]);
+        assert!(stale.iter().all(|path| !path.exists()));
+        assert!(unrelated.exists());
+        Ok(())
+    }
 }
@@ -0,0 +1,466 @@
+//! # Git Module
+//!
+//! Reads repository information by running the `git` command-line tool in the scanned folder.
+//!
+//! # Selections
+//! [`GitSelection`] limits a scan to tracked, staged, untracked, or changed files. Selections read
+//! only the local repository and never contact a remote.
+//!
+//! # Diffs
+//! [`unified_diff`] renders what changed between a [`DiffBase`] and the working tree, limited to
+//! the paths a caller accepts.
+//!
+//! # Notes
+//! - Git metadata is optional: when `git` is not installed, or the folder is not inside a
+//!   repository, [`last_commits`] returns empty results instead of errors. A requested
+//!   [`GitSelection`] or diff cannot be produced without git, so [`selected_files`] and
+//!   [`unified_diff`] report errors.
+//! - Paths are exchanged relative to the scanned folder, which may be a sub-folder of the
+//!   repository.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Prefix that marks commit lines in the `git log` output parsed by [`last_commits`].
+const COMMIT_PREFIX: char = '\u{0}';
+
+/// Git selection modes that need no argument, as accepted by `--git-files`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum GitFileMode {
+    /// Files in the index.
+    Tracked,
+    /// Files with staged changes.
+    Staged,
+    /// Untracked files that no ignore rule excludes.
+    Untracked,
+}
+
+/// Which files of a git repository a scan is limited to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GitSelection {
+    /// Files in the index (`git ls-files`).
+    Tracked,
+    /// Files whose working-tree contents differ from `base`, whether committed or not.
+    ChangedSince { base: String },
+    /// Files whose staged contents differ from `HEAD`.
+    Staged,
+    /// Untracked files that no `.gitignore`, `.git/info/exclude`, or global exclude rule matches.
+    Untracked,
+}
+
+impl GitSelection {
+    /// Short user-facing description, as used in skip reasons.
+    pub fn label(&self) -> String {
+        match self {
+            GitSelection::Tracked => "tracked".to_string(),
+            GitSelection::ChangedSince { base } => format!("changed since {base}"),
+            GitSelection::Staged => "staged".to_string(),
+            GitSelection::Untracked => "untracked".to_string(),
+        }
+    }
+}
+
+impl From<GitFileMode> for GitSelection {
+    fn from(mode: GitFileMode) -> Self {
+        match mode {
+            GitFileMode::Tracked => GitSelection::Tracked,
+            GitFileMode::Staged => GitSelection::Staged,
+            GitFileMode::Untracked => GitSelection::Untracked,
+        }
+    }
+}
+
+/// What the working tree is compared against when rendering a diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffBase {
+    /// The last commit.
+    Head,
+    /// Any commit-ish, such as a branch, tag, or hash.
+    Ref(String),
+}
+
+impl DiffBase {
+    /// The revision passed to `git diff`.
+    pub fn revision(&self) -> &str {
+        match self {
+            DiffBase::Head => "HEAD",
+            DiffBase::Ref(base) => base,
+        }
+    }
+}
+
+/// Files chosen by a [`GitSelection`], relative to the scanned folder.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitFiles {
+    paths: HashSet<String>,
+}
+
+impl GitFiles {
+    /// Returns `true` if `relative_path` (relative to the scanned folder) was selected.
+    pub fn contains(&self, relative_path: &Path) -> bool {
+        self.paths.contains(&slash_path(relative_path))
+    }
+}
+
+/// Lists the files under `root_dir` chosen by `selection`.
+///
+/// Deleted files are never listed. Paths outside `root_dir` (when it is a sub-folder of the
+/// repository) are left out by git itself.
+///
+/// # Errors
+/// Returns a message when `git` cannot be run, `root_dir` is not inside a repository, or the base
+/// ref of [`GitSelection::ChangedSince`] is unknown.
+pub fn selected_files(root_dir: &Path, selection: &GitSelection) -> Result<GitFiles, String> {
+    let mut args = Vec::new();
+    match selection {
+        GitSelection::Tracked => args.extend(["ls-files", "-z", "--cached"]),
+        GitSelection::Untracked => {
+            args.extend(["ls-files", "-z", "--others", "--exclude-standard"])
+        }
+        GitSelection::Staged | GitSelection::ChangedSince { .. } => {
+            args.extend(["diff", "--name-only", "-z", "--no-renames"]);
+            args.extend(["--diff-filter=d", "--relative"]);
+        }
+    }
+    match selection {
+        GitSelection::Staged => args.push("--cached"),
+        GitSelection::ChangedSince { base } => {
+            validate_ref(base)?;
+            args.extend([base.as_str(), "--"]);
+        }
+        GitSelection::Tracked | GitSelection::Untracked => {}
+    }
+
+    let description = format!("list {} files", selection.label());
+    let paths = null_separated(&run_git(root_dir, &args, &description)?)
+        .into_iter()
+        .collect();
+    Ok(GitFiles { paths })
+}
+
+/// Returns the unified diff between `base` and the working tree for the changed files under
+/// `root_dir` that `include` accepts, or an empty string when none of them changed.
+///
+/// `include` receives each changed path relative to `root_dir`, including deleted files. Renames
+/// are shown as a deletion and an addition, and untracked files are not part of the diff. A
+/// `path_prefix` is written before every path in the diff headers, so `a/src/lib.rs` becomes
+/// `a/backend/src/lib.rs`, matching the paths of a multi-root output.
+///
+/// # Errors
+/// Returns a message when `git` cannot be run, `root_dir` is not inside a repository, or `base`
+/// is unknown.
+pub fn unified_diff(
+    root_dir: &Path,
+    base: &DiffBase,
+    path_prefix: Option<&str>,
+    include: impl Fn(&Path) -> bool,
+) -> Result<String, String> {
+    let revision = base.revision();
+    validate_ref(revision)?;
+    let description = format!("diff against {revision}");
+
+    let mut args = vec!["diff", "--name-only", "-z", "--no-renames", "--relative"];
+    args.extend([revision, "--"]);
+    let paths: Vec<String> = null_separated(&run_git(root_dir, &args, &description)?)
+        .into_iter()
+        .filter(|path| include(&path.split('/').collect::<PathBuf>()))
+        .collect();
+    if paths.is_empty() {
+        return Ok(String::new());
+    }
+
+    let prefixes = path_prefix.map(|prefix| {
+        let prefix = prefix.trim_matches('/');
+        [
+            format!("--src-prefix=a/{prefix}/"),
+            format!("--dst-prefix=b/{prefix}/"),
+        ]
+    });
+    let mut args = vec!["--literal-pathspecs", "diff", "--no-color", "--no-ext-diff"];
+    args.extend(prefixes.iter().flatten().map(String::as_str));
+    args.extend(["--no-renames", "--relative", revision, "--"]);
+    args.extend(paths.iter().map(String::as_str));
+    let diff = run_git(root_dir, &args, &description)?;
+    Ok(String::from_utf8_lossy(&diff).into_owned())
+}
+
+/// Rejects refs that git would read as an option.
+fn validate_ref(base: &str) -> Result<(), String> {
+    if base.is_empty() || base.starts_with('-') {
+        return Err(format!("Invalid git ref '{base}'."));
+    }
+    Ok(())
+}
+
+/// Runs `git` with `args` in `root_dir` and returns its standard output.
+///
+/// `description` completes "Could not …" in error messages.
+fn run_git(root_dir: &Path, args: &[&str], description: &str) -> Result<Vec<u8>, String> {
+    let output = Command::new("git")
+        .args(["-c", "core.quotePath=false"])
+        .args(args)
+        .current_dir(root_dir)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|error| format!("Could not run git to {description}: {error}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Could not {description} in '{}': {}",
+            root_dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// Splits `-z` output into its non-empty entries.
+fn null_separated(output: &[u8]) -> Vec<String> {
+    output
+        .split(|&byte| byte == 0)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| String::from_utf8_lossy(entry).into_owned())
+        .collect()
+}
+
+/// Returns the hash of the most recent commit that touched each of `relative_paths`.
+///
+/// Reads `git log` once, newest first, and stops as soon as every path has been seen. Paths that
+/// were never committed (or any path when git is unavailable) are missing from the result.
+pub fn last_commits(root_dir: &Path, relative_paths: &[PathBuf]) -> HashMap<PathBuf, String> {
+    let mut wanted: HashMap<String, &PathBuf> = relative_paths
+        .iter()
+        .map(|path| (slash_path(path), path))
+        .collect();
+    let mut commits = HashMap::new();
+    if wanted.is_empty() {
+        return commits;
+    }
+
+    let Ok(mut child) = Command::new("git")
+        .args(["-c", "core.quotePath=false", "log", "--format=%x00%H"])
+        .args(["--name-only", "--no-renames", "--relative", "--", "."])
+        .current_dir(root_dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return commits;
+    };
+
+    if let Some(stdout) = child.stdout.take() {
+        let mut commit = String::new();
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if let Some(hash) = line.strip_prefix(COMMIT_PREFIX) {
+                commit = hash.to_string();
+            } else if let Some(path) = wanted.remove(line.as_str()) {
+                commits.insert(path.clone(), commit.clone());
+                if wanted.is_empty() {
+                    break;
+                }
+            }
+        }
+    }
+
+    // Stop git early once every path is found; it may still be walking older history.
+    let _ = child.kill();
+    let _ = child.wait();
+    commits
+}
+
+/// Joins path components with `/`, the separator git prints on every platform.
+fn slash_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn git(dir: &Path, args: &[&str]) -> Option<String> {
+        let output = Command::new("git")
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    #[test]
+    fn last_commits_finds_newest_commit_per_path() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let repo = temp.path();
+        if git(repo, &["init", "-q"]).is_none() {
+            return Ok(());
+        }
+        fs::create_dir_all(repo.join("src"))?;
+        fs::write(repo.join("src").join("a.rs"), "a")?;
+        fs::write(repo.join("b.rs"), "b")?;
+        git(repo, &["add", "."]).expect("git add");
+        git(repo, &["commit", "-q", "-m", "first"]).expect("first commit");
+        let first = git(repo, &["rev-parse", "HEAD"]).expect("first hash");
+        fs::write(repo.join("b.rs"), "b2")?;
+        git(repo, &["commit", "-q", "-am", "second"]).expect("second commit");
+        let second = git(repo, &["rev-parse", "HEAD"]).expect("second hash");
+        fs::write(repo.join("new.rs"), "untracked")?;
+
+        let a = Path::new("src").join("a.rs");
+        let commits = last_commits(
+            repo,
+            &[a.clone(), PathBuf::from("b.rs"), PathBuf::from("new.rs")],
+        );
+
+        assert_eq!(commits.get(&a), Some(&first));
+        assert_eq!(commits.get(Path::new("b.rs")), Some(&second));
+        assert_eq!(commits.get(Path::new("new.rs")), None);
+        Ok(())
+    }
+
+    #[test]
+    fn selections_list_tracked_staged_changed_and_untracked_files() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let repo = temp.path();
+        if git(repo, &["init", "-q", "-b", "main"]).is_none() {
+            return Ok(());
+        }
+        fs::create_dir_all(repo.join("src"))?;
+        fs::write(repo.join(".gitignore"), "*.log\n")?;
+        fs::write(repo.join("src").join("kept.rs"), "kept")?;
+        fs::write(repo.join("src").join("edited.rs"), "v1")?;
+        fs::write(repo.join("removed.rs"), "gone soon")?;
+        git(repo, &["add", "."]).expect("git add");
+        git(repo, &["commit", "-q", "-m", "base"]).expect("base commit");
+        git(repo, &["checkout", "-q", "-b", "feature"]).expect("feature branch");
+        fs::write(repo.join("src").join("edited.rs"), "v2")?;
+        fs::write(repo.join("committed.rs"), "new")?;
+        git(repo, &["add", "."]).expect("git add");
+        git(repo, &["commit", "-q", "-m", "feature"]).expect("feature commit");
+        fs::write(repo.join("staged.rs"), "staged")?;
+        git(repo, &["add", "staged.rs"]).expect("git add staged");
+        git(repo, &["rm", "-q", "removed.rs"]).expect("git rm");
+        fs::write(repo.join("untracked.rs"), "untracked")?;
+        fs::write(repo.join("debug.log"), "ignored")?;
+
+        let listed = |selection: GitSelection| -> Vec<String> {
+            let files = selected_files(repo, &selection).expect("selection should list files");
+            let mut paths: Vec<String> = files.paths.into_iter().collect();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(
+            listed(GitSelection::Tracked),
+            vec![
+                ".gitignore",
+                "committed.rs",
+                "src/edited.rs",
+                "src/kept.rs",
+                "staged.rs"
+            ]
+        );
+        assert_eq!(listed(GitSelection::Staged), vec!["staged.rs"]);
+        assert_eq!(
+            listed(GitSelection::ChangedSince {
+                base: "main".to_string()
+            }),
+            vec!["committed.rs", "src/edited.rs", "staged.rs"]
+        );
+        assert_eq!(listed(GitSelection::Untracked), vec!["untracked.rs"]);
+
+        let src_files = selected_files(&repo.join("src"), &GitSelection::Tracked)
+            .expect("sub-folder selection");
+        assert!(src_files.contains(Path::new("edited.rs")));
+        assert!(!src_files.contains(Path::new("staged.rs")));
+
+        let unknown = GitSelection::ChangedSince {
+            base: "no-such-branch".to_string(),
+        };
+        assert!(selected_files(repo, &unknown).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn unified_diff_covers_accepted_paths_against_head_or_a_ref() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let repo = temp.path();
+        if git(repo, &["init", "-q", "-b", "main"]).is_none() {
+            return Ok(());
+        }
+        fs::write(repo.join("a.rs"), "one\n")?;
+        fs::write(repo.join("b.toml"), "x = 1\n")?;
+        fs::write(repo.join("c.rs"), "gone\n")?;
+        git(repo, &["add", "."]).expect("git add");
+        git(repo, &["commit", "-q", "-m", "base"]).expect("base commit");
+        fs::write(repo.join("a.rs"), "two\n")?;
+        git(repo, &["commit", "-q", "-am", "second"]).expect("second commit");
+        fs::write(repo.join("a.rs"), "three\n")?;
+        fs::write(repo.join("b.toml"), "x = 2\n")?;
+        fs::remove_file(repo.join("c.rs"))?;
+        let rust_only = |path: &Path| path.extension().is_some_and(|ext| ext == "rs");
+
+        let diff = unified_diff(repo, &DiffBase::Head, None, rust_only).expect("diff against HEAD");
+        assert!(diff.contains("diff --git a/a.rs b/a.rs"));
+        assert!(diff.contains("-two\n+three\n"));
+        assert!(diff.contains("deleted file mode"));
+        assert!(!diff.contains("b.toml"));
+
+        let diff = unified_diff(repo, &DiffBase::Ref("main~1".to_string()), None, rust_only)
+            .expect("diff against a ref");
+        assert!(diff.contains("-one\n+three\n"));
+
+        let diff =
+            unified_diff(repo, &DiffBase::Head, Some("backend"), rust_only).expect("prefixed diff");
+        assert!(diff.contains("diff --git a/backend/a.rs b/backend/a.rs"));
+
+        assert_eq!(
+            unified_diff(repo, &DiffBase::Head, None, |_| false),
+            Ok(String::new())
+        );
+        assert!(unified_diff(
+            repo,
+            &DiffBase::Ref("--output=x".to_string()),
+            None,
+            rust_only
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn selections_outside_a_repository_are_errors() -> std::io::Result<()> {
+        let temp = tempdir()?;
+
+        let error = selected_files(temp.path(), &GitSelection::Tracked)
+            .expect_err("a plain folder has no tracked files");
+
+        assert!(error.contains("Could not"));
+        Ok(())
+    }
+
+    #[test]
+    fn folders_outside_a_repository_have_no_commits() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        fs::write(temp.path().join("a.rs"), "a")?;
+
+        let commits = last_commits(temp.path(), &[PathBuf::from("a.rs")]);
+
+        assert!(commits.is_empty());
+        Ok(())
+    }
+}
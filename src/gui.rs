@@ -18,6 +18,9 @@
 //! # Features
 //! - Directory and file mode selection.
 //! - Recursive search toggle with folder ignore input.
+//! - Optional `.gitignore` / `.ignore` handling toggle.
+//! - Include/exclude glob pattern inputs.
+//! - Running estimate of the tokens the current selection would produce.
 //! - Additional commands input (multiline).
 //! - Preset command dropdown with editing capabilities.
 //! - Warning and success messages inline in the UI.
@@ -26,14 +29,178 @@
 //! - Uses `eframe::run_native` to block execution until the user completes the selection.
 //! - Updates shared mutable state passed by reference from `main.rs`.
 
-use crate::filetypes::{save_filetypes, FileTypeGroup};
+use crate::budget::OutputBudget;
+use crate::chunks::ChunkLimit;
+use crate::content::DEFAULT_MAX_FILE_SIZE;
+use crate::file_ops::ScanOptions;
+use crate::filetypes::{normalize_extensions, save_filetypes, FileTypeGroup};
+use crate::formats::{OutputFormat, PathStyle};
+use crate::generation::{output_paths_to_exclude, selection_options_for, TagGenerationRequest};
+use crate::ordering::FileOrder;
+use crate::output_text::OutputText;
 use crate::presets::save_presets;
 use crate::presets::{get_presets, PresetCommand};
+use crate::tokens::{estimate_tokens, HeuristicTokenizer, TokenEstimate};
+use crate::tree::TreeOptions;
 
 use eframe::egui;
 use rfd::FileDialog;
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::{Duration, Instant};
+
+/// How long the selections must stay unchanged before the token estimate is recomputed.
+const ESTIMATE_DEBOUNCE: Duration = Duration::from_millis(400);
+
+/// Selections gathered by the GUI and handed back to `main.rs` once the window closes.
+///
+/// # Fields
+/// - `file_type_groups`: All available file type groups (possibly edited in the File Type Manager).
+/// - `selected_type_index`: Index of the selected file type group.
+/// - `enable_clipboard_copy`: Whether the clipboard should be updated after output is generated.
+/// - `output_path`: Path where the generated output file should be written.
+/// - `output_format`: Layout of the generated output (tags, Markdown, JSON, JSON Lines, or XML).
+/// - `path_style`: Separator used in file paths written to the output.
+/// - `additional_commands`: Multiline string entered by the user to append to the output.
+/// - `selected_dir`: The selected folder path for file processing.
+/// - `preset_texts`: Texts of the presets selected when the user pressed OK.
+/// - `enable_recursive_search`: Whether to search directories recursively.
+/// - `detect_shebangs`: Whether extensionless scripts are matched by their shebang interpreter.
+/// - `ignored_folders`: Textbox input for folder names to skip (one per line).
+/// - `respect_gitignore`: Whether `.gitignore`, `.ignore`, and git exclude files are honored.
+/// - `skip_previous_outputs`: Whether files that look like earlier wrapper outputs are skipped.
+/// - `file_order`: Order in which files are written to the output.
+/// - `include_patterns`: Textbox input for include globs (one per line).
+/// - `exclude_patterns`: Textbox input for exclude globs (one per line).
+#[derive(Debug, Clone)]
+pub struct GuiSelections {
+    pub file_type_groups: Vec<FileTypeGroup>,
+    pub selected_type_index: Option<usize>,
+    pub enable_clipboard_copy: bool,
+    pub output_path: String,
+    pub output_format: OutputFormat,
+    pub path_style: PathStyle,
+    pub additional_commands: String,
+    pub selected_dir: Option<PathBuf>,
+    pub preset_texts: Vec<String>,
+    pub enable_recursive_search: bool,
+    pub detect_shebangs: bool,
+    pub ignored_folders: String,
+    pub respect_gitignore: bool,
+    pub skip_previous_outputs: bool,
+    pub file_order: FileOrder,
+    pub include_patterns: String,
+    pub exclude_patterns: String,
+}
+
+impl GuiSelections {
+    /// Creates empty selections with the default `tags_output.txt` output path.
+    pub fn new(file_type_groups: Vec<FileTypeGroup>) -> Self {
+        Self {
+            file_type_groups,
+            selected_type_index: None,
+            enable_clipboard_copy: false,
+            output_path: "tags_output.txt".to_string(),
+            output_format: OutputFormat::Tags,
+            path_style: PathStyle::Native,
+            additional_commands: String::new(),
+            selected_dir: None,
+            preset_texts: Vec::new(),
+            enable_recursive_search: false,
+            detect_shebangs: false,
+            ignored_folders: String::new(),
+            respect_gitignore: false,
+            skip_previous_outputs: false,
+            file_order: FileOrder::Path,
+            include_patterns: String::new(),
+            exclude_patterns: String::new(),
+        }
+    }
+
+    /// Builds the generation request the current selections describe, or `None` until both a
+    /// folder and a file type are chosen.
+    ///
+    /// Building touches no files, so the token estimate can compare requests every frame.
+    pub fn request(&self, output_text: OutputText) -> Option<TagGenerationRequest> {
+        let root_dir = self.selected_dir.clone()?;
+        let group = self
+            .selected_type_index
+            .and_then(|i| self.file_type_groups.get(i))?;
+
+        Some(TagGenerationRequest {
+            root_dir,
+            root_alias: None,
+            additional_roots: Vec::new(),
+            extensions: group.extensions.clone(),
+            strict_extension_case: false,
+            filenames: group.filenames.clone(),
+            detect_shebangs: self.detect_shebangs,
+            file_list: None,
+            recursive: self.enable_recursive_search,
+            ignored_folders: self
+                .ignored_folders
+                .lines()
+                .map(|s| s.trim().to_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            respect_gitignore: self.respect_gitignore,
+            git_selection: None,
+            include_patterns: non_empty_lines(&self.include_patterns),
+            exclude_patterns: non_empty_lines(&self.exclude_patterns),
+            skip_previous_outputs: self.skip_previous_outputs,
+            file_order: self.file_order,
+            priority_patterns: Vec::new(),
+            output_path: PathBuf::from(self.output_path.trim()),
+            output_format: self.output_format,
+            path_style: self.path_style,
+            output_text,
+            template_path: None,
+            token_vocab: None,
+            budget: OutputBudget::default(),
+            chunk_limit: ChunkLimit::default(),
+            tree: TreeOptions::default(),
+            diff: None,
+            metadata: Vec::new(),
+            line_numbers: false,
+            max_file_size: Some(DEFAULT_MAX_FILE_SIZE),
+            fallback_encodings: Vec::new(),
+            additional_commands: self.additional_commands.clone(),
+            preset_texts: self.preset_texts.clone(),
+            copy_to_clipboard: self.enable_clipboard_copy,
+            open_after: !self.enable_clipboard_copy,
+        })
+    }
+}
+
+/// Splits multiline GUI input into trimmed, non-empty lines.
+pub fn non_empty_lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Token estimate for the selection it belongs to, so it is only recomputed on change.
+struct CachedTokenEstimate {
+    dir: PathBuf,
+    /// Scan options without the output exclusions, which are resolved when the scan starts.
+    options: ScanOptions,
+    output_path: PathBuf,
+    /// When the selection last changed; the scan starts after [`ESTIMATE_DEBOUNCE`].
+    changed_at: Instant,
+    state: EstimateState,
+}
+
+/// Progress of a token estimate, which is scanned on a background thread.
+enum EstimateState {
+    /// Waiting for the selection to stop changing.
+    Pending,
+    /// Scanning; the result arrives on the receiver.
+    Calculating(Receiver<Option<TokenEstimate>>),
+    /// Finished; `None` when the patterns are invalid or the folder could not be read.
+    Done(Option<TokenEstimate>),
+}
 
 /// Holds the interactive state and logic for the main GUI window.
 ///
@@ -43,87 +210,119 @@ use std::path::{Path, PathBuf};
 /// - Manages the lifecycle and interactions of the preset manager popup.
 ///
 /// # Fields
-/// - `modes`: All available file type modes (e.g., Rust, JSON).
-/// - `selected_mode`: Currently selected file type mode (shared mutable).
-/// - `enable_clipboard_copy`: Whether the clipboard should be updated after output is generated.
-/// - `output_path`: Path where the generated output file should be written.
-/// - `additional_commands`: Multiline string entered by the user to append to the output.
-/// - `selected_dir`: The selected folder path for file processing.
-/// - `preset_texts`: Output accumulator for selected presets (shared mutable).
+/// - `selections`: [`GuiSelections`] shared with `main.rs` (shared mutable).
 /// - `selected_presets`: Set of selected preset names (limited to 1 in the current UI).
 /// - `warning_message`: Message shown in red if validation fails (e.g., no directory selected).
 /// - `presets`: Full list of loaded/editable `PresetCommand` objects.
-/// - `enable_recursive_search`: Whether to search directories recursively (shared mutable).
-/// - `ignored_folders`: Textbox input for folder names to skip (shared mutable).
 /// - `open_manage_presets`: Whether the preset manager window is currently open.
 /// - `open_preset_index`: Index of the currently expanded preset panel (if any).
 /// - `success_message`: Temporary success toast used when saving presets.
+/// - `token_estimate`: Heuristic token estimate for the current selection, scanned in the background.
 ///
 /// # Behavior
 /// - Passed to `eframe::run_native` and rendered by the `update` method every frame.
 /// - Handles preset selection, folder picking, validation, and final confirmation.
-/// - Owns no internal lifetimes—uses shared mutable references for outward-facing state.
+/// - Owns no internal lifetimes—uses a shared mutable reference for outward-facing state.
 ///
 /// # Notes
 /// - GUI layout and interactivity are driven entirely from the `update()` implementation.
 /// - Not intended to be reused or retained beyond a single GUI session.
 pub struct ModeSelector<'a> {
-    file_type_groups: &'a mut Vec<FileTypeGroup>,
-    selected_type_index: &'a mut Option<usize>,
-    enable_clipboard_copy: &'a mut bool,
-    output_path: &'a mut String,
-    additional_commands: &'a mut String,
-    selected_dir: &'a mut Option<PathBuf>,
-    preset_texts: &'a mut Vec<String>,
+    selections: &'a mut GuiSelections,
     selected_presets: HashSet<String>,
     warning_message: String,
     presets: Vec<PresetCommand>,
-    enable_recursive_search: &'a mut bool,
-    ignored_folders: &'a mut String,
     open_manage_presets: bool,
     open_preset_index: Option<usize>,
     open_manage_types: bool,
     success_message: Option<(String, std::time::Instant)>,
     joined_extensions: Vec<String>,
+    joined_filenames: Vec<String>,
+    token_estimate: Option<CachedTokenEstimate>,
 }
 
 impl<'a> ModeSelector<'a> {
     /// Creates a new GUI instance.
-    pub fn new(
-        file_type_groups: &'a mut Vec<FileTypeGroup>,
-        selected_type_index: &'a mut Option<usize>,
-        enable_clipboard_copy: &'a mut bool,
-        output_path: &'a mut String,
-        additional_commands: &'a mut String,
-        selected_dir: &'a mut Option<PathBuf>,
-        preset_texts: &'a mut Vec<String>,
-        enable_recursive_search: &'a mut bool,
-        ignored_folders: &'a mut String,
-        open_manage_presets: bool,
-    ) -> Self {
-        let joined_extensions = file_type_groups
+    pub fn new(selections: &'a mut GuiSelections, open_manage_presets: bool) -> Self {
+        let joined_extensions = selections
+            .file_type_groups
             .iter()
             .map(|g| g.extensions.join("\n"))
             .collect();
+        let joined_filenames = selections
+            .file_type_groups
+            .iter()
+            .map(|g| g.filenames.join("\n"))
+            .collect();
         Self {
-            file_type_groups,
-            selected_type_index,
-            enable_clipboard_copy,
-            output_path,
-            additional_commands,
-            selected_dir,
-            preset_texts,
+            selections,
             selected_presets: HashSet::new(),
             warning_message: String::new(),
             presets: get_presets(),
-            enable_recursive_search,
-            ignored_folders,
             open_manage_presets,
             open_preset_index: None,
             open_manage_types: false,
             success_message: None,
             joined_extensions,
+            joined_filenames,
+            token_estimate: None,
+        }
+    }
+
+    /// Returns the progress of the token estimate for the current selections.
+    ///
+    /// Scanning reads every selected file, so it runs on a background thread, and only once the
+    /// selections have stopped changing for [`ESTIMATE_DEBOUNCE`]; results for selections that
+    /// changed in the meantime are discarded. Returns `None` until a folder and file type are
+    /// chosen.
+    fn current_token_estimate(&mut self, ctx: &egui::Context) -> Option<&EstimateState> {
+        let Some(request) = self.selections.request(OutputText::default()) else {
+            self.token_estimate = None;
+            return None;
+        };
+        let options = selection_options_for(&request);
+
+        let is_current = self.token_estimate.as_ref().is_some_and(|cached| {
+            cached.dir == request.root_dir
+                && cached.options == options
+                && cached.output_path == request.output_path
+        });
+        if !is_current {
+            self.token_estimate = Some(CachedTokenEstimate {
+                dir: request.root_dir,
+                options,
+                output_path: request.output_path,
+                changed_at: Instant::now(),
+                state: EstimateState::Pending,
+            });
         }
+
+        let cached = self.token_estimate.as_mut()?;
+        match &cached.state {
+            EstimateState::Pending if cached.changed_at.elapsed() >= ESTIMATE_DEBOUNCE => {
+                let (sender, receiver) = mpsc::channel();
+                let (dir, mut options) = (cached.dir.clone(), cached.options.clone());
+                let output_path = cached.output_path.clone();
+                std::thread::spawn(move || {
+                    if !output_path.as_os_str().is_empty() {
+                        options.excluded_paths = output_paths_to_exclude(&output_path);
+                    }
+                    let estimate = estimate_tokens(&dir, &options, &HeuristicTokenizer).ok();
+                    let _ = sender.send(estimate);
+                });
+                cached.state = EstimateState::Calculating(receiver);
+            }
+            EstimateState::Calculating(receiver) => match receiver.try_recv() {
+                Ok(estimate) => cached.state = EstimateState::Done(estimate),
+                Err(TryRecvError::Disconnected) => cached.state = EstimateState::Done(None),
+                Err(TryRecvError::Empty) => {}
+            },
+            EstimateState::Pending | EstimateState::Done(_) => {}
+        }
+        if !matches!(cached.state, EstimateState::Done(_)) {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+        Some(&cached.state)
     }
 }
 
@@ -174,8 +373,12 @@ impl eframe::App for ModeSelector<'_> {
     ///    - Checkboxes for:
     ///      - Enabling clipboard copying.
     ///      - Enabling recursive directory search.
+    ///      - Respecting `.gitignore` and `.ignore` files.
+    ///      - Skipping previously generated output files.
+    ///    - Dropdown for the file order (alphabetical, directories first, size, modified).
     ///    - If recursion is enabled:
     ///      - Multiline text box to enter ignored folders (one per line, case-insensitive).
+    ///    - Side-by-side include/exclude glob pattern inputs.
     /// 4. **Additional Commands Input**
     ///    - Resizable multiline text area for arbitrary user instructions.
     /// 5. **Preset Commands**
@@ -191,15 +394,22 @@ impl eframe::App for ModeSelector<'_> {
     ///    - “OK” button validates inputs and, if valid, closes the window with selections saved into shared state.
     ///
     /// # State Updates
-    /// - Updates the following shared mutable state passed via the constructor:
+    /// - Updates the following fields of the shared [`GuiSelections`] passed via the constructor:
     ///   - `selected_dir`
-    ///   - `selected_mode`
+    ///   - `selected_type_index`
     ///   - `preset_texts`
     ///   - `output_path`
+    ///   - `output_format`
+    ///   - `path_style`
     ///   - `additional_commands`
     ///   - `enable_clipboard_copy`
     ///   - `enable_recursive_search`
     ///   - `ignored_folders`
+    ///   - `respect_gitignore`
+    ///   - `skip_previous_outputs`
+    ///   - `file_order`
+    ///   - `include_patterns`
+    ///   - `exclude_patterns`
     ///
     /// # Panics
     /// - This function does not panic.
@@ -225,11 +435,11 @@ impl eframe::App for ModeSelector<'_> {
             ui.horizontal(|ui| {
                 if ui.button("Select Directory").clicked() {
                     if let Some(dir) = FileDialog::new().set_directory(".").pick_folder() {
-                        *self.selected_dir = Some(dir);
+                        self.selections.selected_dir = Some(dir);
                         self.warning_message.clear();
                     }
                 }
-                if let Some(dir) = &self.selected_dir {
+                if let Some(dir) = &self.selections.selected_dir {
                     ui.add(
                         egui::TextEdit::singleline(&mut dir.display().to_string())
                             .desired_width(300.0)
@@ -244,20 +454,24 @@ impl eframe::App for ModeSelector<'_> {
                 ui.label("File Type:");
 
                 let selected_label = self
+                    .selections
                     .selected_type_index
-                    .and_then(|i| self.file_type_groups.get(i))
+                    .and_then(|i| self.selections.file_type_groups.get(i))
                     .map(|g| g.name.as_str())
                     .unwrap_or("None");
 
-                egui::ComboBox::from_id_source("filetype_group")
+                egui::ComboBox::from_id_salt("filetype_group")
                     .selected_text(selected_label)
                     .show_ui(ui, |ui| {
-                        for (i, group) in self.file_type_groups.iter().enumerate() {
+                        for (i, group) in self.selections.file_type_groups.iter().enumerate() {
                             if ui
-                                .selectable_label(*self.selected_type_index == Some(i), &group.name)
+                                .selectable_label(
+                                    self.selections.selected_type_index == Some(i),
+                                    &group.name,
+                                )
                                 .clicked()
                             {
-                                *self.selected_type_index = Some(i);
+                                self.selections.selected_type_index = Some(i);
                                 self.warning_message.clear();
                             }
                         }
@@ -269,23 +483,77 @@ impl eframe::App for ModeSelector<'_> {
             });
 
             ui.checkbox(
-                self.enable_clipboard_copy,
+                &mut self.selections.enable_clipboard_copy,
                 "Enable save to clipboard automatically",
             );
             ui.checkbox(
-                self.enable_recursive_search,
+                &mut self.selections.enable_recursive_search,
                 "Enable recursive directory search",
             );
+            ui.checkbox(
+                &mut self.selections.detect_shebangs,
+                "Match extensionless scripts by shebang (e.g. python as py)",
+            );
+            ui.checkbox(
+                &mut self.selections.respect_gitignore,
+                "Respect .gitignore and .ignore files",
+            );
+            ui.checkbox(
+                &mut self.selections.skip_previous_outputs,
+                "Skip previously generated output files",
+            );
+
+            ui.horizontal(|ui| {
+                ui.label("File Order:");
+                egui::ComboBox::from_id_salt("file_order")
+                    .selected_text(self.selections.file_order.label())
+                    .show_ui(ui, |ui| {
+                        for order in FileOrder::ALL {
+                            ui.selectable_value(
+                                &mut self.selections.file_order,
+                                order,
+                                order.label(),
+                            );
+                        }
+                    });
+            });
 
             ui.horizontal(|ui| {
                 ui.label("Output File:");
                 ui.add(
-                    egui::TextEdit::singleline(self.output_path)
+                    egui::TextEdit::singleline(&mut self.selections.output_path)
                         .desired_width(ui.available_width()),
                 );
             });
 
-            if *self.enable_recursive_search {
+            ui.horizontal(|ui| {
+                ui.label("Output Format:");
+                egui::ComboBox::from_id_salt("output_format")
+                    .selected_text(self.selections.output_format.label())
+                    .show_ui(ui, |ui| {
+                        for format in OutputFormat::ALL {
+                            ui.selectable_value(
+                                &mut self.selections.output_format,
+                                format,
+                                format.label(),
+                            );
+                        }
+                    });
+                ui.label("Path Separators:");
+                egui::ComboBox::from_id_salt("path_style")
+                    .selected_text(self.selections.path_style.label())
+                    .show_ui(ui, |ui| {
+                        for style in PathStyle::ALL {
+                            ui.selectable_value(
+                                &mut self.selections.path_style,
+                                style,
+                                style.label(),
+                            );
+                        }
+                    });
+            });
+
+            if self.selections.enable_recursive_search {
                 ui.group(|ui| {
                     ui.label("Ignore Folders (one per line, case insensitive):");
                     egui::ScrollArea::vertical()
@@ -293,7 +561,7 @@ impl eframe::App for ModeSelector<'_> {
                         .max_height(100.0)
                         .show(ui, |ui| {
                             ui.add(
-                                egui::TextEdit::multiline(self.ignored_folders)
+                                egui::TextEdit::multiline(&mut self.selections.ignored_folders)
                                     .desired_width(ui.available_width())
                                     .desired_rows(4),
                             );
@@ -301,6 +569,26 @@ impl eframe::App for ModeSelector<'_> {
                 });
             }
 
+            ui.group(|ui| {
+                ui.label("Path Patterns (one glob per line, relative to the directory):");
+                ui.columns(2, |columns| {
+                    columns[0].label("Include:");
+                    columns[0].add(
+                        egui::TextEdit::multiline(&mut self.selections.include_patterns)
+                            .desired_width(f32::INFINITY)
+                            .desired_rows(3)
+                            .hint_text("src/**"),
+                    );
+                    columns[1].label("Exclude:");
+                    columns[1].add(
+                        egui::TextEdit::multiline(&mut self.selections.exclude_patterns)
+                            .desired_width(f32::INFINITY)
+                            .desired_rows(3)
+                            .hint_text("src/generated/**"),
+                    );
+                });
+            });
+
             // Additional commands
             ui.group(|ui| {
                 ui.label("Additional Commands:");
@@ -309,7 +597,7 @@ impl eframe::App for ModeSelector<'_> {
                     .id_salt("Additional_commands_scroll")
                     .show(ui, |ui| {
                         ui.add(
-                            egui::TextEdit::multiline(self.additional_commands)
+                            egui::TextEdit::multiline(&mut self.selections.additional_commands)
                                 .desired_width(ui.available_width())
                                 .desired_rows(5),
                         );
@@ -362,6 +650,19 @@ impl eframe::App for ModeSelector<'_> {
                 }
             }
 
+            match self.current_token_estimate(ctx) {
+                Some(EstimateState::Pending | EstimateState::Calculating(_)) => {
+                    ui.label("Estimated tokens: calculating…");
+                }
+                Some(EstimateState::Done(Some(estimate))) => {
+                    ui.label(format!(
+                        "Estimated tokens: {} ({} files)",
+                        estimate.tokens, estimate.files
+                    ));
+                }
+                Some(EstimateState::Done(None)) | None => {}
+            }
+
             // Warning Message
             if !self.warning_message.is_empty() {
                 ui.colored_label(egui::Color32::RED, &self.warning_message);
@@ -369,21 +670,22 @@ impl eframe::App for ModeSelector<'_> {
 
             // OK Button
             if ui.button("OK").clicked() {
-                if self.selected_dir.is_none() {
+                if self.selections.selected_dir.is_none() {
                     self.warning_message = "⚠️ Please select a directory before proceeding!".into();
-                } else if self.selected_type_index.is_none() {
+                } else if self.selections.selected_type_index.is_none() {
                     self.warning_message = "⚠️ Please select a file type before proceeding!".into();
-                } else if let Err(message) = validate_output_path_input(self.output_path) {
+                } else if let Err(message) =
+                    validate_output_path_input(&self.selections.output_path)
+                {
                     self.warning_message = format!("⚠️ {message}");
                 } else {
-                    *self.output_path = self.output_path.trim().to_string();
+                    self.selections.output_path = self.selections.output_path.trim().to_string();
                     // Push selected preset text
                     for preset in &self.presets {
                         if self.selected_presets.contains(&preset.name) {
-                            self.preset_texts.push(preset.text.clone());
+                            self.selections.preset_texts.push(preset.text.clone());
                         }
                     }
-                    *self.file_type_groups = self.file_type_groups.clone();
                     ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                 }
             }
@@ -413,15 +715,22 @@ impl eframe::App for ModeSelector<'_> {
                         let mut to_delete: Option<usize> = None;
 
                         // Sync buffer if group count changed
-                        if self.joined_extensions.len() != self.file_type_groups.len() {
+                        if self.joined_extensions.len() != self.selections.file_type_groups.len() {
                             self.joined_extensions = self
+                                .selections
                                 .file_type_groups
                                 .iter()
                                 .map(|g| g.extensions.join("\n"))
                                 .collect();
+                            self.joined_filenames = self
+                                .selections
+                                .file_type_groups
+                                .iter()
+                                .map(|g| g.filenames.join("\n"))
+                                .collect();
                         }
 
-                        for (i, group) in self.file_type_groups.iter_mut().enumerate() {
+                        for (i, group) in self.selections.file_type_groups.iter_mut().enumerate() {
                             let is_open = self.open_preset_index == Some(i);
                             let response = egui::CollapsingHeader::new(&group.name)
                                 .id_salt(format!("filetype_{}", i))
@@ -439,7 +748,7 @@ impl eframe::App for ModeSelector<'_> {
 
                                     egui::ScrollArea::vertical()
                                         .max_height(100.0)
-                                        .id_source(format!("ext_scroll_{}", i))
+                                        .id_salt(format!("ext_scroll_{}", i))
                                         .show(ui, |ui| {
                                             let buf = &mut self.joined_extensions[i];
                                             let changed = ui
@@ -451,11 +760,28 @@ impl eframe::App for ModeSelector<'_> {
                                                 .changed();
 
                                             if changed {
-                                                group.extensions = buf
-                                                    .lines()
-                                                    .map(|s| s.trim().to_string())
-                                                    .filter(|s| !s.is_empty())
-                                                    .collect();
+                                                group.extensions =
+                                                    normalize_extensions(buf.lines(), true);
+                                            }
+                                        });
+
+                                    ui.label("File names or globs (one per line):");
+
+                                    egui::ScrollArea::vertical()
+                                        .max_height(100.0)
+                                        .id_salt(format!("filename_scroll_{}", i))
+                                        .show(ui, |ui| {
+                                            let buf = &mut self.joined_filenames[i];
+                                            let changed = ui
+                                                .add(
+                                                    egui::TextEdit::multiline(buf)
+                                                        .desired_width(ui.available_width())
+                                                        .hint_text("Makefile\nDockerfile.*"),
+                                                )
+                                                .changed();
+
+                                            if changed {
+                                                group.filenames = non_empty_lines(buf);
                                             }
                                         });
 
@@ -468,23 +794,26 @@ impl eframe::App for ModeSelector<'_> {
                         }
 
                         if let Some(i) = to_delete {
-                            self.file_type_groups.remove(i);
+                            self.selections.file_type_groups.remove(i);
                             self.joined_extensions.remove(i);
+                            self.joined_filenames.remove(i);
                             if self.open_preset_index == Some(i) {
                                 self.open_preset_index = None;
                             }
                         }
 
                         if ui.button("Add New Group").clicked() {
-                            self.file_type_groups.push(FileTypeGroup {
+                            self.selections.file_type_groups.push(FileTypeGroup {
                                 name: "New Group".into(),
                                 extensions: vec![],
+                                filenames: vec![],
                             });
                             self.joined_extensions.push(String::new());
+                            self.joined_filenames.push(String::new());
                         }
 
                         if ui.button("Save Changes").clicked() {
-                            save_filetypes(&self.file_type_groups);
+                            save_filetypes(&self.selections.file_type_groups);
                             self.success_message = Some((
                                 "✅ File types saved successfully.".into(),
                                 std::time::Instant::now(),
@@ -595,7 +924,10 @@ impl eframe::App for ModeSelector<'_> {
 
 #[cfg(test)]
 mod tests {
-    use super::validate_output_path_input;
+    use super::{validate_output_path_input, GuiSelections};
+    use crate::filetypes::FileTypeGroup;
+    use crate::output_text::OutputText;
+    use std::path::PathBuf;
     use tempfile::tempdir;
 
     #[test]
@@ -640,4 +972,28 @@ mod tests {
         assert!(error.contains("existing directory"));
         Ok(())
     }
+
+    #[test]
+    fn selections_build_a_request_once_folder_and_file_type_are_chosen() {
+        let mut selections = GuiSelections::new(vec![FileTypeGroup {
+            name: "Rust".to_string(),
+            extensions: vec!["rs".to_string()],
+            filenames: Vec::new(),
+        }]);
+        selections.selected_dir = Some(PathBuf::from("project"));
+        assert!(selections.request(OutputText::default()).is_none());
+
+        selections.selected_type_index = Some(0);
+        selections.ignored_folders = " Target \n\nnode_modules\n".to_string();
+        selections.output_path = " context.txt ".to_string();
+        let request = selections
+            .request(OutputText::default())
+            .expect("folder and file type are chosen");
+
+        assert_eq!(request.root_dir, PathBuf::from("project"));
+        assert_eq!(request.extensions, vec!["rs".to_string()]);
+        assert_eq!(request.ignored_folders, vec!["target", "node_modules"]);
+        assert_eq!(request.output_path, PathBuf::from("context.txt"));
+        assert!(request.open_after);
+    }
 }
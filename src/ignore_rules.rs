@@ -0,0 +1,194 @@
+//! # Ignore Rules Module
+//!
+//! Evaluates gitignore-style exclusion files while `file_ops` walks a project directory.
+//!
+//! # Sources
+//! Rules are consulted from highest to lowest precedence:
+//! - `.ignore` files, then `.gitignore` files, starting in the deepest directory and moving upward.
+//! - `.git/info/exclude` of the enclosing repository.
+//! - The global git excludes file (`core.excludesFile`, or the XDG default).
+//!
+//! # Notes
+//! - Patterns use full gitignore semantics (negation, anchored patterns, directory-only patterns)
+//!   through the matcher provided by the `ignore` crate.
+//! - Ignore files in parent folders up to the repository root also apply to the scanned folder.
+//! - `.gitignore` files are honored even when the scanned folder is not inside a git repository.
+//! - Unreadable or malformed ignore files are treated as empty rather than aborting the scan.
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::path::{Path, PathBuf};
+
+const IGNORE_FILE_NAMES: [&str; 2] = [".gitignore", ".ignore"];
+
+/// Stack of ignore matchers that mirrors the directories currently being traversed.
+///
+/// # Behavior
+/// - [`IgnoreRules::load`] prepares matchers for the scan root and its repository ancestors.
+/// - [`IgnoreRules::enter_dir`] and [`IgnoreRules::leave_dir`] must be called in pairs while recursing.
+/// - [`IgnoreRules::is_ignored`] accepts paths in the same form as the root passed to `load`.
+#[derive(Debug, Clone)]
+pub struct IgnoreRules {
+    root: PathBuf,
+    absolute_root: PathBuf,
+    levels: Vec<Gitignore>,
+    fallbacks: Vec<Gitignore>,
+}
+
+impl IgnoreRules {
+    /// Loads the ignore files that apply to `root`, including repository-wide and global excludes.
+    pub fn load(root: &Path) -> Self {
+        let absolute_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let repository_root = absolute_root
+            .ancestors()
+            .find(|ancestor| ancestor.join(".git").exists())
+            .map(Path::to_path_buf);
+
+        let mut levels = Vec::new();
+        if let Some(repository_root) = &repository_root {
+            let mut parents: Vec<&Path> = absolute_root
+                .ancestors()
+                .skip(1)
+                .take_while(|ancestor| ancestor.starts_with(repository_root))
+                .collect();
+            parents.reverse();
+            levels.extend(parents.into_iter().map(directory_rules));
+        }
+        levels.push(directory_rules(&absolute_root));
+
+        let mut fallbacks = Vec::new();
+        if let Some(repository_root) = &repository_root {
+            let exclude_path = repository_root.join(".git").join("info").join("exclude");
+            if exclude_path.is_file() {
+                let mut builder = GitignoreBuilder::new(repository_root);
+                let _ = builder.add(exclude_path);
+                fallbacks.push(builder.build().unwrap_or_else(|_| Gitignore::empty()));
+            }
+        }
+        let global_root = repository_root.as_deref().unwrap_or(&absolute_root);
+        fallbacks.push(GitignoreBuilder::new(global_root).build_global().0);
+
+        Self {
+            root: root.to_path_buf(),
+            absolute_root,
+            levels,
+            fallbacks,
+        }
+    }
+
+    /// Adds the `.gitignore` and `.ignore` rules of a directory that traversal is descending into.
+    pub fn enter_dir(&mut self, dir: &Path) {
+        let absolute_dir = self.to_absolute(dir);
+        self.levels.push(directory_rules(&absolute_dir));
+    }
+
+    /// Removes the rules added by the matching [`IgnoreRules::enter_dir`] call.
+    pub fn leave_dir(&mut self) {
+        self.levels.pop();
+    }
+
+    /// Returns `true` if the closest matching rule excludes `path`.
+    ///
+    /// A negated (`!pattern`) match stops the search and keeps the path, matching git's precedence.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let absolute_path = self.to_absolute(path);
+
+        for matcher in self.levels.iter().rev().chain(self.fallbacks.iter()) {
+            match matcher.matched(&absolute_path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+
+        false
+    }
+
+    fn to_absolute(&self, path: &Path) -> PathBuf {
+        match path.strip_prefix(&self.root) {
+            Ok(relative_path) => self.absolute_root.join(relative_path),
+            Err(_) => path.to_path_buf(),
+        }
+    }
+}
+
+/// Builds one matcher from a directory's `.gitignore` and `.ignore` files.
+///
+/// `.ignore` is added last so its patterns override `.gitignore` patterns in the same folder.
+fn directory_rules(dir: &Path) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(dir);
+    for file_name in IGNORE_FILE_NAMES {
+        let path = dir.join(file_name);
+        if path.is_file() {
+            let _ = builder.add(path);
+        }
+    }
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn negated_pattern_re_includes_file() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        fs::write(temp.path().join(".gitignore"), "*.rs\n!keep.rs\n")?;
+
+        let rules = IgnoreRules::load(temp.path());
+
+        assert!(rules.is_ignored(&temp.path().join("drop.rs"), false));
+        assert!(!rules.is_ignored(&temp.path().join("keep.rs"), false));
+        Ok(())
+    }
+
+    #[test]
+    fn directory_only_pattern_does_not_match_files() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        fs::write(temp.path().join(".gitignore"), "build/\n")?;
+
+        let rules = IgnoreRules::load(temp.path());
+
+        assert!(rules.is_ignored(&temp.path().join("build"), true));
+        assert!(!rules.is_ignored(&temp.path().join("build"), false));
+        Ok(())
+    }
+
+    #[test]
+    fn nested_rules_apply_only_inside_their_directory() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let nested = temp.path().join("nested");
+        fs::create_dir_all(&nested)?;
+        fs::write(nested.join(".ignore"), "local.rs\n")?;
+
+        let mut rules = IgnoreRules::load(temp.path());
+        assert!(!rules.is_ignored(&temp.path().join("local.rs"), false));
+
+        rules.enter_dir(&nested);
+        assert!(rules.is_ignored(&nested.join("local.rs"), false));
+        rules.leave_dir();
+
+        assert!(!rules.is_ignored(&temp.path().join("local.rs"), false));
+        Ok(())
+    }
+
+    #[test]
+    fn repository_exclude_and_parent_gitignore_apply_to_subfolder() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let info = temp.path().join(".git").join("info");
+        fs::create_dir_all(&info)?;
+        fs::write(info.join("exclude"), "secret.rs\n")?;
+        fs::write(temp.path().join(".gitignore"), "/app/generated.rs\n")?;
+        let app = temp.path().join("app");
+        fs::create_dir_all(&app)?;
+
+        let rules = IgnoreRules::load(&app);
+
+        assert!(rules.is_ignored(&app.join("secret.rs"), false));
+        assert!(rules.is_ignored(&app.join("generated.rs"), false));
+        assert!(!rules.is_ignored(&app.join("main.rs"), false));
+        Ok(())
+    }
+}
@@ -16,30 +16,50 @@
 //! - `main.rs` orchestrates entry points, then delegates filesystem scanning/writing through `generation`.
 //! - The selected output path may be the default `tags_output.txt` or any caller-provided file path.
 
+mod budget;
+mod chunks;
 mod cli;
+mod content;
+mod file_list;
 mod file_ops;
 mod filetypes;
+mod formats;
 mod generation;
+mod git;
 mod gui;
+mod ignore_rules;
+mod metadata;
+mod ordering;
+mod output_text;
+mod patterns;
 mod presets;
+mod preview;
 mod profiles;
+mod roots;
+mod templates;
+mod tokens;
+mod tree;
 mod utils;
 
-use crate::cli::{build_run_request, Cli, Command};
+use crate::cli::{build_run_request, validate_run_flags, Cli, Command};
 use crate::filetypes::{get_filetypes, FileTypeGroup};
-use crate::generation::{generate_tag_output, GenerationSummary, TagGenerationRequest};
-use crate::gui::ModeSelector;
+use crate::generation::{generate_tag_output, preview_tag_output, GenerationSummary};
+use crate::gui::{GuiSelections, ModeSelector};
+use crate::output_text::get_output_text;
 use crate::presets::get_presets;
 use crate::profiles::{
     delete_profile, find_profile, load_profiles, profile_from_run_args, profile_to_run_request,
-    save_profile,
+    save_profile, validate_profile,
 };
-use crate::utils::get_cursor_position;
+use crate::utils::{copy_to_clipboard, get_cursor_position};
 
 use clap::Parser;
 use eframe::egui;
 use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
-use std::path::PathBuf;
+use std::io::IsTerminal;
+
+/// Number of files listed in the CLI token report.
+const TOKEN_REPORT_LIMIT: usize = 10;
 
 #[cfg(windows)]
 const OPEN_COMMAND: &str = "notepad";
@@ -77,7 +97,7 @@ fn main() {
                 }
             };
 
-            if let Err(error) = profile_to_run_request(&profile, &file_type_groups, &presets) {
+            if let Err(error) = validate_profile(&profile, &file_type_groups, &presets) {
                 eprintln!("❌ ERROR: {error}");
                 std::process::exit(1);
             }
@@ -91,22 +111,56 @@ fn main() {
             std::process::exit(0);
         }
         Some(Command::RunProfile { name }) => run_profile_command(&name),
+        Some(Command::List(args)) => {
+            let json = args.json;
+            preview_built_request(build_cli_request(args), json);
+        }
         Some(Command::Run(args)) => {
-            let file_type_groups = get_filetypes();
-            let presets = get_presets();
-            let built = match build_run_request(args, &file_type_groups, &presets) {
-                Ok(built) => built,
-                Err(error) => {
-                    eprintln!("❌ ERROR: {error}");
-                    std::process::exit(1);
-                }
-            };
-
+            if let Err(error) = validate_run_flags(&args) {
+                eprintln!("❌ ERROR: {error}");
+                std::process::exit(1);
+            }
+            let (dry_run, json) = (args.dry_run, args.json);
+            let built = build_cli_request(args);
+            if dry_run {
+                preview_built_request(built, json);
+            }
             run_built_request(built);
         }
     }
 }
 
+fn build_cli_request(args: crate::cli::RunArgs) -> crate::cli::BuiltRunRequest {
+    let file_type_groups = get_filetypes();
+    let presets = get_presets();
+    match build_run_request(args, &file_type_groups, &presets) {
+        Ok(built) => built,
+        Err(error) => {
+            eprintln!("❌ ERROR: {error}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Prints which files a request would include or skip, then exits without writing output.
+fn preview_built_request(mut built: crate::cli::BuiltRunRequest, json: bool) -> ! {
+    built.request.output_text = get_output_text().with_overrides(&built.request.output_text);
+    let preview = match preview_tag_output(&built.request) {
+        Ok(preview) => preview,
+        Err(e) => {
+            eprintln!("❌ ERROR: Could not preview tag output: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if json {
+        println!("{}", preview.to_json());
+    } else {
+        print!("{}", preview.format_table());
+    }
+    std::process::exit(0);
+}
+
 fn run_profile_command(name: &str) -> ! {
     let profiles = load_profiles();
     let Some(profile) = find_profile(&profiles, name) else {
@@ -127,9 +181,13 @@ fn run_profile_command(name: &str) -> ! {
     run_built_request(built);
 }
 
-fn run_built_request(built: crate::cli::BuiltRunRequest) -> ! {
+fn run_built_request(mut built: crate::cli::BuiltRunRequest) -> ! {
+    // CLI and profile text settings are overrides layered on top of output_text.json.
+    built.request.output_text = get_output_text().with_overrides(&built.request.output_text);
     let open_after = built.request.open_after;
+    let copy_parts = built.request.copy_to_clipboard;
     let extensions_used = built.extensions_used.clone();
+    let filenames_used = built.filenames_used.clone();
     let summary = match generate_tag_output(built.request) {
         Ok(summary) => summary,
         Err(e) => {
@@ -142,7 +200,10 @@ fn run_built_request(built: crate::cli::BuiltRunRequest) -> ! {
         open_output_file(&summary);
     }
 
-    print_cli_summary(&summary, &extensions_used);
+    print_cli_summary(&summary, &extensions_used, &filenames_used);
+    if copy_parts {
+        copy_remaining_parts_cli(&summary);
+    }
     std::process::exit(0);
 }
 
@@ -150,49 +211,22 @@ fn run_gui_flow() {
     let initial_file_type_groups = get_filetypes();
     let cursor_position = get_cursor_position();
 
-    let (
-        file_type_groups,
-        selected_dir,
-        selected_type_index,
-        enable_clipboard_copy,
-        output_path,
-        additional_commands,
-        preset_texts,
-        enable_recursive_search,
-        ignored_folders,
-    ) = mode_selection_gui(initial_file_type_groups.clone(), cursor_position);
-
-    let Some(dir) = selected_dir else {
+    let selections = mode_selection_gui(initial_file_type_groups.clone(), cursor_position);
+
+    let Some(dir) = &selections.selected_dir else {
         eprintln!("⚠️ No directory selected. Exiting.");
         std::process::exit(0);
     };
 
     println!("📂 User selected directory: {:?}", dir);
 
-    let Some(group) = selected_type_index.and_then(|i| file_type_groups.get(i)) else {
+    let Some(request) = selections.request(get_output_text()) else {
         eprintln!("⚠️ No file type group selected. Exiting.");
         std::process::exit(0);
     };
+    let open_after = request.open_after;
 
-    let ignored_folders: Vec<String> = ignored_folders
-        .lines()
-        .map(|s| s.trim().to_lowercase())
-        .filter(|s| !s.is_empty())
-        .collect();
-
-    let open_after = !enable_clipboard_copy;
-    let request = TagGenerationRequest {
-        root_dir: dir,
-        extensions: group.extensions.clone(),
-        recursive: enable_recursive_search,
-        ignored_folders,
-        output_path: PathBuf::from(output_path),
-        additional_commands,
-        preset_texts,
-        copy_to_clipboard: enable_clipboard_copy,
-        open_after,
-    };
-
+    let copy_parts = request.copy_to_clipboard;
     let summary = match generate_tag_output(request) {
         Ok(summary) => summary,
         Err(e) => {
@@ -201,6 +235,10 @@ fn run_gui_flow() {
         }
     };
 
+    if copy_parts {
+        copy_remaining_parts_gui(&summary);
+    }
+
     if open_after {
         let result = MessageDialog::new()
             .set_title("Open Output File?")
@@ -241,18 +279,168 @@ fn list_profiles() {
 
 fn list_file_types() {
     for group in get_filetypes() {
-        println!("{}: {}", group.name, group.extensions.join(", "));
+        if group.filenames.is_empty() {
+            println!("{}: {}", group.name, group.extensions.join(", "));
+        } else {
+            println!(
+                "{}: {} (files: {})",
+                group.name,
+                group.extensions.join(", "),
+                group.filenames.join(", ")
+            );
+        }
     }
 }
 
-fn print_cli_summary(summary: &GenerationSummary, extensions_used: &[String]) {
+/// Copies parts 2..n of split output one at a time, waiting for Enter before each part.
+///
+/// Stops quietly when stdin is not a terminal, so scripted runs never block.
+fn copy_remaining_parts_cli(summary: &GenerationSummary) {
+    let count = summary.output_paths.len();
+    if count < 2 {
+        return;
+    }
+
+    println!("📋 Part 1 of {count} copied to the clipboard.");
+    if !std::io::stdin().is_terminal() {
+        return;
+    }
+
+    for (index, path) in summary.output_paths.iter().enumerate().skip(1) {
+        println!(
+            "Press Enter to copy part {} of {count} (Ctrl+C to stop)...",
+            index + 1
+        );
+        let mut line = String::new();
+        if std::io::stdin().read_line(&mut line).unwrap_or(0) == 0 {
+            return;
+        }
+        if let Err(error) = copy_to_clipboard(&path.to_string_lossy()) {
+            eprintln!("❌ ERROR: Could not copy part {}: {error}", index + 1);
+            return;
+        }
+        println!("📋 Part {} of {count} copied to the clipboard.", index + 1);
+    }
+}
+
+/// Copies parts 2..n of split output one at a time, asking with a dialog before each part.
+fn copy_remaining_parts_gui(summary: &GenerationSummary) {
+    let count = summary.output_paths.len();
+    for (index, path) in summary.output_paths.iter().enumerate().skip(1) {
+        let result = MessageDialog::new()
+            .set_title("Copy Next Part?")
+            .set_description(format!(
+                "Part {index} of {count} is on the clipboard.\n\nCopy part {} of {count} now?",
+                index + 1
+            ))
+            .set_buttons(MessageButtons::OkCancel)
+            .set_level(MessageLevel::Info)
+            .show();
+        if result != MessageDialogResult::Ok {
+            return;
+        }
+        if let Err(error) = copy_to_clipboard(&path.to_string_lossy()) {
+            eprintln!("❌ ERROR: Could not copy part {}: {error}", index + 1);
+            return;
+        }
+    }
+}
+
+fn print_cli_summary(
+    summary: &GenerationSummary,
+    extensions_used: &[String],
+    filenames_used: &[String],
+) {
     println!("✅ Generation complete.");
-    println!("Output path: {}", summary.output_path.display());
+    if summary.output_paths.len() > 1 {
+        println!("Output parts: {}", summary.output_paths.len());
+        for path in &summary.output_paths {
+            println!("  {}", path.display());
+        }
+    } else {
+        println!("Output path: {}", summary.output_path.display());
+    }
     println!("Files included: {}", summary.files_written);
     println!("Files skipped: {}", summary.files_skipped);
     println!("Non-UTF8 files skipped: {}", summary.skipped_non_utf8_files);
+    if !summary.rejected_files.is_empty() {
+        println!(
+            "Binary or oversized files skipped: {}",
+            summary.rejected_files.len()
+        );
+        for file in &summary.rejected_files {
+            println!("  {} ({})", file.path, file.reason.label());
+        }
+    }
+    if !summary.transcoded_files.is_empty() {
+        println!(
+            "Files transcoded to UTF-8: {}",
+            summary.transcoded_files.len()
+        );
+        for file in &summary.transcoded_files {
+            println!("  {} (from {})", file.path, file.encoding.label());
+        }
+    }
+    if summary.files_not_included > 0 {
+        println!(
+            "Files matching no --include pattern: {}",
+            summary.files_not_included
+        );
+    }
+    if summary.output_files_skipped > 0 {
+        println!(
+            "Output files skipped (current or previous): {}",
+            summary.output_files_skipped
+        );
+    }
+    for exclusion in &summary.pattern_exclusions {
+        println!(
+            "Excluded by '{}': {}",
+            exclusion.pattern, exclusion.files_excluded
+        );
+    }
     println!("Recursive: {}", summary.recursive);
-    println!("Extensions used: {}", extensions_used.join(", "));
+    if !extensions_used.is_empty() {
+        println!("Extensions used: {}", extensions_used.join(", "));
+    }
+    if !filenames_used.is_empty() {
+        println!("File names used: {}", filenames_used.join(", "));
+    }
+    println!(
+        "Estimated tokens ({}): {} total, {} in file contents",
+        summary.tokenizer,
+        summary.total_tokens,
+        summary
+            .file_tokens
+            .iter()
+            .map(|file| file.tokens)
+            .sum::<usize>()
+    );
+
+    let mut largest_files: Vec<_> = summary.file_tokens.iter().collect();
+    largest_files.sort_by(|a, b| b.tokens.cmp(&a.tokens).then_with(|| a.path.cmp(&b.path)));
+    if !largest_files.is_empty() {
+        println!("Largest files by tokens:");
+        for file in largest_files.iter().take(TOKEN_REPORT_LIMIT) {
+            println!("  {:>8}  {}", file.tokens, file.path);
+        }
+    }
+
+    if !summary.truncated_files.is_empty() {
+        println!("Truncated to fit budget: {}", summary.truncated_files.len());
+        for file in &summary.truncated_files {
+            println!("  {} ({} lines removed)", file.path, file.lines_removed);
+        }
+    }
+    if !summary.budget_dropped.is_empty() {
+        println!("Dropped to fit budget: {}", summary.budget_dropped.len());
+        for file in &summary.budget_dropped {
+            println!(
+                "  {} ({} tokens, {} bytes)",
+                file.path, file.tokens, file.bytes
+            );
+        }
+    }
 }
 
 fn open_output_file(summary: &GenerationSummary) {
@@ -280,16 +468,14 @@ fn open_output_file(summary: &GenerationSummary) {
 /// - `initial_pos`: Optional screen coordinates `(x, y)` to position the GUI window near the cursor.
 ///
 /// # Returns
-/// A tuple containing:
-/// - `Vec<FileTypeGroup>`: Possibly updated list of file type groups (if the user modified them).
-/// - `Option<PathBuf>`: The selected directory path.
-/// - `Option<usize>`: Index of the selected file type group, or `None` if not selected.
-/// - `bool`: Whether to copy output to the clipboard after generation.
-/// - `String`: Output file path.
-/// - `String`: Custom text commands to be appended to the output file.
-/// - `Vec<String>`: Collected preset command texts selected by the user.
-/// - `bool`: Whether recursive directory search is enabled.
-/// - `String`: Newline-separated list of folder names to ignore (e.g., `"target\n.git"`).
+/// A [`GuiSelections`] value containing:
+/// - The possibly updated list of file type groups (if the user modified them).
+/// - The selected directory path and file type group index (`None` if not selected).
+/// - Whether to copy output to the clipboard after generation.
+/// - The output file path, additional commands, and selected preset texts.
+/// - Whether recursive directory search is enabled and the newline-separated ignored folders.
+/// - Whether `.gitignore` and `.ignore` files should be honored.
+/// - The selected output format and path separator style.
 ///
 /// # Behavior
 /// - Spawns an `eframe` GUI using [`ModeSelector`], blocking until user presses OK or closes the window.
@@ -312,8 +498,7 @@ fn open_output_file(summary: &GenerationSummary) {
 /// ```rust
 /// let groups = get_filetypes();
 /// let cursor = get_cursor_position();
-/// let (groups, dir, mode, clipboard, output, commands, presets, recursive, ignored) =
-///     mode_selection_gui(groups, cursor);
+/// let selections = mode_selection_gui(groups, cursor);
 /// ```
 ///
 /// # Related
@@ -322,42 +507,13 @@ fn open_output_file(summary: &GenerationSummary) {
 fn mode_selection_gui(
     file_type_groups: Vec<FileTypeGroup>,
     initial_pos: Option<(f32, f32)>,
-) -> (
-    Vec<FileTypeGroup>,
-    Option<PathBuf>, // selected_dir
-    Option<usize>,   // selected file type group index
-    bool,            // clipboard
-    String,          // output path
-    String,          // additional commands
-    Vec<String>,     // preset texts
-    bool,            // recursive
-    String,          // ignored folders
-) {
-    let mut local_file_type_groups = file_type_groups;
-    let mut selected_mode: Option<usize> = None;
-    let mut enable_clipboard_copy = false;
-    let mut output_path = "tags_output.txt".to_string();
-    let mut additional_commands = String::new();
-    let mut selected_dir: Option<PathBuf> = None;
-    let mut preset_texts = Vec::new();
-    let mut enable_recursive_search = false;
-    let mut ignored_folders = String::new();
+) -> GuiSelections {
+    let mut selections = GuiSelections::new(file_type_groups);
 
     // Retrieve cursor position if available
     let (x, y) = initial_pos.unwrap_or((100.0, 100.0)); // Default if position is unavailable
 
-    let app = ModeSelector::new(
-        &mut local_file_type_groups,
-        &mut selected_mode,
-        &mut enable_clipboard_copy,
-        &mut output_path,
-        &mut additional_commands,
-        &mut selected_dir,
-        &mut preset_texts,
-        &mut enable_recursive_search,
-        &mut ignored_folders,
-        false,
-    );
+    let app = ModeSelector::new(&mut selections, false);
 
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
@@ -373,15 +529,5 @@ fn mode_selection_gui(
         Box::new(|_cc| Ok(Box::new(app))),
     );
 
-    (
-        local_file_type_groups,
-        selected_dir,
-        selected_mode,
-        enable_clipboard_copy,
-        output_path,
-        additional_commands,
-        preset_texts,
-        enable_recursive_search,
-        ignored_folders,
-    )
+    selections
 }
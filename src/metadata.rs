@@ -0,0 +1,232 @@
+//! # File Metadata Module
+//!
+//! Collects optional per-file attributes (size, line count, language, modification time, content
+//! hash, and last git commit) so downstream tooling can tell when a wrapped snippet is stale.
+//!
+//! # Rendering
+//! - Tags and XML: attributes on the opening tag / `<file>` element (e.g., `sha256="…"`).
+//! - Markdown: a `---` front-matter block between the heading and the code fence.
+//! - JSON / JSON Lines: `modified`, `sha256`, and `git_commit` fields (size, lines, and language
+//!   are always present there, and always collected for these formats).
+//! - Templates: `file.modified`, `file.sha256`, and `file.git_commit` (`file.size` and
+//!   `file.lines` are always present).
+//!
+//! # Notes
+//! - Values describe the file on disk, so a file truncated by the budget or split across parts
+//!   keeps the size, line count, and hash of the whole file. Size and hash cover the raw bytes,
+//!   byte order mark included, so they match `ls -l` and `sha256sum` even for transcoded files.
+//! - Timestamps are UTC, in RFC 3339 format with second precision.
+//! - The git commit is the newest commit that touched the file; it is omitted for untracked files
+//!   and outside repositories (see `git`).
+
+use crate::formats::language_for_path;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use std::path::Path;
+
+/// One optional attribute that can be written with each file.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ValueEnum,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum MetadataField {
+    /// Size in bytes on disk.
+    Size,
+    /// Number of lines.
+    Lines,
+    /// Code-fence language name, derived from the extension.
+    Language,
+    /// Last-modified timestamp.
+    Modified,
+    /// SHA-256 of the bytes on disk, as lowercase hex.
+    Sha256,
+    /// Hash of the newest git commit that touched the file.
+    GitCommit,
+}
+
+impl MetadataField {
+    /// All fields, in the order they are written.
+    pub const ALL: [MetadataField; 6] = [
+        MetadataField::Size,
+        MetadataField::Lines,
+        MetadataField::Language,
+        MetadataField::Modified,
+        MetadataField::Sha256,
+        MetadataField::GitCommit,
+    ];
+
+    /// Attribute or field name used in the output.
+    pub fn name(self) -> &'static str {
+        match self {
+            MetadataField::Size => "size",
+            MetadataField::Lines => "lines",
+            MetadataField::Language => "language",
+            MetadataField::Modified => "modified",
+            MetadataField::Sha256 => "sha256",
+            MetadataField::GitCommit => "git_commit",
+        }
+    }
+}
+
+/// Collected metadata of one file; only the requested fields are set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileMetadata {
+    pub size: Option<u64>,
+    pub lines: Option<usize>,
+    pub language: Option<String>,
+    pub modified: Option<String>,
+    pub sha256: Option<String>,
+    pub git_commit: Option<String>,
+}
+
+impl Default for FileMetadata {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+impl FileMetadata {
+    /// Metadata with no fields set.
+    pub const NONE: FileMetadata = FileMetadata {
+        size: None,
+        lines: None,
+        language: None,
+        modified: None,
+        sha256: None,
+        git_commit: None,
+    };
+
+    /// Collects the requested `fields` for a file read from `path`.
+    ///
+    /// `bytes` are the raw bytes read from `path`, used for the size and hash; `contents` is the
+    /// decoded text, used for the line count. `git_commit` is the file's last commit when known;
+    /// it is only kept if requested.
+    pub fn collect(
+        path: &Path,
+        bytes: &[u8],
+        contents: &str,
+        fields: &[MetadataField],
+        git_commit: Option<String>,
+    ) -> Self {
+        let wants = |field| fields.contains(&field);
+        Self {
+            size: wants(MetadataField::Size).then_some(bytes.len() as u64),
+            lines: wants(MetadataField::Lines).then(|| contents.lines().count()),
+            language: wants(MetadataField::Language)
+                .then(|| language_for_path(path).to_string())
+                .filter(|language| !language.is_empty()),
+            modified: wants(MetadataField::Modified)
+                .then(|| modified_timestamp(path))
+                .flatten(),
+            sha256: wants(MetadataField::Sha256).then(|| sha256_hex(bytes)),
+            git_commit: git_commit.filter(|_| wants(MetadataField::GitCommit)),
+        }
+    }
+
+    /// Returns every set field as `(name, value)` pairs, in [`MetadataField::ALL`] order.
+    pub fn attributes(&self) -> Vec<(&'static str, String)> {
+        MetadataField::ALL
+            .into_iter()
+            .filter_map(|field| {
+                let value = match field {
+                    MetadataField::Size => self.size.map(|size| size.to_string()),
+                    MetadataField::Lines => self.lines.map(|lines| lines.to_string()),
+                    MetadataField::Language => self.language.clone(),
+                    MetadataField::Modified => self.modified.clone(),
+                    MetadataField::Sha256 => self.sha256.clone(),
+                    MetadataField::GitCommit => self.git_commit.clone(),
+                }?;
+                Some((field.name(), value))
+            })
+            .collect()
+    }
+}
+
+fn modified_timestamp(path: &Path) -> Option<String> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(humantime::format_rfc3339_seconds(modified).to_string())
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .fold(String::with_capacity(64), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn collects_only_requested_fields() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let path = temp.path().join("main.rs");
+        fs::write(&path, "abc\n")?;
+
+        let metadata = FileMetadata::collect(
+            &path,
+            b"abc\n",
+            "abc\n",
+            &[
+                MetadataField::Lines,
+                MetadataField::Sha256,
+                MetadataField::Modified,
+            ],
+            Some("1234abcd".to_string()),
+        );
+
+        assert_eq!(metadata.size, None);
+        assert_eq!(metadata.lines, Some(1));
+        assert_eq!(
+            metadata.sha256.as_deref(),
+            Some("edeaaff3f1774ad2888673770c6d64097e391bc362d7d6fb34982ddf0efd18cb")
+        );
+        assert!(metadata
+            .modified
+            .as_deref()
+            .is_some_and(|modified| modified.ends_with('Z')));
+        assert_eq!(metadata.git_commit, None);
+        Ok(())
+    }
+
+    #[test]
+    fn size_and_hash_describe_the_raw_bytes() {
+        let metadata = FileMetadata::collect(
+            Path::new("main.rs"),
+            b"\xEF\xBB\xBFabc\n",
+            "abc\n",
+            &[MetadataField::Size, MetadataField::Sha256],
+            None,
+        );
+
+        assert_eq!(metadata.size, Some(7));
+        assert_eq!(metadata.sha256, Some(sha256_hex(b"\xEF\xBB\xBFabc\n")));
+        assert_ne!(metadata.sha256, Some(sha256_hex(b"abc\n")));
+    }
+
+    #[test]
+    fn attributes_follow_field_order() {
+        let metadata = FileMetadata {
+            git_commit: Some("1234abcd".to_string()),
+            size: Some(4),
+            language: Some("rust".to_string()),
+            ..FileMetadata::NONE
+        };
+
+        assert_eq!(
+            metadata.attributes(),
+            vec![
+                ("size", "4".to_string()),
+                ("language", "rust".to_string()),
+                ("git_commit", "1234abcd".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! # Ordering Module
+//!
+//! Sorts the files selected by a folder scan so the same project always produces the same output,
+//! regardless of the order in which the filesystem returns directory entries.
+//!
+//! # Orderings
+//! - [`FileOrder::Path`] (default): alphabetical by relative path, compared folder by folder.
+//! - [`FileOrder::DirsFirst`]: like `Path`, but sub-folders come before files at every level.
+//! - [`FileOrder::Size`]: smallest files first.
+//! - [`FileOrder::Modified`]: most recently modified files first.
+//!
+//! # Notes
+//! - Priority globs (see `patterns::PriorityPatterns`) always win over the selected ordering.
+//! - Ties are broken by relative path, so every ordering is fully deterministic.
+//! - Files whose metadata cannot be read sort as if they were empty and never modified.
+
+use crate::patterns::PriorityPatterns;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Order in which selected files are written to the output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileOrder {
+    /// Alphabetical by relative path.
+    #[default]
+    Path,
+    /// Alphabetical, with sub-folders listed before files at each level.
+    DirsFirst,
+    /// Smallest files first.
+    Size,
+    /// Most recently modified files first.
+    Modified,
+}
+
+impl FileOrder {
+    /// All orderings, in the order they are offered to users.
+    pub const ALL: [FileOrder; 4] = [
+        FileOrder::Path,
+        FileOrder::DirsFirst,
+        FileOrder::Size,
+        FileOrder::Modified,
+    ];
+
+    /// Short user-facing label.
+    pub fn label(self) -> &'static str {
+        match self {
+            FileOrder::Path => "Alphabetical",
+            FileOrder::DirsFirst => "Directories first",
+            FileOrder::Size => "Size (smallest first)",
+            FileOrder::Modified => "Modified (newest first)",
+        }
+    }
+}
+
+/// Sorts `files` (paths under `root_dir`) by priority rank, then by `order`, then by relative path.
+pub fn sort_files(
+    root_dir: &Path,
+    files: &mut [PathBuf],
+    order: FileOrder,
+    priority: &PriorityPatterns,
+) {
+    files.sort_by_cached_key(|path| {
+        let relative_path = path.strip_prefix(root_dir).unwrap_or(path).to_path_buf();
+        let metadata = match order {
+            FileOrder::Size | FileOrder::Modified => fs::metadata(path).ok(),
+            FileOrder::Path | FileOrder::DirsFirst => None,
+        };
+        let size = match order {
+            FileOrder::Size => metadata.as_ref().map_or(0, fs::Metadata::len),
+            _ => 0,
+        };
+        let modified = match order {
+            FileOrder::Modified => metadata
+                .and_then(|metadata| metadata.modified().ok())
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |duration| duration.as_nanos()),
+            _ => 0,
+        };
+        let folders_first = match order {
+            FileOrder::DirsFirst => dirs_first_key(&relative_path),
+            _ => Vec::new(),
+        };
+
+        (
+            priority.rank(&relative_path),
+            size,
+            Reverse(modified),
+            folders_first,
+            relative_path,
+        )
+    });
+}
+
+/// Builds a per-component key where folder components (every component but the last) sort
+/// before file names that share the same parent.
+fn dirs_first_key(relative_path: &Path) -> Vec<(bool, OsString)> {
+    let components: Vec<_> = relative_path.components().collect();
+    let last_index = components.len().saturating_sub(1);
+    components
+        .into_iter()
+        .enumerate()
+        .map(|(index, component)| (index == last_index, component.as_os_str().to_os_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn relative(root: &Path, files: &[PathBuf]) -> Vec<String> {
+        files
+            .iter()
+            .map(|path| {
+                path.strip_prefix(root)
+                    .expect("path under root")
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .collect()
+    }
+
+    fn no_priority() -> PriorityPatterns {
+        PriorityPatterns::compile(&[]).expect("empty patterns compile")
+    }
+
+    #[test]
+    fn path_and_dirs_first_orderings_are_deterministic() {
+        let root = Path::new("project");
+        let mut files = vec![
+            root.join("z.rs"),
+            root.join("src").join("main.rs"),
+            root.join("a.rs"),
+            root.join("src").join("bin").join("tool.rs"),
+        ];
+
+        sort_files(root, &mut files, FileOrder::Path, &no_priority());
+        assert_eq!(
+            relative(root, &files),
+            vec!["a.rs", "src/bin/tool.rs", "src/main.rs", "z.rs"]
+        );
+
+        sort_files(root, &mut files, FileOrder::DirsFirst, &no_priority());
+        assert_eq!(
+            relative(root, &files),
+            vec!["src/bin/tool.rs", "src/main.rs", "a.rs", "z.rs"]
+        );
+    }
+
+    #[test]
+    fn size_ordering_puts_smallest_first_and_breaks_ties_by_path() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let root = temp.path();
+        fs::write(root.join("big.rs"), "0123456789")?;
+        fs::write(root.join("b.rs"), "1")?;
+        fs::write(root.join("a.rs"), "1")?;
+        let mut files = vec![root.join("big.rs"), root.join("b.rs"), root.join("a.rs")];
+
+        sort_files(root, &mut files, FileOrder::Size, &no_priority());
+
+        assert_eq!(relative(root, &files), vec!["a.rs", "b.rs", "big.rs"]);
+        Ok(())
+    }
+
+    #[test]
+    fn priority_patterns_come_before_selected_order() {
+        let root = Path::new("project");
+        let mut files = vec![
+            root.join("README.md"),
+            root.join("src").join("main.rs"),
+            root.join("Cargo.toml"),
+            root.join("src").join("lib.rs"),
+        ];
+        let priority =
+            PriorityPatterns::compile(&["Cargo.toml".to_string(), "src/main.rs".to_string()])
+                .expect("patterns compile");
+
+        sort_files(root, &mut files, FileOrder::Path, &priority);
+
+        assert_eq!(
+            relative(root, &files),
+            vec!["Cargo.toml", "src/main.rs", "README.md", "src/lib.rs"]
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! # Output Text Module
+//!
+//! Stores the user-editable wording that surrounds the wrapped files: an optional header, the
+//! instruction footer, and the title of the Additional Commands section.
+//!
+//! # Key Components
+//! - [`OutputText`]: The configurable texts, each of which can be left at its default, replaced, or disabled.
+//! - [`get_output_text`]: Loads the team-wide texts from `output_text.json`.
+//!
+//! # Value Semantics
+//! - `null` (or a missing field) keeps the built-in text for the selected output format.
+//! - An empty string disables that part of the output.
+//! - Any other string replaces the built-in text verbatim.
+//!
+//! # File Format
+//! ```json
+//! {
+//!   "header": "Project snapshot for review:",
+//!   "footer": "* Answer with complete files only.",
+//!   "additional_commands_title": "[Tasks]"
+//! }
+//! ```
+//!
+//! # Notes
+//! - Run profiles and CLI flags can override each field; see [`OutputText::with_overrides`].
+//! - A missing or unreadable file yields the built-in defaults, matching the original output.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+pub const OUTPUT_TEXT_FILE: &str = "output_text.json";
+
+/// Header, footer, and section title wording for generated output.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputText {
+    /// Text written after the output marker, before the first file. Off by default.
+    #[serde(default)]
+    pub header: Option<String>,
+    /// Instruction text written after the last file.
+    #[serde(default)]
+    pub footer: Option<String>,
+    /// Title line written above the additional commands.
+    #[serde(default)]
+    pub additional_commands_title: Option<String>,
+}
+
+impl OutputText {
+    /// Returns a copy where every field set in `overrides` replaces the value in `self`.
+    pub fn with_overrides(&self, overrides: &OutputText) -> OutputText {
+        OutputText {
+            header: overrides.header.clone().or_else(|| self.header.clone()),
+            footer: overrides.footer.clone().or_else(|| self.footer.clone()),
+            additional_commands_title: overrides
+                .additional_commands_title
+                .clone()
+                .or_else(|| self.additional_commands_title.clone()),
+        }
+    }
+
+    /// Returns `true` when no field has been customized.
+    pub fn is_default(&self) -> bool {
+        self == &OutputText::default()
+    }
+}
+
+/// Resolves a configurable text: the custom value, `default` when unset, or `None` when disabled.
+pub fn configured_text<'a>(value: &'a Option<String>, default: &'a str) -> Option<&'a str> {
+    match value.as_deref() {
+        None => Some(default).filter(|text| !text.is_empty()),
+        Some(text) if text.trim().is_empty() => None,
+        Some(text) => Some(text),
+    }
+}
+
+/// Loads output text settings from `output_text.json` in the current working directory.
+///
+/// Returns the built-in defaults when the file is missing or cannot be parsed, so a broken
+/// config never blocks generation.
+pub fn get_output_text() -> OutputText {
+    load_output_text_from_path(OUTPUT_TEXT_FILE).unwrap_or_default()
+}
+
+/// Loads output text settings from `path`, treating a missing or empty file as the defaults.
+pub fn load_output_text_from_path(path: impl AsRef<Path>) -> std::io::Result<OutputText> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(OutputText::default());
+    }
+
+    let data = fs::read_to_string(path)?;
+    if data.trim().is_empty() {
+        return Ok(OutputText::default());
+    }
+
+    serde_json::from_str(&data).map_err(|error| Error::new(ErrorKind::InvalidData, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn configured_text_distinguishes_default_custom_and_disabled() {
+        assert_eq!(configured_text(&None, "default"), Some("default"));
+        assert_eq!(configured_text(&None, ""), None);
+        assert_eq!(
+            configured_text(&Some("custom".to_string()), "default"),
+            Some("custom")
+        );
+        assert_eq!(configured_text(&Some(String::new()), "default"), None);
+    }
+
+    #[test]
+    fn overrides_replace_only_the_fields_they_set() {
+        let config = OutputText {
+            header: Some("team header".to_string()),
+            footer: Some("team footer".to_string()),
+            additional_commands_title: None,
+        };
+        let overrides = OutputText {
+            footer: Some(String::new()),
+            ..OutputText::default()
+        };
+
+        let merged = config.with_overrides(&overrides);
+
+        assert_eq!(merged.header.as_deref(), Some("team header"));
+        assert_eq!(merged.footer.as_deref(), Some(""));
+        assert_eq!(merged.additional_commands_title, None);
+    }
+
+    #[test]
+    fn missing_or_partial_config_file_uses_defaults() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let path = temp.path().join(OUTPUT_TEXT_FILE);
+
+        assert!(load_output_text_from_path(&path)?.is_default());
+
+        fs::write(&path, r#"{"footer": ""}"#)?;
+        let loaded = load_output_text_from_path(&path)?;
+
+        assert_eq!(loaded.header, None);
+        assert_eq!(loaded.footer.as_deref(), Some(""));
+        Ok(())
+    }
+}
@@ -0,0 +1,247 @@
+//! # Path Patterns Module
+//!
+//! Compiles the user-supplied `--include` / `--exclude` glob patterns and evaluates them against
+//! project-relative file paths during a folder scan.
+//!
+//! # Matching Rules
+//! - Patterns are matched against the path relative to the scanned root, using `/` separators on
+//!   every platform (e.g., `src/generated/mod.rs`).
+//! - `*` and `?` never cross a `/`; use `**` to match any number of folders (e.g., `**/*.rs`).
+//! - When include patterns are present, a file must match at least one of them.
+//! - A file matching any exclude pattern is skipped, and the exclusion is credited to the first
+//!   matching pattern in the order the patterns were supplied.
+//! - Priority patterns (see [`PriorityPatterns`]) use the same syntax and rank files by the first
+//!   pattern they match.
+//! - File name patterns (see [`FileNamePatterns`]) select file types by name instead of path:
+//!   they are matched against the file name alone, so `Dockerfile.*` matches in every folder.
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::path::Path;
+
+/// Number of files skipped because of one exclude pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternExclusion {
+    pub pattern: String,
+    pub files_excluded: usize,
+}
+
+/// Outcome of evaluating a relative path against the compiled patterns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternDecision {
+    /// The path passed every include and exclude pattern.
+    Included,
+    /// Include patterns were supplied but none matched the path.
+    NotIncluded,
+    /// The path matched the exclude pattern at this index.
+    Excluded(usize),
+}
+
+/// Compiled include and exclude patterns for a single scan.
+#[derive(Debug, Clone)]
+pub struct PathPatterns {
+    includes: GlobSet,
+    excludes: GlobSet,
+    exclude_patterns: Vec<String>,
+}
+
+impl PathPatterns {
+    /// Compiles include and exclude patterns, returning a user-facing message for invalid globs.
+    pub fn compile(
+        include_patterns: &[String],
+        exclude_patterns: &[String],
+    ) -> Result<Self, String> {
+        Ok(Self {
+            includes: build_glob_set(include_patterns)?,
+            excludes: build_glob_set(exclude_patterns)?,
+            exclude_patterns: exclude_patterns.to_vec(),
+        })
+    }
+
+    /// Decides whether a path relative to the scan root is included.
+    pub fn evaluate(&self, relative_path: &Path) -> PatternDecision {
+        let candidate = to_match_path(relative_path);
+
+        if !self.includes.is_empty() && !self.includes.is_match(&candidate) {
+            return PatternDecision::NotIncluded;
+        }
+
+        match self.excludes.matches(&candidate).first() {
+            Some(&index) => PatternDecision::Excluded(index),
+            None => PatternDecision::Included,
+        }
+    }
+
+    /// Creates one zeroed counter per exclude pattern, in the order they were supplied.
+    pub fn empty_exclusions(&self) -> Vec<PatternExclusion> {
+        self.exclude_patterns
+            .iter()
+            .map(|pattern| PatternExclusion {
+                pattern: pattern.clone(),
+                files_excluded: 0,
+            })
+            .collect()
+    }
+}
+
+/// Compiled priority globs used to move specific files to the front of the output.
+#[derive(Debug, Clone)]
+pub struct PriorityPatterns {
+    patterns: Vec<GlobSet>,
+}
+
+impl PriorityPatterns {
+    /// Compiles priority globs, keeping them in the order supplied.
+    pub fn compile(priority_patterns: &[String]) -> Result<Self, String> {
+        let patterns = priority_patterns
+            .iter()
+            .map(|pattern| build_glob_set(std::slice::from_ref(pattern)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// Returns the index of the first matching priority glob, or the number of globs when none match.
+    ///
+    /// Lower ranks sort first, so unmatched files always follow prioritised ones.
+    pub fn rank(&self, relative_path: &Path) -> usize {
+        let candidate = to_match_path(relative_path);
+        self.patterns
+            .iter()
+            .position(|pattern| pattern.is_match(&candidate))
+            .unwrap_or(self.patterns.len())
+    }
+}
+
+/// Compiled exact file names and file-name globs that select files regardless of extension.
+#[derive(Debug, Clone)]
+pub struct FileNamePatterns {
+    names: GlobSet,
+}
+
+impl FileNamePatterns {
+    /// Compiles file names (`Makefile`) and file-name globs (`Dockerfile.*`, `.env.*`).
+    pub fn compile(file_names: &[String]) -> Result<Self, String> {
+        Ok(Self {
+            names: build_glob_set(file_names)?,
+        })
+    }
+
+    /// Returns `true` if the file name of `path` matches any pattern.
+    pub fn matches(&self, path: &Path) -> bool {
+        path.file_name()
+            .is_some_and(|file_name| self.names.is_match(Path::new(file_name)))
+    }
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .backslash_escape(false)
+            .build()
+            .map_err(|error| format!("Invalid glob pattern '{pattern}': {error}"))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|error| format!("Invalid glob patterns: {error}"))
+}
+
+/// Renders a relative path with `/` separators so patterns behave the same on every platform.
+fn to_match_path(relative_path: &Path) -> String {
+    relative_path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(include: &[&str], exclude: &[&str]) -> PathPatterns {
+        let include: Vec<String> = include.iter().map(|p| p.to_string()).collect();
+        let exclude: Vec<String> = exclude.iter().map(|p| p.to_string()).collect();
+        PathPatterns::compile(&include, &exclude).expect("patterns should compile")
+    }
+
+    #[test]
+    fn exclude_pattern_only_matches_its_anchored_folder() {
+        let patterns = patterns(&[], &["src/generated/**"]);
+
+        assert_eq!(
+            patterns.evaluate(Path::new("src/generated/mod.rs")),
+            PatternDecision::Excluded(0)
+        );
+        assert_eq!(
+            patterns.evaluate(Path::new("tools/generated/mod.rs")),
+            PatternDecision::Included
+        );
+    }
+
+    #[test]
+    fn include_pattern_single_star_does_not_cross_folders() {
+        let patterns = patterns(&["crates/*/src/**/*.rs"], &[]);
+
+        assert_eq!(
+            patterns.evaluate(Path::new("crates/core/src/lib.rs")),
+            PatternDecision::Included
+        );
+        assert_eq!(
+            patterns.evaluate(Path::new("crates/core/nested/src/lib.rs")),
+            PatternDecision::NotIncluded
+        );
+    }
+
+    #[test]
+    fn exclusion_is_credited_to_first_matching_pattern() {
+        let patterns = patterns(&[], &["**/*.rs", "src/**"]);
+
+        assert_eq!(
+            patterns.evaluate(Path::new("src/main.rs")),
+            PatternDecision::Excluded(0)
+        );
+        assert_eq!(
+            patterns.evaluate(Path::new("src/notes.md")),
+            PatternDecision::Excluded(1)
+        );
+    }
+
+    #[test]
+    fn priority_rank_follows_supplied_order() {
+        let priority =
+            PriorityPatterns::compile(&["Cargo.toml".to_string(), "src/*.rs".to_string()])
+                .expect("patterns should compile");
+
+        assert_eq!(priority.rank(Path::new("Cargo.toml")), 0);
+        assert_eq!(priority.rank(Path::new("src/main.rs")), 1);
+        assert_eq!(priority.rank(Path::new("README.md")), 2);
+    }
+
+    #[test]
+    fn file_name_patterns_match_names_in_any_folder() {
+        let names = FileNamePatterns::compile(&[
+            "Makefile".to_string(),
+            "Dockerfile.*".to_string(),
+            ".env.*".to_string(),
+        ])
+        .expect("patterns should compile");
+
+        assert!(names.matches(Path::new("Makefile")));
+        assert!(names.matches(Path::new("tools/build/Makefile")));
+        assert!(names.matches(Path::new("docker/Dockerfile.dev")));
+        assert!(names.matches(Path::new(".env.example")));
+        assert!(!names.matches(Path::new("Makefile.bak")));
+        assert!(!names.matches(Path::new("makefile")));
+        assert!(!names.matches(Path::new("Dockerfile")));
+    }
+
+    #[test]
+    fn invalid_pattern_reports_the_pattern() {
+        let error = PathPatterns::compile(&["src/[".to_string()], &[])
+            .expect_err("unclosed class should fail");
+
+        assert!(error.contains("Invalid glob pattern 'src/['"));
+    }
+}
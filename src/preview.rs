@@ -0,0 +1,361 @@
+//! # Preview Module
+//!
+//! Describes what a run would write without writing it: every file that would be included, with
+//! its size and estimated tokens, and every file or folder that would be left out, with the reason.
+//! Backs `run --dry-run` and the `list` subcommand.
+//!
+//! # Entry Order
+//! - Included (and truncated) files come first, in output order.
+//! - Files dropped by the output budget follow, in the order the budget considered them.
+//! - Everything the scan passed over comes last, sorted by path.
+//!
+//! # Notes
+//! - Decisions come from `file_ops::prepare_roots`, the same code path `write_roots` uses, so a
+//!   preview never disagrees with the run it describes.
+//! - Sizes are on-disk sizes; tokens are counted on the contents that would be written.
+
+use crate::file_ops::{PreparedFiles, ScanRoot};
+use crate::formats::PathStyle;
+use serde::Serialize;
+use std::path::Path;
+
+/// What a run would do with one file or folder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PreviewDecision {
+    Included,
+    Truncated,
+    Skipped,
+}
+
+impl PreviewDecision {
+    /// Short user-facing label.
+    pub fn label(self) -> &'static str {
+        match self {
+            PreviewDecision::Included => "included",
+            PreviewDecision::Truncated => "truncated",
+            PreviewDecision::Skipped => "skipped",
+        }
+    }
+}
+
+/// One row of a selection preview.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PreviewEntry {
+    /// Path relative to the scanned root, rendered with the run's path style.
+    pub path: String,
+    /// `file` or `folder`.
+    pub kind: &'static str,
+    /// Size on disk in bytes, for files whose metadata could be read.
+    pub size: Option<u64>,
+    /// Estimated tokens of the contents that would be written (or were measured by the budget).
+    pub tokens: Option<usize>,
+    pub decision: PreviewDecision,
+    /// Why the file was truncated or skipped.
+    pub reason: Option<String>,
+}
+
+/// Every decision a run would make, in the order described in the module docs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SelectionPreview {
+    /// Scanned root directory (the first one, when several roots are scanned).
+    pub root: String,
+    /// Tokenizer used for the estimates.
+    pub tokenizer: String,
+    pub entries: Vec<PreviewEntry>,
+    /// Number of files that would be written, including truncated files.
+    pub included: usize,
+    /// Number of files and folders that would be left out.
+    pub skipped: usize,
+    /// Estimated tokens of every file that would be written.
+    pub total_tokens: usize,
+}
+
+impl SelectionPreview {
+    /// Builds the preview for files prepared from `roots`.
+    ///
+    /// Skipped paths are shown like written paths: relative to their root, after its path prefix.
+    pub fn new(
+        roots: &[ScanRoot],
+        path_style: PathStyle,
+        prepared: &PreparedFiles,
+        tokenizer: String,
+    ) -> Self {
+        let display = |path: &Path| {
+            roots
+                .iter()
+                .find_map(|root| {
+                    let relative = path.strip_prefix(&root.dir).ok()?;
+                    match &root.options.path_prefix {
+                        Some(prefix) => path_style.render(&Path::new(prefix).join(relative)),
+                        None => path_style.render(relative),
+                    }
+                })
+                .unwrap_or_else(|| path.display().to_string())
+        };
+        let file_size = |path: &Path| std::fs::metadata(path).ok().map(|metadata| metadata.len());
+
+        let mut entries = Vec::new();
+        for file in &prepared.files {
+            let truncation = prepared
+                .summary
+                .truncated_files
+                .iter()
+                .find(|truncated| truncated.path == file.display_path);
+            entries.push(PreviewEntry {
+                path: file.display_path.clone(),
+                kind: "file",
+                size: file_size(&file.path),
+                tokens: Some(file.tokens),
+                decision: if truncation.is_some() {
+                    PreviewDecision::Truncated
+                } else {
+                    PreviewDecision::Included
+                },
+                reason: truncation.map(|truncated| {
+                    format!("over budget: {} lines removed", truncated.lines_removed)
+                }),
+            });
+        }
+
+        for dropped in &prepared.summary.budget_dropped {
+            entries.push(PreviewEntry {
+                path: dropped.path.clone(),
+                kind: "file",
+                size: file_size(&dropped.source),
+                tokens: Some(dropped.tokens),
+                decision: PreviewDecision::Skipped,
+                reason: Some("over budget".to_string()),
+            });
+        }
+
+        let mut skipped: Vec<PreviewEntry> = prepared
+            .skipped
+            .iter()
+            .map(|skipped| PreviewEntry {
+                path: display(&skipped.path),
+                kind: if skipped.is_dir { "folder" } else { "file" },
+                size: if skipped.is_dir {
+                    None
+                } else {
+                    file_size(&skipped.path)
+                },
+                tokens: None,
+                decision: PreviewDecision::Skipped,
+                reason: Some(skipped.reason.label()),
+            })
+            .collect();
+        skipped.sort_by(|a, b| a.path.cmp(&b.path));
+        entries.extend(skipped);
+
+        let included = prepared.files.len();
+        Self {
+            root: roots
+                .first()
+                .map(|root| root.dir.display().to_string())
+                .unwrap_or_default(),
+            tokenizer,
+            skipped: entries.len() - included,
+            total_tokens: prepared.files.iter().map(|file| file.tokens).sum(),
+            included,
+            entries,
+        }
+    }
+
+    /// Renders the preview as an aligned table followed by a totals line.
+    pub fn format_table(&self) -> String {
+        let rows: Vec<[String; 4]> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let path = if entry.kind == "folder" {
+                    format!("{}/", entry.path)
+                } else {
+                    entry.path.clone()
+                };
+                let decision = match &entry.reason {
+                    Some(reason) => format!("{} ({reason})", entry.decision.label()),
+                    None => entry.decision.label().to_string(),
+                };
+                [
+                    path,
+                    entry.size.map_or("-".to_string(), |size| size.to_string()),
+                    entry
+                        .tokens
+                        .map_or("-".to_string(), |tokens| tokens.to_string()),
+                    decision,
+                ]
+            })
+            .collect();
+
+        let headers = ["PATH", "SIZE", "TOKENS", "DECISION"];
+        let width = |column: usize| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .chain([headers[column].len()])
+                .max()
+                .unwrap_or(0)
+        };
+        let (path_width, size_width, tokens_width) = (width(0), width(1), width(2));
+
+        let mut table = String::new();
+        for row in std::iter::once(headers.map(str::to_string)).chain(rows) {
+            let line = format!(
+                "{:<path_width$}  {:>size_width$}  {:>tokens_width$}  {}",
+                row[0], row[1], row[2], row[3]
+            );
+            table.push_str(line.trim_end());
+            table.push('\n');
+        }
+        table.push_str(&format!(
+            "\n{} included, {} skipped, {} estimated tokens ({})\n",
+            self.included, self.skipped, self.total_tokens, self.tokenizer
+        ));
+        table
+    }
+
+    /// Renders the preview as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::{DroppedFile, TruncatedFile};
+    use crate::file_ops::{
+        LoadedFile, ScanOptions, SkipReason, SkippedPath, WriteFolderTagsSummary,
+    };
+    use crate::metadata::FileMetadata;
+    use std::path::PathBuf;
+
+    fn roots() -> Vec<ScanRoot> {
+        vec![ScanRoot {
+            dir: PathBuf::from("project"),
+            options: ScanOptions::default(),
+        }]
+    }
+
+    fn prepared() -> PreparedFiles {
+        let root = PathBuf::from("project");
+        PreparedFiles {
+            files: vec![
+                LoadedFile {
+                    path: root.join("main.rs"),
+                    display_path: "main.rs".to_string(),
+                    metadata: FileMetadata::default(),
+                    contents: "fn main() {}\n".to_string(),
+                    tokens: 7,
+                },
+                LoadedFile {
+                    path: root.join("big.rs"),
+                    display_path: "big.rs".to_string(),
+                    metadata: FileMetadata::default(),
+                    contents: "a\n[truncated 3 lines]\n".to_string(),
+                    tokens: 9,
+                },
+            ],
+            summary: WriteFolderTagsSummary {
+                truncated_files: vec![TruncatedFile {
+                    path: "big.rs".to_string(),
+                    lines_removed: 3,
+                }],
+                budget_dropped: vec![DroppedFile {
+                    path: "huge.rs".to_string(),
+                    source: root.join("huge.rs"),
+                    tokens: 500,
+                    bytes: 2000,
+                }],
+                ..WriteFolderTagsSummary::default()
+            },
+            skipped: vec![
+                SkippedPath {
+                    path: root.join("target"),
+                    is_dir: true,
+                    reason: SkipReason::IgnoredFolder,
+                },
+                SkippedPath {
+                    path: root.join("notes.txt"),
+                    is_dir: false,
+                    reason: SkipReason::ExtensionMismatch,
+                },
+            ],
+            tree: None,
+            diff: None,
+        }
+    }
+
+    #[test]
+    fn entries_list_included_then_dropped_then_skipped_by_path() {
+        let preview = SelectionPreview::new(
+            &roots(),
+            PathStyle::ForwardSlash,
+            &prepared(),
+            "heuristic".to_string(),
+        );
+
+        let rows: Vec<(&str, PreviewDecision)> = preview
+            .entries
+            .iter()
+            .map(|entry| (entry.path.as_str(), entry.decision))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("main.rs", PreviewDecision::Included),
+                ("big.rs", PreviewDecision::Truncated),
+                ("huge.rs", PreviewDecision::Skipped),
+                ("notes.txt", PreviewDecision::Skipped),
+                ("target", PreviewDecision::Skipped),
+            ]
+        );
+        assert_eq!((preview.included, preview.skipped), (2, 3));
+        assert_eq!(preview.total_tokens, 16);
+    }
+
+    #[test]
+    fn table_and_json_show_reasons() {
+        let preview = SelectionPreview::new(
+            &roots(),
+            PathStyle::ForwardSlash,
+            &prepared(),
+            "heuristic".to_string(),
+        );
+
+        let table = preview.format_table();
+        assert!(table.starts_with("PATH "));
+        assert!(table.contains("target/"));
+        assert!(table.contains("skipped (ignored folder)"));
+        assert!(table.contains("truncated (over budget: 3 lines removed)"));
+        assert!(table.contains("2 included, 3 skipped, 16 estimated tokens (heuristic)"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&preview.to_json()).expect("preview JSON parses");
+        assert_eq!(json["entries"][2]["reason"], "over budget");
+        assert_eq!(json["entries"][2]["tokens"], 500);
+        assert_eq!(json["entries"][4]["kind"], "folder");
+        assert_eq!(json["entries"][4]["decision"], "skipped");
+    }
+
+    #[test]
+    fn dropped_files_report_their_size_on_disk() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let huge = dir.path().join("huge.rs");
+        std::fs::write(&huge, "\u{feff}fn huge() {}\n")?;
+
+        let mut prepared = prepared();
+        prepared.summary.budget_dropped[0].source = huge;
+        let preview = SelectionPreview::new(
+            &roots(),
+            PathStyle::ForwardSlash,
+            &prepared,
+            "heuristic".to_string(),
+        );
+
+        let dropped = &preview.entries[2];
+        assert_eq!(dropped.path, "huge.rs");
+        assert_eq!(dropped.size, Some(16));
+        Ok(())
+    }
+}
@@ -1,6 +1,16 @@
-use crate::cli::{build_run_request, BuiltRunRequest, RunArgs};
+use crate::budget::OutputBudget;
+use crate::chunks::ChunkLimit;
+use crate::cli::{build_run_request, validate_run_args, BuiltRunRequest, RunArgs};
+use crate::content::TextEncoding;
 use crate::filetypes::FileTypeGroup;
+use crate::formats::{OutputFormat, PathStyle};
+use crate::git::GitFileMode;
+use crate::metadata::MetadataField;
+use crate::ordering::FileOrder;
+use crate::output_text::OutputText;
 use crate::presets::PresetCommand;
+use crate::roots::RootSpec;
+use crate::tree::TreeOptions;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::{Error, ErrorKind};
@@ -12,11 +22,71 @@ pub const PROFILES_FILE: &str = "profiles.json";
 pub struct RunProfile {
     pub name: String,
     pub dir: PathBuf,
+    /// `--alias` for `dir`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// Additional `--root` folders, with their own file types and ignored folders.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roots: Vec<RootSpec>,
     pub file_type: Option<String>,
     pub extensions: Vec<String>,
+    #[serde(default)]
+    pub strict_extension_case: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filenames: Vec<String>,
+    #[serde(default)]
+    pub detect_shebangs: bool,
+    /// `--files-from` as given (`-` reads the list from stdin when the profile runs).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub files_from: Option<PathBuf>,
     pub recursive: bool,
     pub ignored_folders: Vec<String>,
+    #[serde(default)]
+    pub respect_gitignore: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_files: Option<GitFileMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changed_since: Option<String>,
+    #[serde(default)]
+    pub include_patterns: Vec<String>,
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+    #[serde(default)]
+    pub skip_previous_outputs: bool,
+    #[serde(default)]
+    pub file_order: FileOrder,
+    #[serde(default)]
+    pub priority_patterns: Vec<String>,
     pub output: PathBuf,
+    #[serde(default)]
+    pub output_format: OutputFormat,
+    #[serde(default)]
+    pub path_style: PathStyle,
+    #[serde(default, skip_serializing_if = "OutputText::is_default")]
+    pub output_text: OutputText,
+    #[serde(default)]
+    pub template: Option<PathBuf>,
+    #[serde(default)]
+    pub token_vocab: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "OutputBudget::is_default")]
+    pub budget: OutputBudget,
+    #[serde(default, skip_serializing_if = "ChunkLimit::is_unlimited")]
+    pub chunks: ChunkLimit,
+    #[serde(default, skip_serializing_if = "TreeOptions::is_default")]
+    pub tree: TreeOptions,
+    #[serde(default)]
+    pub diff: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff_base: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub metadata: Vec<MetadataField>,
+    #[serde(default)]
+    pub line_numbers: bool,
+    /// `--max-file-size` as given; `None` keeps the default limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_encodings: Vec<TextEncoding>,
     pub copy: bool,
     pub open: bool,
     pub presets: Vec<String>,
@@ -67,15 +137,47 @@ pub fn profile_from_run_args(name: String, args: RunArgs) -> Result<RunProfile,
         args.additional_commands_file.as_ref(),
         args.additional_commands.as_deref(),
     )?;
+    let output_text = crate::cli::output_text_overrides(&args);
+    let budget = crate::cli::output_budget(&args);
+    let chunks = crate::cli::chunk_limit(&args);
+    let tree = crate::cli::tree_options(&args);
 
     Ok(RunProfile {
         name,
         dir: args.dir,
+        alias: args.alias,
+        roots: args.roots,
         file_type: args.file_type,
         extensions: args.extensions,
+        strict_extension_case: args.strict_extension_case,
+        filenames: args.filenames,
+        detect_shebangs: args.detect_shebangs,
+        files_from: args.files_from,
         recursive: args.recursive,
         ignored_folders: args.ignored_folders,
+        respect_gitignore: args.respect_gitignore,
+        git_files: args.git_files,
+        changed_since: args.changed_since,
+        include_patterns: args.include_patterns,
+        exclude_patterns: args.exclude_patterns,
+        skip_previous_outputs: args.skip_previous_outputs,
+        file_order: args.file_order,
+        priority_patterns: args.priority_patterns,
         output: args.output,
+        output_format: args.output_format,
+        path_style: args.path_style,
+        output_text,
+        template: args.template,
+        token_vocab: args.token_vocab,
+        budget,
+        chunks,
+        tree,
+        diff: args.diff,
+        diff_base: args.diff_base,
+        metadata: args.metadata,
+        line_numbers: args.line_numbers,
+        max_file_size: args.max_file_size,
+        fallback_encodings: args.fallback_encodings,
         copy: args.copy,
         open: args.open,
         presets: args.presets,
@@ -143,6 +245,23 @@ pub fn profile_to_run_request(
     file_type_groups: &[FileTypeGroup],
     presets: &[PresetCommand],
 ) -> Result<BuiltRunRequest, String> {
+    validate_profile_directory(profile)?;
+    build_run_request(profile.clone().into_run_args(), file_type_groups, presets)
+        .map_err(|error| format!("Profile '{}': {error}", profile.name))
+}
+
+/// Checks that `profile` would build a run request, without reading its `--files-from` list.
+pub fn validate_profile(
+    profile: &RunProfile,
+    file_type_groups: &[FileTypeGroup],
+    presets: &[PresetCommand],
+) -> Result<(), String> {
+    validate_profile_directory(profile)?;
+    validate_run_args(profile.clone().into_run_args(), file_type_groups, presets)
+        .map_err(|error| format!("Profile '{}': {error}", profile.name))
+}
+
+fn validate_profile_directory(profile: &RunProfile) -> Result<(), String> {
     if !profile.dir.is_dir() {
         return Err(format!(
             "Profile '{}' directory '{}' does not exist or is not a folder.",
@@ -150,25 +269,63 @@ pub fn profile_to_run_request(
             profile.dir.display()
         ));
     }
-
-    build_run_request(profile.clone().into_run_args(), file_type_groups, presets)
-        .map_err(|error| format!("Profile '{}': {error}", profile.name))
+    Ok(())
 }
 
 impl RunProfile {
     fn into_run_args(self) -> RunArgs {
         RunArgs {
             dir: self.dir,
+            alias: self.alias,
+            roots: self.roots,
             file_type: self.file_type,
             extensions: self.extensions,
+            strict_extension_case: self.strict_extension_case,
+            filenames: self.filenames,
+            detect_shebangs: self.detect_shebangs,
+            files_from: self.files_from,
             recursive: self.recursive,
             ignored_folders: self.ignored_folders,
+            respect_gitignore: self.respect_gitignore,
+            git_files: self.git_files,
+            changed_since: self.changed_since,
+            include_patterns: self.include_patterns,
+            exclude_patterns: self.exclude_patterns,
+            skip_previous_outputs: self.skip_previous_outputs,
+            file_order: self.file_order,
+            priority_patterns: self.priority_patterns,
             output: self.output,
+            output_format: self.output_format,
+            path_style: self.path_style,
+            header: self.output_text.header,
+            no_header: false,
+            footer: self.output_text.footer,
+            no_footer: false,
+            additional_title: self.output_text.additional_commands_title,
+            no_additional_title: false,
+            template: self.template,
+            token_vocab: self.token_vocab,
+            max_tokens: self.budget.max_tokens,
+            max_bytes: self.budget.max_bytes,
+            truncate: self.budget.truncate,
+            budget_rank: self.budget.rank,
+            chunk_tokens: self.chunks.max_tokens,
+            chunk_bytes: self.chunks.max_bytes,
+            tree: self.tree.enabled,
+            tree_depth: self.tree.max_depth,
+            diff: self.diff,
+            diff_base: self.diff_base,
+            metadata: self.metadata,
+            line_numbers: self.line_numbers,
+            max_file_size: self.max_file_size,
+            fallback_encodings: self.fallback_encodings,
             copy: self.copy,
             open: self.open,
             presets: self.presets,
             additional_commands: self.additional_commands,
             additional_commands_file: None,
+            dry_run: false,
+            json: false,
         }
     }
 }
@@ -176,17 +333,67 @@ impl RunProfile {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::budget::BudgetRank;
     use tempfile::tempdir;
 
     fn profile(name: &str) -> RunProfile {
         RunProfile {
             name: name.to_string(),
             dir: PathBuf::from("."),
+            alias: Some("app".to_string()),
+            roots: vec![RootSpec {
+                dir: PathBuf::from("../web"),
+                alias: Some("web".to_string()),
+                file_type: Some("TypeScript".to_string()),
+                ignored_folders: vec!["dist".to_string()],
+                ..RootSpec::default()
+            }],
             file_type: Some("Rust".to_string()),
             extensions: vec!["toml".to_string()],
+            strict_extension_case: true,
+            filenames: vec!["Makefile".to_string()],
+            detect_shebangs: true,
+            files_from: None,
             recursive: true,
             ignored_folders: vec!["target".to_string()],
+            respect_gitignore: true,
+            git_files: None,
+            changed_since: Some("main".to_string()),
+            include_patterns: vec!["src/**".to_string()],
+            exclude_patterns: vec!["src/generated/**".to_string()],
+            skip_previous_outputs: true,
+            file_order: FileOrder::Modified,
+            priority_patterns: vec!["Cargo.toml".to_string()],
             output: PathBuf::from("context.txt"),
+            output_format: OutputFormat::Markdown,
+            path_style: PathStyle::ForwardSlash,
+            output_text: OutputText {
+                header: None,
+                footer: Some(String::new()),
+                additional_commands_title: Some("## Tasks".to_string()),
+            },
+            template: Some(PathBuf::from("layout.jinja")),
+            token_vocab: None,
+            budget: OutputBudget {
+                max_tokens: Some(100_000),
+                max_bytes: None,
+                truncate: true,
+                rank: BudgetRank::Smallest,
+            },
+            chunks: ChunkLimit {
+                max_tokens: Some(30_000),
+                max_bytes: None,
+            },
+            tree: TreeOptions {
+                enabled: true,
+                max_depth: Some(3),
+            },
+            diff: true,
+            diff_base: Some("main".to_string()),
+            metadata: vec![MetadataField::Sha256, MetadataField::GitCommit],
+            line_numbers: true,
+            max_file_size: Some(0),
+            fallback_encodings: vec![TextEncoding::Utf16Le, TextEncoding::Windows1252],
             copy: false,
             open: true,
             presets: vec!["Known".to_string()],
@@ -207,6 +414,29 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn profiles_saved_before_respect_gitignore_load_with_it_disabled() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let path = temp.path().join("profiles.json");
+        fs::write(
+            &path,
+            r#"[{"name":"old","dir":".","file_type":null,"extensions":["rs"],"recursive":true,
+                "ignored_folders":[],"output":"out.txt","copy":false,"open":false,
+                "presets":[],"additional_commands":null}]"#,
+        )?;
+
+        let reloaded = load_profiles_from_path(&path)?;
+
+        assert_eq!(reloaded.len(), 1);
+        assert!(!reloaded[0].respect_gitignore);
+        assert_eq!(reloaded[0].output_format, OutputFormat::Tags);
+        assert_eq!(reloaded[0].path_style, PathStyle::Native);
+        assert!(reloaded[0].output_text.is_default());
+        assert!(reloaded[0].budget.is_unlimited());
+        assert!(!reloaded[0].tree.enabled);
+        Ok(())
+    }
+
     #[test]
     fn find_profile_by_exact_name() {
         let profiles = vec![profile("daily"), profile("Daily")];
@@ -262,4 +492,36 @@ mod tests {
         assert!(error.contains("Unknown file type group 'Unknown'"));
         Ok(())
     }
+
+    #[test]
+    fn saving_a_stdin_file_list_profile_does_not_read_the_list() -> std::io::Result<()> {
+        use crate::cli::{Cli, Command};
+        use clap::Parser;
+
+        let temp = tempdir()?;
+        let dir = temp.path().to_str().expect("temp path is UTF-8");
+        let cli = Cli::try_parse_from([
+            "code-file-wrapper",
+            "save-profile",
+            "t",
+            "--dir",
+            dir,
+            "--files-from",
+            "-",
+        ])
+        .expect("CLI should parse");
+        let Some(Command::SaveProfile(args)) = cli.command else {
+            panic!("expected save-profile command");
+        };
+
+        let profile = profile_from_run_args(args.name, args.run).expect("profile should build");
+        validate_profile(&profile, &[], &[]).expect("stdin list is read when the profile runs");
+        assert_eq!(profile.files_from, Some(PathBuf::from("-")));
+
+        let mut missing = profile.clone();
+        missing.files_from = Some(temp.path().join("missing.txt"));
+        let error = validate_profile(&missing, &[], &[]).expect_err("missing list should fail");
+        assert!(error.contains("missing.txt"), "{error}");
+        Ok(())
+    }
 }
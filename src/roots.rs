@@ -0,0 +1,127 @@
+//! # Roots Module
+//!
+//! Describes the additional folders of a multi-root generation, such as a backend and a frontend
+//! kept in separate checkouts, as given to `run --root` and saved in run profiles.
+//!
+//! # Format
+//! `PATH[,alias=NAME][,file-type=GROUP][,ext=EXT]...[,filename=NAME]...[,ignore=FOLDER]...`
+//!
+//! For example `../web,alias=frontend,ext=ts,ext=tsx,ignore=dist`. Keys may repeat where
+//! the matching `run` flag may; paths containing a comma cannot be given this way.
+//!
+//! # Notes
+//! - A root without `file-type`, `ext`, or `filename` uses the main root's file types.
+//! - A root without `ignore` uses the main root's ignored folders.
+//! - Resolving groups and inheriting settings happens in `cli`; this module only parses.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// An additional root folder as written on the command line or in a run profile.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RootSpec {
+    pub dir: PathBuf,
+    /// Prefix for this root's paths in the output; defaults to the folder name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// File type group selected for this root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filenames: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignored_folders: Vec<String>,
+}
+
+impl RootSpec {
+    /// Whether the spec chooses its own file types instead of inheriting the main root's.
+    pub fn selects_file_types(&self) -> bool {
+        self.file_type.is_some() || !self.extensions.is_empty() || !self.filenames.is_empty()
+    }
+}
+
+impl FromStr for RootSpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut parts = spec.split(',');
+        let dir = parts.next().unwrap_or_default().trim();
+        if dir.is_empty() || dir.contains('=') {
+            return Err(format!(
+                "Root '{spec}' must start with a folder path, e.g. '../web,alias=frontend'."
+            ));
+        }
+
+        let mut root = RootSpec {
+            dir: PathBuf::from(dir),
+            ..RootSpec::default()
+        };
+        for part in parts {
+            let (key, value) = part
+                .split_once('=')
+                .map(|(key, value)| (key.trim(), value.trim()))
+                .filter(|(_, value)| !value.is_empty())
+                .ok_or_else(|| format!("Expected key=value in root '{spec}', found '{part}'."))?;
+            match key {
+                "alias" => root.alias = Some(value.to_string()),
+                "file-type" => root.file_type = Some(value.to_string()),
+                "ext" => root.extensions.push(value.to_string()),
+                "filename" => root.filenames.push(value.to_string()),
+                "ignore" => root.ignored_folders.push(value.to_string()),
+                _ => {
+                    return Err(format!(
+                        "Unknown key '{key}' in root '{spec}'; expected alias, file-type, ext, filename, or ignore."
+                    ))
+                }
+            }
+        }
+        Ok(root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn specs_parse_a_path_followed_by_settings() {
+        let root: RootSpec =
+            "../web, alias=frontend,file-type=TypeScript,ext=css,ext=scss,ignore=dist"
+                .parse()
+                .expect("spec should parse");
+
+        assert_eq!(
+            root,
+            RootSpec {
+                dir: PathBuf::from("../web"),
+                alias: Some("frontend".to_string()),
+                file_type: Some("TypeScript".to_string()),
+                extensions: vec!["css".to_string(), "scss".to_string()],
+                filenames: Vec::new(),
+                ignored_folders: vec!["dist".to_string()],
+            }
+        );
+        assert!(root.selects_file_types());
+        assert!(!"../api"
+            .parse::<RootSpec>()
+            .expect("path only")
+            .selects_file_types());
+    }
+
+    #[test]
+    fn malformed_specs_are_rejected() {
+        for (spec, message) in [
+            ("", "must start with a folder path"),
+            ("alias=api", "must start with a folder path"),
+            ("../api,alias", "Expected key=value"),
+            ("../api,alias=", "Expected key=value"),
+            ("../api,colour=red", "Unknown key 'colour'"),
+        ] {
+            let error = spec.parse::<RootSpec>().expect_err(spec);
+            assert!(error.contains(message), "{spec}: {error}");
+        }
+    }
+}
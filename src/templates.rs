@@ -0,0 +1,246 @@
+//! # Templates Module
+//!
+//! Renders the whole output document from a user-supplied [minijinja] template (Jinja2 syntax),
+//! for layouts the built-in formats cannot express, such as vendor-specific prompt structures.
+//!
+//! # Template Variables
+//! - `marker`: The wrapper's output marker; include it to let `--skip-previous-outputs` recognise the file.
+//! - `project.name` / `project.root`: Root folder name and path as supplied.
+//! - `files`: One entry per file, in output order, with `index` (1-based), `path`, `language`,
+//!   `size` (bytes on disk), `lines`, `tokens`, and `content`, plus `modified`, `sha256`, and
+//!   `git_commit` when those metadata fields are selected (otherwise `none`).
+//! - `part`: `index` and `count` when the output is split into parts, otherwise `none`.
+//! - `tree`: The rendered directory tree when enabled (first part only), otherwise `none`.
+//! - `diff`: The unified diff when requested and not empty (final part only), otherwise `none`.
+//! - `presets`: Trimmed texts of the selected presets.
+//! - `additional_commands`: Trimmed manual command text (empty when none).
+//! - `combined_additional_commands`: Presets and manual commands joined as the text formats write them.
+//!
+//! # Example
+//! ```jinja
+//! <documents>
+//! {% for file in files %}<document index="{{ file.index }}">
+//! <source>{{ file.path }}</source>
+//! <document_content>{{ file.content }}</document_content>
+//! </document>
+//! {% endfor %}</documents>
+//! {{ combined_additional_commands }}
+//! ```
+//!
+//! # Notes
+//! - Paths already follow the selected `PathStyle`.
+//! - Values are inserted verbatim; no HTML or XML escaping is applied unless the template asks
+//!   for it with the `escape` filter.
+//! - Templates are compiled by [`validate_template`] before the output file is created, so a
+//!   syntax error never leaves a truncated output behind.
+
+use crate::formats::{
+    language_for_path, OutputPart, OutputRenderer, RenderContext, RenderedFile, OUTPUT_MARKER,
+};
+use minijinja::Environment;
+use serde::Serialize;
+use std::io::{self, Error, ErrorKind, Write};
+use std::path::Path;
+
+/// Renders a document from template source once every file has been collected.
+///
+/// Files are buffered because a template may reference the file list anywhere, including
+/// before or after the loop that writes contents.
+#[derive(Debug)]
+pub struct TemplateRenderer {
+    source: String,
+    files: Vec<TemplateFile>,
+}
+
+impl TemplateRenderer {
+    /// Creates a renderer for the given template source.
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            files: Vec::new(),
+        }
+    }
+}
+
+impl OutputRenderer for TemplateRenderer {
+    fn begin(&mut self, _output: &mut dyn Write, _context: &RenderContext<'_>) -> io::Result<()> {
+        self.files.clear();
+        Ok(())
+    }
+
+    fn write_file(&mut self, _output: &mut dyn Write, file: &RenderedFile<'_>) -> io::Result<()> {
+        self.files.push(TemplateFile {
+            index: self.files.len() + 1,
+            path: file.path.to_string(),
+            language: language_for_path(Path::new(file.path)).to_string(),
+            size: file.size(),
+            lines: file.lines(),
+            tokens: file.tokens,
+            modified: file.metadata.modified.clone(),
+            sha256: file.metadata.sha256.clone(),
+            git_commit: file.metadata.git_commit.clone(),
+            content: file.contents.to_string(),
+        });
+        Ok(())
+    }
+
+    fn finish(&mut self, output: &mut dyn Write, context: &RenderContext<'_>) -> io::Result<()> {
+        let options = context.options;
+        let template_context = TemplateContext {
+            marker: OUTPUT_MARKER,
+            project: TemplateProject {
+                name: context.project_name(),
+                root: context.root_dir.to_string_lossy().into_owned(),
+            },
+            part: context.part,
+            tree: options.tree.as_deref(),
+            diff: options.diff.as_deref(),
+            files: &self.files,
+            presets: options
+                .preset_texts
+                .iter()
+                .map(|text| text.trim())
+                .filter(|text| !text.is_empty())
+                .collect(),
+            additional_commands: options.additional_commands.trim(),
+            combined_additional_commands: options.combined_additional_commands(),
+        };
+
+        let environment = template_environment();
+        let rendered = environment
+            .template_from_str(&self.source)
+            .and_then(|template| template.render(&template_context))
+            .map_err(template_error)?;
+        output.write_all(rendered.as_bytes())
+    }
+}
+
+/// Checks that template source compiles, returning a user-facing message if it does not.
+pub fn validate_template(source: &str) -> Result<(), String> {
+    template_environment()
+        .template_from_str(source)
+        .map(|_| ())
+        .map_err(|error| format!("Invalid template: {error:#}"))
+}
+
+#[derive(Debug, Serialize)]
+struct TemplateFile {
+    index: usize,
+    path: String,
+    language: String,
+    size: u64,
+    lines: usize,
+    tokens: usize,
+    modified: Option<String>,
+    sha256: Option<String>,
+    git_commit: Option<String>,
+    content: String,
+}
+
+#[derive(Serialize)]
+struct TemplateProject {
+    name: String,
+    root: String,
+}
+
+#[derive(Serialize)]
+struct TemplateContext<'a> {
+    marker: &'static str,
+    project: TemplateProject,
+    part: Option<OutputPart>,
+    tree: Option<&'a str>,
+    diff: Option<&'a str>,
+    files: &'a [TemplateFile],
+    presets: Vec<&'a str>,
+    additional_commands: &'a str,
+    combined_additional_commands: String,
+}
+
+fn template_environment() -> Environment<'static> {
+    let mut environment = Environment::new();
+    environment.set_keep_trailing_newline(true);
+    environment
+}
+
+fn template_error(error: minijinja::Error) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!("Template error: {error:#}"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::RenderOptions;
+    use crate::metadata::FileMetadata;
+
+    fn render(source: &str, files: &[RenderedFile<'_>]) -> io::Result<String> {
+        let options = RenderOptions {
+            preset_texts: vec![" Review ".to_string()],
+            additional_commands: "Add tests".to_string(),
+            ..RenderOptions::default()
+        };
+        let context = RenderContext {
+            root_dir: Path::new("project"),
+            options: &options,
+            part: None,
+        };
+        let mut renderer = TemplateRenderer::new(source);
+        let mut output = Vec::new();
+        renderer.begin(&mut output, &context)?;
+        for file in files {
+            renderer.write_file(&mut output, file)?;
+        }
+        renderer.finish(&mut output, &context)?;
+        Ok(String::from_utf8(output).expect("UTF-8 output"))
+    }
+
+    #[test]
+    fn template_receives_files_and_commands() -> io::Result<()> {
+        let source = "{{ marker }}\n<documents>{% for file in files %}\
+            <document index=\"{{ file.index }}\" lang=\"{{ file.language }}\" tokens=\"{{ file.tokens }}\">{{ file.path }}:{{ file.content }}</document>\
+            {% endfor %}</documents>\n{{ presets[0] }}|{{ additional_commands }}|{{ project.name }}\n";
+        let files = [
+            RenderedFile {
+                path: "src/main.rs",
+                contents: "fn main() {}",
+                tokens: 3,
+                metadata: &FileMetadata::NONE,
+            },
+            RenderedFile {
+                path: "a.py",
+                contents: "print(1 < 2)",
+                tokens: 3,
+                metadata: &FileMetadata::NONE,
+            },
+        ];
+
+        let output = render(source, &files)?;
+
+        assert_eq!(
+            output,
+            format!(
+                "{OUTPUT_MARKER}\n<documents>\
+                 <document index=\"1\" lang=\"rust\" tokens=\"3\">src/main.rs:fn main() {{}}</document>\
+                 <document index=\"2\" lang=\"python\" tokens=\"3\">a.py:print(1 < 2)</document>\
+                 </documents>\nReview|Add tests|project\n"
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn undefined_filter_is_reported_at_render_time() {
+        let error = render("{{ files | no_such_filter }}", &[]).expect_err("render should fail");
+
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("Template error"));
+    }
+
+    #[test]
+    fn validate_template_rejects_syntax_errors() {
+        assert!(validate_template("{% for file in files %}").is_err());
+        assert!(validate_template("{{ project.name }}").is_ok());
+    }
+}
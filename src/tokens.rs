@@ -0,0 +1,381 @@
+//! # Tokens Module
+//!
+//! Estimates how many model tokens the selected files and the generated output will use, so
+//! outputs can be checked against a model's context limit before they are pasted.
+//!
+//! # Tokenizers
+//! - [`HeuristicTokenizer`] (default): fast, dependency-free estimate based on word and symbol counts.
+//! - [`BpeTokenizer`]: byte-pair encoding driven by a local vocabulary file in the `tiktoken`
+//!   format (`<base64 token> <rank>` per line, e.g. `cl100k_base.tiktoken`). Nothing is downloaded.
+//!
+//! # Notes
+//! - Both tokenizers implement [`Tokenizer`], so other implementations can be plugged in.
+//! - The BPE pre-tokenizer approximates the usual GPT splitting rules (letters, up to three digits,
+//!   punctuation runs, whitespace), so counts are close to, but not always identical with, the
+//!   vendor's tokenizer.
+
+use crate::content::read_text_file;
+use crate::file_ops::{collect_matching_files, ScanOptions};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+/// Counts tokens in text.
+pub trait Tokenizer {
+    /// Short description shown in summaries (e.g., `heuristic`).
+    fn name(&self) -> String;
+
+    /// Returns the number of tokens `text` encodes to.
+    fn count_tokens(&self, text: &str) -> usize;
+}
+
+/// Token count for one written file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileTokens {
+    /// Path as written to the output.
+    pub path: String,
+    pub tokens: usize,
+}
+
+/// Fast estimate: roughly one token per four word characters, one per symbol, one per line break.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicTokenizer;
+
+impl Tokenizer for HeuristicTokenizer {
+    fn name(&self) -> String {
+        "heuristic".to_string()
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        let mut tokens = 0;
+        let mut word_chars = 0usize;
+
+        for c in text.chars() {
+            if c.is_alphanumeric() || c == '_' {
+                word_chars += 1;
+                continue;
+            }
+
+            tokens += word_chars.div_ceil(4);
+            word_chars = 0;
+            if c == '\n' || !c.is_whitespace() {
+                tokens += 1;
+            }
+        }
+
+        tokens + word_chars.div_ceil(4)
+    }
+}
+
+/// Byte-pair encoding tokenizer backed by a `tiktoken`-style vocabulary.
+#[derive(Debug, Clone)]
+pub struct BpeTokenizer {
+    name: String,
+    ranks: HashMap<Vec<u8>, u32>,
+}
+
+impl BpeTokenizer {
+    /// Loads a vocabulary file with one `<base64 token bytes> <rank>` pair per line.
+    ///
+    /// # Errors
+    /// - Any error reading the file.
+    /// - `ErrorKind::InvalidData` naming the first malformed line.
+    pub fn from_file(path: &Path) -> std::io::Result<Self> {
+        let data = fs::read_to_string(path)?;
+        let mut ranks = HashMap::new();
+
+        for (index, line) in data.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parsed = line.split_once(' ').and_then(|(token, rank)| {
+                Some((STANDARD.decode(token).ok()?, rank.trim().parse().ok()?))
+            });
+            let Some((token, rank)) = parsed else {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Invalid vocabulary line {} in '{}'",
+                        index + 1,
+                        path.display()
+                    ),
+                ));
+            };
+            ranks.insert(token, rank);
+        }
+
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(Self::from_ranks(format!("bpe:{name}"), ranks))
+    }
+
+    /// Creates a tokenizer from token bytes and their merge ranks (lower merges first).
+    pub fn from_ranks(name: impl Into<String>, ranks: HashMap<Vec<u8>, u32>) -> Self {
+        Self {
+            name: name.into(),
+            ranks,
+        }
+    }
+
+    /// Counts the tokens one pre-tokenized piece encodes to.
+    fn count_piece(&self, piece: &[u8]) -> usize {
+        if piece.len() <= 1 || self.ranks.contains_key(piece) {
+            return 1;
+        }
+
+        // Boundaries of the current parts; part `i` is `piece[bounds[i]..bounds[i + 1]]`.
+        let mut bounds: Vec<usize> = (0..=piece.len()).collect();
+        loop {
+            let best = bounds
+                .windows(3)
+                .enumerate()
+                .filter_map(|(index, window)| {
+                    self.ranks
+                        .get(&piece[window[0]..window[2]])
+                        .map(|rank| (*rank, index))
+                })
+                .min();
+            match best {
+                Some((_, index)) => {
+                    bounds.remove(index + 1);
+                }
+                None => return bounds.len() - 1,
+            }
+        }
+    }
+}
+
+impl Tokenizer for BpeTokenizer {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        pre_tokenize(text)
+            .into_iter()
+            .map(|piece| self.count_piece(piece.as_bytes()))
+            .sum()
+    }
+}
+
+/// Loads the BPE tokenizer for `vocab_path`, or the heuristic tokenizer when no path is given.
+pub fn load_tokenizer(vocab_path: Option<&Path>) -> std::io::Result<Box<dyn Tokenizer>> {
+    match vocab_path {
+        Some(path) => {
+            let tokenizer = BpeTokenizer::from_file(path).map_err(|error| {
+                Error::new(
+                    error.kind(),
+                    format!("Could not load token vocabulary: {error}"),
+                )
+            })?;
+            Ok(Box::new(tokenizer))
+        }
+        None => Ok(Box::new(HeuristicTokenizer)),
+    }
+}
+
+/// Files and tokens that a scan would currently produce.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenEstimate {
+    pub files: usize,
+    pub tokens: usize,
+}
+
+/// Estimates the tokens of every readable file a scan would include, without writing output.
+pub fn estimate_tokens(
+    dir: &Path,
+    options: &ScanOptions,
+    tokenizer: &dyn Tokenizer,
+) -> std::io::Result<TokenEstimate> {
+    let selection = collect_matching_files(dir, options)?;
+    let mut estimate = TokenEstimate::default();
+
+    for path in &selection.files {
+        if let Ok(text) = read_text_file(path, options.max_file_size, &options.fallback_encodings) {
+            estimate.files += 1;
+            estimate.tokens += tokenizer.count_tokens(&text.contents);
+        }
+    }
+
+    Ok(estimate)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Letter,
+    Digit,
+    Space,
+    Newline,
+    Symbol,
+}
+
+fn char_class(c: char) -> CharClass {
+    if c.is_alphabetic() {
+        CharClass::Letter
+    } else if c.is_numeric() {
+        CharClass::Digit
+    } else if c == '\n' || c == '\r' {
+        CharClass::Newline
+    } else if c.is_whitespace() {
+        CharClass::Space
+    } else {
+        CharClass::Symbol
+    }
+}
+
+/// Splits text into the pieces BPE merges operate on.
+///
+/// Letters (with at most one leading space or symbol), groups of up to three digits, symbol runs
+/// (with an optional leading space and trailing line breaks), and whitespace runs each form one
+/// piece. A whitespace run leaves its last space to the following word.
+fn pre_tokenize(text: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let class_at = |index: usize| chars.get(index).map(|&(_, c)| char_class(c));
+    let take_while = |mut index: usize, class: CharClass| {
+        while class_at(index) == Some(class) {
+            index += 1;
+        }
+        index
+    };
+
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let current = char_class(chars[start].1);
+        let next = class_at(start + 1);
+        let end = match current {
+            CharClass::Letter => take_while(start, CharClass::Letter),
+            CharClass::Space | CharClass::Symbol if next == Some(CharClass::Letter) => {
+                take_while(start + 1, CharClass::Letter)
+            }
+            CharClass::Digit => {
+                let mut end = start;
+                while end - start < 3 && class_at(end) == Some(CharClass::Digit) {
+                    end += 1;
+                }
+                end
+            }
+            CharClass::Space | CharClass::Symbol => {
+                let symbols_start =
+                    if current == CharClass::Space && next == Some(CharClass::Symbol) {
+                        start + 1
+                    } else {
+                        start
+                    };
+                if class_at(symbols_start) == Some(CharClass::Symbol) {
+                    let symbols_end = take_while(symbols_start, CharClass::Symbol);
+                    take_while(symbols_end, CharClass::Newline)
+                } else {
+                    let mut end = start;
+                    while matches!(class_at(end), Some(CharClass::Space | CharClass::Newline)) {
+                        end += 1;
+                    }
+                    let followed_by_text = class_at(end).is_some();
+                    if followed_by_text
+                        && end - start > 1
+                        && class_at(end - 1) == Some(CharClass::Space)
+                    {
+                        end - 1
+                    } else {
+                        end
+                    }
+                }
+            }
+            CharClass::Newline => {
+                let mut end = start;
+                while matches!(class_at(end), Some(CharClass::Space | CharClass::Newline)) {
+                    end += 1;
+                }
+                if class_at(end).is_some()
+                    && end - start > 1
+                    && class_at(end - 1) == Some(CharClass::Space)
+                {
+                    end - 1
+                } else {
+                    end
+                }
+            }
+        };
+
+        let byte_start = chars[start].0;
+        let byte_end = chars.get(end).map_or(text.len(), |&(offset, _)| offset);
+        pieces.push(&text[byte_start..byte_end]);
+        start = end;
+    }
+
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn vocab(tokens: &[&str]) -> HashMap<Vec<u8>, u32> {
+        tokens
+            .iter()
+            .enumerate()
+            .map(|(rank, token)| (token.as_bytes().to_vec(), rank as u32))
+            .collect()
+    }
+
+    #[test]
+    fn heuristic_counts_words_symbols_and_lines() {
+        let tokenizer = HeuristicTokenizer;
+
+        assert_eq!(tokenizer.count_tokens(""), 0);
+        // "fn" (1) "main" (1) "(" ")" "{" "}" (4) newline (1)
+        assert_eq!(tokenizer.count_tokens("fn main() {}\n"), 7);
+        // nine word characters round up to three tokens
+        assert_eq!(tokenizer.count_tokens("abcdefghi"), 3);
+    }
+
+    #[test]
+    fn pre_tokenizer_keeps_leading_space_with_words() {
+        assert_eq!(
+            pre_tokenize("let  value = 12345;\n"),
+            vec!["let", " ", " value", " =", " ", "123", "45", ";\n"]
+        );
+    }
+
+    #[test]
+    fn bpe_merges_by_lowest_rank() {
+        let tokenizer = BpeTokenizer::from_ranks("test", vocab(&["ab", "cd", "abcd", " x"]));
+
+        assert_eq!(tokenizer.count_tokens("abcd"), 1);
+        assert_eq!(tokenizer.count_tokens("abcde"), 2);
+        assert_eq!(tokenizer.count_tokens("dcba"), 4);
+        assert_eq!(tokenizer.count_tokens("abcd x"), 2);
+    }
+
+    #[test]
+    fn bpe_vocabulary_loads_from_tiktoken_file() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let path = temp.path().join("tiny.tiktoken");
+        fs::write(&path, "YWI= 0\nY2Q= 1\nYWJjZA== 2\n")?;
+
+        let tokenizer = load_tokenizer(Some(&path))?;
+
+        assert_eq!(tokenizer.name(), "bpe:tiny.tiktoken");
+        assert_eq!(tokenizer.count_tokens("abcd"), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn malformed_vocabulary_reports_line() -> std::io::Result<()> {
+        let temp = tempdir()?;
+        let path = temp.path().join("broken.tiktoken");
+        fs::write(&path, "YWI= 0\nnot-a-rank\n")?;
+
+        let error = BpeTokenizer::from_file(&path).expect_err("line 2 is invalid");
+
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(error.to_string().contains("line 2"));
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+//! # Directory Tree Module
+//!
+//! Renders an ASCII overview of the scanned project, written before the file contents so a model
+//! sees the project layout up front.
+//!
+//! # Output Format
+//! ```text
+//! app/
+//! |-- src/
+//! |   |-- generated/ (4 files not shown)
+//! |   |-- [+] lib.rs
+//! |   `-- [~] main.rs
+//! |-- [+] Cargo.toml
+//! `-- [-] notes.txt
+//! ```
+//!
+//! # Marks
+//! - `[+]` included in full, `[~]` truncated to fit the budget, `[-]` exists but filtered out
+//!   (extension, include/exclude glob, unreadable contents, or budget).
+//! - Every mark has the same width, so the tree's size does not depend on budget decisions.
+//!
+//! # Notes
+//! - The tree follows the scan's rules: ignored, hidden, and gitignored folders, gitignored files,
+//!   and earlier outputs are left out entirely.
+//! - Folders appear only when they contain a listed file. Sub-folders of a non-recursive scan are
+//!   listed as `(not scanned)`.
+//! - Folders come before files at every level; both are sorted case-insensitively.
+//! - Folders deeper than [`TreeOptions::max_depth`] are collapsed to a count of their files.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Legend explaining the marks, written next to the tree by every format.
+pub const TREE_LEGEND: &str = "[+] included, [~] truncated, [-] filtered out";
+
+/// Whether and how deeply the directory tree section is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeOptions {
+    /// Writes the tree section before the file contents.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Number of folder levels below the root to expand; deeper folders are collapsed.
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+}
+
+impl TreeOptions {
+    /// Returns `true` when the tree is disabled with no depth limit, so profiles can omit it.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// How one file is represented in the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeMark {
+    Included,
+    Truncated,
+    Filtered,
+}
+
+impl TreeMark {
+    fn symbol(self) -> &'static str {
+        match self {
+            TreeMark::Included => "[+]",
+            TreeMark::Truncated => "[~]",
+            TreeMark::Filtered => "[-]",
+        }
+    }
+}
+
+/// Files and unscanned folders of one project, collected before rendering.
+#[derive(Debug, Default)]
+pub struct DirectoryTree {
+    root: Node,
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    children: BTreeMap<String, Node>,
+    mark: Option<TreeMark>,
+    unscanned: bool,
+}
+
+impl Node {
+    fn is_folder(&self) -> bool {
+        self.mark.is_none()
+    }
+
+    fn file_count(&self) -> usize {
+        if !self.is_folder() {
+            return 1;
+        }
+        self.children.values().map(Node::file_count).sum()
+    }
+
+    fn sorted_children(&self) -> Vec<(&String, &Node)> {
+        let mut children: Vec<(&String, &Node)> = self.children.iter().collect();
+        children.sort_by(|(a_name, a), (b_name, b)| {
+            b.is_folder()
+                .cmp(&a.is_folder())
+                .then_with(|| a_name.to_lowercase().cmp(&b_name.to_lowercase()))
+                .then_with(|| a_name.cmp(b_name))
+        });
+        children
+    }
+}
+
+impl DirectoryTree {
+    /// Adds (or re-marks) a file, given its path relative to the scanned root.
+    pub fn set_file(&mut self, relative_path: &Path, mark: TreeMark) {
+        self.node_mut(relative_path).mark = Some(mark);
+    }
+
+    /// Adds a folder that exists but was not traversed.
+    pub fn add_unscanned_folder(&mut self, relative_path: &Path) {
+        self.node_mut(relative_path).unscanned = true;
+    }
+
+    fn node_mut(&mut self, relative_path: &Path) -> &mut Node {
+        relative_path
+            .components()
+            .fold(&mut self.root, |node, component| {
+                node.children
+                    .entry(component.as_os_str().to_string_lossy().into_owned())
+                    .or_default()
+            })
+    }
+
+    /// Renders the tree under a `root_name/` line, expanding at most `max_depth` folder levels.
+    pub fn render(&self, root_name: &str, max_depth: Option<usize>) -> String {
+        let mut output = String::new();
+        output.push_str(&folder_line(root_name, &self.root, 0, max_depth));
+        output.push('\n');
+        if expands(0, max_depth) {
+            render_children(&self.root, "", 1, max_depth, &mut output);
+        }
+        output
+    }
+}
+
+fn expands(depth: usize, max_depth: Option<usize>) -> bool {
+    max_depth.is_none_or(|max_depth| depth < max_depth)
+}
+
+fn folder_line(name: &str, node: &Node, depth: usize, max_depth: Option<usize>) -> String {
+    if node.unscanned && node.children.is_empty() {
+        return format!("{name}/ (not scanned)");
+    }
+    if expands(depth, max_depth) {
+        return format!("{name}/");
+    }
+    match node.file_count() {
+        1 => format!("{name}/ (1 file not shown)"),
+        count => format!("{name}/ ({count} files not shown)"),
+    }
+}
+
+fn render_children(
+    node: &Node,
+    prefix: &str,
+    depth: usize,
+    max_depth: Option<usize>,
+    output: &mut String,
+) {
+    let children = node.sorted_children();
+    let last_index = children.len().saturating_sub(1);
+    for (index, (name, child)) in children.into_iter().enumerate() {
+        let is_last = index == last_index;
+        output.push_str(prefix);
+        output.push_str(if is_last { "`-- " } else { "|-- " });
+        match child.mark {
+            Some(mark) => output.push_str(&format!("{} {name}", mark.symbol())),
+            None => output.push_str(&folder_line(name, child, depth, max_depth)),
+        }
+        output.push('\n');
+
+        if child.is_folder() && expands(depth, max_depth) {
+            let child_prefix = format!("{prefix}{}", if is_last { "    " } else { "|   " });
+            render_children(child, &child_prefix, depth + 1, max_depth, output);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> DirectoryTree {
+        let mut tree = DirectoryTree::default();
+        tree.set_file(Path::new("Cargo.toml"), TreeMark::Included);
+        tree.set_file(Path::new("notes.txt"), TreeMark::Filtered);
+        tree.set_file(Path::new("src/main.rs"), TreeMark::Included);
+        tree.set_file(Path::new("src/lib.rs"), TreeMark::Included);
+        tree.set_file(Path::new("src/gen/a.rs"), TreeMark::Filtered);
+        tree.set_file(Path::new("src/gen/b.rs"), TreeMark::Filtered);
+        tree.add_unscanned_folder(Path::new("docs"));
+        tree
+    }
+
+    #[test]
+    fn renders_folders_first_with_marks() {
+        let mut tree = sample();
+        tree.set_file(Path::new("src/main.rs"), TreeMark::Truncated);
+
+        assert_eq!(
+            tree.render("app", None),
+            "app/\n\
+             |-- docs/ (not scanned)\n\
+             |-- src/\n\
+             |   |-- gen/\n\
+             |   |   |-- [-] a.rs\n\
+             |   |   `-- [-] b.rs\n\
+             |   |-- [+] lib.rs\n\
+             |   `-- [~] main.rs\n\
+             |-- [+] Cargo.toml\n\
+             `-- [-] notes.txt\n"
+        );
+    }
+
+    #[test]
+    fn depth_limit_collapses_deeper_folders() {
+        let tree = sample();
+
+        assert_eq!(
+            tree.render("app", Some(1)),
+            "app/\n\
+             |-- docs/ (not scanned)\n\
+             |-- src/ (4 files not shown)\n\
+             |-- [+] Cargo.toml\n\
+             `-- [-] notes.txt\n"
+        );
+        assert_eq!(tree.render("app", Some(0)), "app/ (6 files not shown)\n");
+    }
+}
@@ -80,12 +80,11 @@ pub fn copy_to_clipboard(file_path: &str) -> io::Result<()> {
 pub fn copy_to_clipboard(file_path: &str) -> io::Result<()> {
     let file_contents = read_to_string(file_path)?;
 
-    let mut clipboard =
-        Clipboard::new().map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+    let mut clipboard = Clipboard::new().map_err(|e| io::Error::other(e.to_string()))?;
 
     clipboard
         .set_text(file_contents)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        .map_err(|e| io::Error::other(e.to_string()))?;
 
     Ok(())
 }